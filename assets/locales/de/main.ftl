@@ -0,0 +1,243 @@
+## Antworten
+
+register-done = Anwendungsbefehle registriert
+
+codename-title = Codename
+codename-generated =
+    Dein generierter Codename lautet:
+    {" "}**{ $codename }!**
+codename-given =
+    Der Codename von { $mention } lautet:
+    {" "}**{ $codename }!**
+codename-batch-header = Deine generierten Codenamen sind:
+codename-favorite =
+    Dein Lieblings-Codename ist:
+    {" "}**{ $codename }!**
+codename-batch-title = Codename-Ideen
+codename-batch-footer = 🎲 würfelt einen Eintrag neu, ⭐ wählt deinen Favoriten
+codename-batch-favorite-title = Lieblings-Codename: { $codename }
+codename-batch-favorite-footer = Als endgültige Wahl ausgewählt
+codename-batch-reroll-all = Alle neu würfeln
+codename-seeded-footer = Aus der Nachricht von { $name } erzeugt
+codename-reloaded = Codename-Daten neu geladen: { $adjectives } Adjektive, { $animals } Tiere
+codename-reload-failed = Neuladen fehlgeschlagen, die bisherigen Codename-Daten bleiben aktiv: { $error }
+codename-unknown = **{ $codename }** ist kein Codename, den ich generiert haben könnte
+codename-claimed = **{ $codename }** gehört jetzt { $owner }
+codename-claimed-for = **{ $codename }** gehört jetzt { $owner }: { $description }
+codename-already-claimed = **{ $codename }** ist auf diesem Server bereits vergeben
+codename-released = **{ $codename }** wurde freigegeben
+codename-not-yours = Du hast keinen Anspruch auf **{ $codename }**, den du freigeben könntest
+codename-list-empty = Auf diesem Server wurden noch keine Codenamen beansprucht
+codename-list-header = Beanspruchte Codenamen ({ $count }):
+
+avatar-title = Avatar von { $name }
+avatar-no-banner = { $name } hat kein Profilbanner
+
+userinfo-title = Über { $name }
+userinfo-created = Konto erstellt
+userinfo-joined = Server beigetreten
+userinfo-roles = Rollen
+userinfo-roles-more = +{ $count } weitere
+userinfo-commands-run = Ausgeführte Befehle
+userinfo-date-age = { $date } (vor { $age })
+age-less-than-a-day = weniger als einem Tag
+age-years = { $count ->
+    [one] einem Jahr
+   *[other] { $count } Jahren
+}
+age-months = { $count ->
+    [one] einem Monat
+   *[other] { $count } Monaten
+}
+age-days = { $count ->
+    [one] einem Tag
+   *[other] { $count } Tagen
+}
+
+permission-denied = Du darfst `/{ $command }` hier nicht verwenden
+perms-list-title = Berechtigungsregeln
+perms-list-empty = Auf diesem Server sind keine Berechtigungsregeln gesetzt; jeder Befehl steht allen offen
+perms-allow = erlauben
+perms-deny = verbieten
+perms-now-allowed = `{ $command }` ist jetzt für { $target } erlaubt
+perms-now-denied = `{ $command }` ist jetzt für { $target } verboten
+perms-removed = Die `{ $command }`-Regel für { $target } wurde entfernt
+perms-no-rule = Es gibt keine `{ $command }`-Regel für { $target }
+perms-pick-one-target = Wähle genau eines aus: Rolle, Benutzer oder Kanal
+perms-unknown-command = Es gibt keinen Befehl namens `{ $command }`
+
+ratelimit-user = Langsam! Du verwendest `/{ $command }` zu schnell, versuche es in { $seconds } s erneut
+ratelimit-channel = Langsam! Dieser Kanal verwendet `/{ $command }` zu schnell, versuche es in { $seconds } s erneut
+ratelimit-guild = Langsam! Dieser Server verwendet `/{ $command }` zu schnell, versuche es in { $seconds } s erneut
+ratelimits-title = Ratenlimits
+ratelimits-none = Es sind keine Ratenlimits eingerichtet
+ratelimits-rule = `{ $command }` { $scope }: { $capacity } pro { $seconds } s
+ratelimits-throttled-title = Gebremst in den letzten 7 Tagen
+ratelimits-nothing-throttled = Nichts wurde gebremst
+ratelimits-throttled = `{ $command }` ({ $scope }): { $count }
+
+config-title = Servereinstellungen
+config-updated = `{ $key }` ist jetzt `{ $value }`
+config-reset = `{ $key }` ist wieder auf dem Standardwert `{ $value }`
+config-unknown-key = Es gibt keine Einstellung namens `{ $key }`, versuche eine von: { $keys }
+config-invalid-value = `{ $value }` ist kein gültiger Wert für `{ $key }`, erwartet wird einer von: { $allowed }
+setting-feed_visible = Befehle dieses Servers im Web-Feed anzeigen
+setting-ephemeral_replies = nur der aufrufende Benutzer sieht die Antworten
+setting-codename_style = Schreibweise der Codenamen
+setting-locale = Sprache der Antworten (auto folgt der Discord-Sprache jedes Benutzers)
+setting-history_logging = Befehle dieses Servers im Verlauf speichern
+setting-history_retention_days = wie viele Tage der Verlauf dieses Servers aufbewahrt wird (default nutzt die Grenze des Bots)
+
+privacy-opted-out = Du hast widersprochen: Deine Befehle werden nicht mehr protokolliert und der Live-Feed zeigt dich als Anonym. Mit `/privacy delete` löschst du, was vorher protokolliert wurde
+privacy-already-opted-out = Du hast bereits widersprochen
+privacy-opted-in = Dein Widerspruch ist aufgehoben: Deine Befehle werden wieder protokolliert und im Live-Feed angezeigt
+privacy-not-opted-out = Du hast nicht widersprochen
+privacy-export-dm = Hier ist alles, was ich über dich protokolliert habe ({ $count ->
+        [one] 1 Befehl
+       *[other] { $count } Befehle
+    })
+privacy-export-sent = Ich habe dir deine Daten per Direktnachricht geschickt
+privacy-export-failed = Ich konnte dir keine Direktnachricht schicken, bitte erlaube Direktnachrichten von diesem Server und versuche es erneut
+privacy-deleted = { $count ->
+        [one] 1 protokollierter Befehl wurde gelöscht
+       *[other] { $count } protokollierte Befehle wurden gelöscht
+    }
+
+history-pruned = { $total ->
+        [one] 1 Verlaufseintrag entfernt
+       *[other] { $total } Verlaufseinträge entfernt
+    } ({ $expired } abgelaufen, { $trimmed } über der Zeilengrenze)
+
+history-exported = { $count ->
+        [one] 1 Verlaufseintrag exportiert
+       *[other] { $count } Verlaufseinträge exportiert
+    }
+history-export-too-large = Der Export mit { $count } Einträgen ist zu groß für einen Anhang; nutze stattdessen `discordbot history export` oder den REST-Endpunkt
+
+stats-title = Nutzung { $days ->
+        [one] am letzten Tag
+       *[other] in den letzten { $days } Tagen
+    }
+stats-empty = In diesem Zeitraum wurden hier keine Befehle ausgeführt
+stats-total = { $count ->
+        [one] 1 Befehl ausgeführt
+       *[other] { $count } Befehle ausgeführt
+    }
+stats-peak-day = Aktivster Tag: { $date } ({ $count })
+stats-top-commands = Meistgenutzte Befehle
+stats-top-users = Aktivste Benutzer
+stats-busiest-hours = Aktivste Stunden (UTC)
+leaderboard-title = Am aktivsten { $days ->
+        [one] am letzten Tag
+       *[other] in den letzten { $days } Tagen
+    }
+leaderboard-entry = **{ $name }**: { $count ->
+        [one] 1 Befehl
+       *[other] { $count } Befehle
+    }
+
+## Befehlsbeschreibungen, Kontextmenü-Namen und Optionen
+
+command-register-description = Registriert die Anwendungsbefehle bei Discord (nur Besitzer)
+command-codename-description = Codename-Generator und serverweites Codename-Register
+command-codename-generate-description = Generiert einen zufälligen Codenamen
+command-codename-generate-unique-name = eindeutig
+command-codename-generate-unique-description = Neu würfeln, bis der Codename auf diesem Server nicht vergeben ist
+command-codename-generate-count-name = anzahl
+command-codename-generate-count-description = Wie viele Codenamen vorgeschlagen werden (bis zu 10)
+command-codename-claim-description = Beansprucht einen generierten Codenamen auf diesem Server
+command-codename-claim-codename-name = codename
+command-codename-claim-codename-description = Der generierte Codename, der beansprucht werden soll
+command-codename-claim-description-name = beschreibung
+command-codename-claim-description-description = Wofür der Codename verwendet wird
+command-codename-claim-owner-name = besitzer
+command-codename-claim-owner-description = Wem der Codename gehört (standardmäßig dir)
+command-codename-list-description = Listet die auf diesem Server beanspruchten Codenamen auf
+command-codename-release-description = Gibt einen beanspruchten Codenamen frei (deinen eigenen, oder jeden mit Server verwalten)
+command-codename-release-codename-name = codename
+command-codename-release-codename-description = Der beanspruchte Codename, der freigegeben werden soll
+command-reload-description = Lädt die Codename-Datei ohne Neustart neu (nur Besitzer)
+command-avatar-description = Zeigt den Avatar des angegebenen Benutzers
+command-avatar-user-name = benutzer
+command-avatar-user-description = Wessen Avatar angezeigt werden soll
+command-avatar-mention-name = erwähnen
+command-avatar-mention-description = Den Benutzer in der Antwort erwähnen
+command-avatar-size-name = größe
+command-avatar-size-description = Bildgröße in Pixeln (16-4096, auf eine Zweierpotenz abgerundet)
+command-avatar-format-name = format
+command-avatar-format-description = Bildformat
+command-avatar-source-name = quelle
+command-avatar-source-description = Globaler Avatar oder Avatar auf diesem Server
+command-avatar-banner-name = banner
+command-avatar-banner-description = Zusätzlich das Profilbanner des Benutzers anzeigen
+command-userinfo-description = Zeigt Kontoalter, Beitrittsdatum, Rollen und Befehlsnutzung eines Benutzers
+command-userinfo-user-name = benutzer
+command-userinfo-user-description = Wer angezeigt werden soll (standardmäßig du)
+command-show-avatar-name = Avatar anzeigen
+command-user-info-name = Benutzerinfo
+command-give-codename-name = Codename vergeben
+command-codename-from-this-message-name = Codename aus dieser Nachricht
+command-perms-description = Befehlsberechtigungen pro Server (Server verwalten)
+command-perms-allow-description = Erlaubt einer Rolle, einem Benutzer oder Kanal einen Befehl
+command-perms-allow-command-name = befehl
+command-perms-allow-command-description = Befehlsname, z. B. "codename claim", oder * für alle Befehle
+command-perms-allow-role-name = rolle
+command-perms-allow-role-description = Rolle, die erlaubt wird
+command-perms-allow-user-name = benutzer
+command-perms-allow-user-description = Benutzer, der erlaubt wird
+command-perms-allow-channel-name = kanal
+command-perms-allow-channel-description = Kanal, in dem der Befehl verwendet werden darf
+command-perms-deny-description = Verbietet einer Rolle, einem Benutzer oder Kanal einen Befehl
+command-perms-deny-command-name = befehl
+command-perms-deny-command-description = Befehlsname, z. B. "codename claim", oder * für alle Befehle
+command-perms-deny-role-name = rolle
+command-perms-deny-role-description = Rolle, die verboten wird
+command-perms-deny-user-name = benutzer
+command-perms-deny-user-description = Benutzer, der verboten wird
+command-perms-deny-channel-name = kanal
+command-perms-deny-channel-description = Kanal, in dem der Befehl nicht verwendet werden darf
+command-perms-remove-description = Entfernt die Regel für eine Rolle, einen Benutzer oder Kanal
+command-perms-remove-command-name = befehl
+command-perms-remove-command-description = Befehlsname, für den die Regel gesetzt wurde
+command-perms-remove-role-name = rolle
+command-perms-remove-role-description = Rolle der Regel
+command-perms-remove-user-name = benutzer
+command-perms-remove-user-description = Benutzer der Regel
+command-perms-remove-channel-name = kanal
+command-perms-remove-channel-description = Kanal der Regel
+command-perms-list-description = Listet die Berechtigungsregeln dieses Servers auf
+command-ratelimits-description = Zeigt die Ratenlimits und wie oft sie gegriffen haben (nur Besitzer)
+command-config-description = Bot-Einstellungen pro Server (Server verwalten)
+command-config-get-description = Zeigt eine oder alle Einstellungen
+command-config-get-key-name = schlüssel
+command-config-get-key-description = Anzuzeigende Einstellung (standardmäßig alle)
+command-config-set-description = Ändert eine Einstellung
+command-config-set-key-name = schlüssel
+command-config-set-key-description = Zu ändernde Einstellung
+command-config-set-value-name = wert
+command-config-set-value-description = Neuer Wert
+command-config-reset-description = Setzt eine Einstellung auf den Standardwert zurück
+command-config-reset-key-name = schlüssel
+command-config-reset-key-description = Zurückzusetzende Einstellung
+command-privacy-description = Legt fest, was der Bot über dich speichert
+command-privacy-optout-description = Beendet die Protokollierung deiner Befehle und verbirgt deinen Namen im Live-Feed
+command-privacy-optin-description = Nimmt die Protokollierung deiner Befehle und die Anzeige im Live-Feed wieder auf
+command-privacy-export-description = Schickt dir eine JSON-Datei mit allem, was der Bot über dich protokolliert hat
+command-privacy-delete-description = Löscht alle Befehle, die der Bot für dich protokolliert hat
+command-history-description = Wartung des Befehlsverlaufs (für Besitzer)
+command-history-prune-description = Wendet die Aufbewahrungsregeln für den Verlauf jetzt an und meldet die entfernten Einträge (für Besitzer)
+command-history-export-description = Exportiert den Befehlsverlauf als CSV- oder JSON-Lines-Datei (für Besitzer)
+command-history-export-format-name = format
+command-history-export-format-description = Dateiformat (Standard: csv)
+command-history-export-days-name = tage
+command-history-export-days-description = Nur die letzten so vielen Tage
+command-history-export-user-name = benutzer
+command-history-export-user-description = Nur die Befehle dieses Benutzers
+command-history-export-command-name = befehl
+command-history-export-command-description = Nur dieser Befehl und seine Unterbefehle
+command-stats-description = Zeigt die meistgenutzten Befehle, die aktivsten Benutzer und die tägliche Nutzung dieses Servers
+command-stats-days-name = tage
+command-stats-days-description = Wie viele Tage zurück (Standard: 30)
+command-leaderboard-description = Rangliste der aktivsten Benutzer dieses Servers
+command-leaderboard-days-name = tage
+command-leaderboard-days-description = Wie viele Tage zurück (Standard: 30)
@@ -0,0 +1,243 @@
+## Replies
+
+register-done = Registered application commands
+
+codename-title = Codename
+codename-generated =
+    Your generated codename is:
+    {" "}**{ $codename }!**
+codename-given =
+    { $mention }'s codename is:
+    {" "}**{ $codename }!**
+codename-batch-header = Your generated codenames are:
+codename-favorite =
+    Your favorite codename is:
+    {" "}**{ $codename }!**
+codename-batch-title = Codename ideas
+codename-batch-footer = 🎲 re-rolls an entry, ⭐ picks your favorite
+codename-batch-favorite-title = Favorite codename: { $codename }
+codename-batch-favorite-footer = Picked as the final choice
+codename-batch-reroll-all = Re-roll all
+codename-seeded-footer = Seeded from { $name }'s message
+codename-reloaded = Reloaded codename data: { $adjectives } adjectives, { $animals } animals
+codename-reload-failed = Reload failed, keeping the previous codename data: { $error }
+codename-unknown = **{ $codename }** is not a codename I could have generated
+codename-claimed = **{ $codename }** is now claimed by { $owner }
+codename-claimed-for = **{ $codename }** is now claimed by { $owner }: { $description }
+codename-already-claimed = **{ $codename }** is already claimed in this server
+codename-released = **{ $codename }** has been released
+codename-not-yours = You have no claim on **{ $codename }** to release
+codename-list-empty = No codenames have been claimed in this server yet
+codename-list-header = Claimed codenames ({ $count }):
+
+avatar-title = { $name }'s avatar
+avatar-no-banner = { $name } has no profile banner
+
+userinfo-title = About { $name }
+userinfo-created = Account created
+userinfo-joined = Joined server
+userinfo-roles = Roles
+userinfo-roles-more = +{ $count } more
+userinfo-commands-run = Commands run
+userinfo-date-age = { $date } ({ $age } ago)
+age-less-than-a-day = less than a day
+age-years = { $count ->
+    [one] 1 year
+   *[other] { $count } years
+}
+age-months = { $count ->
+    [one] 1 month
+   *[other] { $count } months
+}
+age-days = { $count ->
+    [one] 1 day
+   *[other] { $count } days
+}
+
+permission-denied = You are not allowed to use `/{ $command }` here
+perms-list-title = Permission rules
+perms-list-empty = No permission rules are set in this server; every command is open to everyone
+perms-allow = allow
+perms-deny = deny
+perms-now-allowed = `{ $command }` is now allowed for { $target }
+perms-now-denied = `{ $command }` is now denied for { $target }
+perms-removed = Removed the `{ $command }` rule for { $target }
+perms-no-rule = There is no `{ $command }` rule for { $target }
+perms-pick-one-target = Pick exactly one of role, user or channel
+perms-unknown-command = There is no command called `{ $command }`
+
+ratelimit-user = Slow down! You're using `/{ $command }` too quickly, try again in { $seconds }s
+ratelimit-channel = Slow down! This channel is using `/{ $command }` too quickly, try again in { $seconds }s
+ratelimit-guild = Slow down! This server is using `/{ $command }` too quickly, try again in { $seconds }s
+ratelimits-title = Rate limits
+ratelimits-none = No rate limits are configured
+ratelimits-rule = `{ $command }` { $scope }: { $capacity } per { $seconds }s
+ratelimits-throttled-title = Throttled in the last 7 days
+ratelimits-nothing-throttled = Nothing was throttled
+ratelimits-throttled = `{ $command }` ({ $scope }): { $count }
+
+config-title = Server settings
+config-updated = `{ $key }` is now `{ $value }`
+config-reset = `{ $key }` is back to its default, `{ $value }`
+config-unknown-key = There is no setting called `{ $key }`, try one of: { $keys }
+config-invalid-value = `{ $value }` is not a valid value for `{ $key }`, expected one of: { $allowed }
+setting-feed_visible = show this server's commands in the web feed
+setting-ephemeral_replies = only the invoking user sees command replies
+setting-codename_style = how codenames are written
+setting-locale = language of the bot's replies (auto follows each user's Discord language)
+setting-history_logging = record this server's commands in the history
+setting-history_retention_days = days this server's history is kept (default uses the bot-wide limit)
+
+privacy-opted-out = You're opted out: your commands are no longer logged and the live feed shows you as Anonymous. Use `/privacy delete` to erase what was logged before
+privacy-already-opted-out = You're already opted out
+privacy-opted-in = You're opted back in: your commands are logged and shown in the live feed again
+privacy-not-opted-out = You're not opted out
+privacy-export-dm = Here is everything I have logged about you ({ $count ->
+        [one] 1 command
+       *[other] { $count } commands
+    })
+privacy-export-sent = I sent you a DM with your data
+privacy-export-failed = I couldn't DM you, please allow direct messages from this server and try again
+privacy-deleted = Deleted { $count ->
+        [one] 1 logged command
+       *[other] { $count } logged commands
+    }
+
+history-pruned = Removed { $total ->
+        [one] 1 history row
+       *[other] { $total } history rows
+    } ({ $expired } expired, { $trimmed } over the row limit)
+
+history-exported = Exported { $count ->
+        [one] 1 history row
+       *[other] { $count } history rows
+    }
+history-export-too-large = The export of { $count } rows is too large to attach; use `discordbot history export` or the REST endpoint instead
+
+stats-title = Usage over the last { $days ->
+        [one] day
+       *[other] { $days } days
+    }
+stats-empty = No commands were run here in that time
+stats-total = { $count ->
+        [one] 1 command run
+       *[other] { $count } commands run
+    }
+stats-peak-day = Busiest day: { $date } ({ $count })
+stats-top-commands = Top commands
+stats-top-users = Top users
+stats-busiest-hours = Busiest hours (UTC)
+leaderboard-title = Most active over the last { $days ->
+        [one] day
+       *[other] { $days } days
+    }
+leaderboard-entry = **{ $name }**: { $count ->
+        [one] 1 command
+       *[other] { $count } commands
+    }
+
+## Command descriptions, context menu names and options
+
+command-register-description = Registers application commands on discord (owner use)
+command-codename-description = Codename generation and the per-server codename registry
+command-codename-generate-description = Generates a random codename
+command-codename-generate-unique-name = unique
+command-codename-generate-unique-description = Re-roll until the codename is not claimed in this server
+command-codename-generate-count-name = count
+command-codename-generate-count-description = How many codenames to brainstorm (up to 10)
+command-codename-claim-description = Claims a generated codename in this server so nobody else can use it
+command-codename-claim-codename-name = codename
+command-codename-claim-codename-description = The generated codename to claim
+command-codename-claim-description-name = description
+command-codename-claim-description-description = What the codename will be used for
+command-codename-claim-owner-name = owner
+command-codename-claim-owner-description = Who owns the codename (defaults to you)
+command-codename-list-description = Lists the codenames claimed in this server
+command-codename-release-description = Releases a claimed codename (your own, or any with Manage Server)
+command-codename-release-codename-name = codename
+command-codename-release-codename-description = The claimed codename to release
+command-reload-description = Reloads the codename data file without restarting the bot (owner use)
+command-avatar-description = Displays the avatar of the specified user
+command-avatar-user-name = user
+command-avatar-user-description = Whose avatar to show
+command-avatar-mention-name = mention
+command-avatar-mention-description = Mention the user in the reply
+command-avatar-size-name = size
+command-avatar-size-description = Image size in pixels (16-4096, rounded to a power of two)
+command-avatar-format-name = format
+command-avatar-format-description = Image format
+command-avatar-source-name = source
+command-avatar-source-description = Global avatar or this server's avatar
+command-avatar-banner-name = banner
+command-avatar-banner-description = Also show the user's profile banner
+command-userinfo-description = Shows account age, server join date, roles and command usage of a user
+command-userinfo-user-name = user
+command-userinfo-user-description = Who to show (defaults to you)
+command-show-avatar-name = Show avatar
+command-user-info-name = User info
+command-give-codename-name = Give codename
+command-codename-from-this-message-name = Codename from this message
+command-perms-description = Per-server command permissions (Manage Server)
+command-perms-allow-description = Allows a role, user or channel to use a command
+command-perms-allow-command-name = command
+command-perms-allow-command-description = Command name, e.g. "codename claim", or * for every command
+command-perms-allow-role-name = role
+command-perms-allow-role-description = Role to allow
+command-perms-allow-user-name = user
+command-perms-allow-user-description = User to allow
+command-perms-allow-channel-name = channel
+command-perms-allow-channel-description = Channel the command may be used in
+command-perms-deny-description = Denies a role, user or channel the use of a command
+command-perms-deny-command-name = command
+command-perms-deny-command-description = Command name, e.g. "codename claim", or * for every command
+command-perms-deny-role-name = role
+command-perms-deny-role-description = Role to deny
+command-perms-deny-user-name = user
+command-perms-deny-user-description = User to deny
+command-perms-deny-channel-name = channel
+command-perms-deny-channel-description = Channel the command may not be used in
+command-perms-remove-description = Removes the allow or deny rule for a role, user or channel
+command-perms-remove-command-name = command
+command-perms-remove-command-description = Command name the rule was set for
+command-perms-remove-role-name = role
+command-perms-remove-role-description = Role of the rule
+command-perms-remove-user-name = user
+command-perms-remove-user-description = User of the rule
+command-perms-remove-channel-name = channel
+command-perms-remove-channel-description = Channel of the rule
+command-perms-list-description = Lists the permission rules set in this server
+command-ratelimits-description = Shows the configured rate limits and how often they were hit (owner use)
+command-config-description = Per-server bot settings (Manage Server)
+command-config-get-description = Shows one setting, or all of them
+command-config-get-key-name = key
+command-config-get-key-description = Setting to show (defaults to all)
+command-config-set-description = Changes a setting
+command-config-set-key-name = key
+command-config-set-key-description = Setting to change
+command-config-set-value-name = value
+command-config-set-value-description = New value
+command-config-reset-description = Resets a setting to its default
+command-config-reset-key-name = key
+command-config-reset-key-description = Setting to reset
+command-privacy-description = Controls what the bot stores about you
+command-privacy-optout-description = Stops logging your commands and hides your name from the live feed
+command-privacy-optin-description = Resumes logging your commands and showing your name in the live feed
+command-privacy-export-description = Sends you a JSON file of everything the bot has logged about you
+command-privacy-delete-description = Erases every command the bot has logged for you
+command-history-description = Command history maintenance (owner use)
+command-history-prune-description = Applies the history retention policy now and reports the rows removed (owner use)
+command-history-export-description = Exports the command history as a CSV or JSON Lines file (owner use)
+command-history-export-format-name = format
+command-history-export-format-description = File format (defaults to csv)
+command-history-export-days-name = days
+command-history-export-days-description = Only the last this many days
+command-history-export-user-name = user
+command-history-export-user-description = Only this user's commands
+command-history-export-command-name = command
+command-history-export-command-description = Only this command and its subcommands
+command-stats-description = Shows the most used commands, most active users and daily usage of this server
+command-stats-days-name = days
+command-stats-days-description = How many days back to look (defaults to 30)
+command-leaderboard-description = Ranks the most active users of this server
+command-leaderboard-days-name = days
+command-leaderboard-days-description = How many days back to look (defaults to 30)
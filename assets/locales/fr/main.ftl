@@ -0,0 +1,242 @@
+## Réponses
+
+register-done = Commandes d'application enregistrées
+
+codename-title = Nom de code
+codename-generated =
+    Ton nom de code généré est :
+    {" "}**{ $codename } !**
+codename-given =
+    Le nom de code de { $mention } est :
+    {" "}**{ $codename } !**
+codename-batch-header = Tes noms de code générés sont :
+codename-favorite =
+    Ton nom de code préféré est :
+    {" "}**{ $codename } !**
+codename-batch-title = Idées de noms de code
+codename-batch-footer = 🎲 relance une entrée, ⭐ choisit ton préféré
+codename-batch-favorite-title = Nom de code préféré : { $codename }
+codename-batch-favorite-footer = Choisi comme choix final
+codename-batch-reroll-all = Tout relancer
+codename-seeded-footer = Généré à partir du message de { $name }
+codename-reloaded = Données de noms de code rechargées : { $adjectives } adjectifs, { $animals } animaux
+codename-reload-failed = Échec du rechargement, les données précédentes sont conservées : { $error }
+codename-unknown = **{ $codename }** n'est pas un nom de code que j'aurais pu générer
+codename-claimed = **{ $codename }** appartient désormais à { $owner }
+codename-claimed-for = **{ $codename }** appartient désormais à { $owner } : { $description }
+codename-already-claimed = **{ $codename }** est déjà réservé sur ce serveur
+codename-released = **{ $codename }** a été libéré
+codename-not-yours = Tu n'as aucune réservation sur **{ $codename }** à libérer
+codename-list-empty = Aucun nom de code n'a encore été réservé sur ce serveur
+codename-list-header = Noms de code réservés ({ $count }) :
+
+avatar-title = Avatar de { $name }
+avatar-no-banner = { $name } n'a pas de bannière de profil
+
+userinfo-title = À propos de { $name }
+userinfo-created = Compte créé
+userinfo-joined = A rejoint le serveur
+userinfo-roles = Rôles
+userinfo-roles-more = +{ $count } de plus
+userinfo-commands-run = Commandes exécutées
+userinfo-date-age = { $date } (il y a { $age })
+age-less-than-a-day = moins d'un jour
+age-years = { $count ->
+    [one] 1 an
+   *[other] { $count } ans
+}
+age-months = { $count ->
+   *[other] { $count } mois
+}
+age-days = { $count ->
+    [one] 1 jour
+   *[other] { $count } jours
+}
+
+permission-denied = Tu n'as pas le droit d'utiliser `/{ $command }` ici
+perms-list-title = Règles de permission
+perms-list-empty = Aucune règle de permission n'est définie sur ce serveur ; toutes les commandes sont ouvertes à tous
+perms-allow = autoriser
+perms-deny = interdire
+perms-now-allowed = `{ $command }` est désormais autorisé pour { $target }
+perms-now-denied = `{ $command }` est désormais interdit pour { $target }
+perms-removed = La règle `{ $command }` pour { $target } a été supprimée
+perms-no-rule = Il n'y a pas de règle `{ $command }` pour { $target }
+perms-pick-one-target = Choisis exactement un rôle, un utilisateur ou un salon
+perms-unknown-command = Il n'existe pas de commande `{ $command }`
+
+ratelimit-user = Doucement ! Tu utilises `/{ $command }` trop vite, réessaie dans { $seconds } s
+ratelimit-channel = Doucement ! Ce salon utilise `/{ $command }` trop vite, réessaie dans { $seconds } s
+ratelimit-guild = Doucement ! Ce serveur utilise `/{ $command }` trop vite, réessaie dans { $seconds } s
+ratelimits-title = Limites de débit
+ratelimits-none = Aucune limite de débit n'est configurée
+ratelimits-rule = `{ $command }` { $scope } : { $capacity } par { $seconds } s
+ratelimits-throttled-title = Freinés ces 7 derniers jours
+ratelimits-nothing-throttled = Rien n'a été freiné
+ratelimits-throttled = `{ $command }` ({ $scope }) : { $count }
+
+config-title = Paramètres du serveur
+config-updated = `{ $key }` vaut désormais `{ $value }`
+config-reset = `{ $key }` est revenu à sa valeur par défaut, `{ $value }`
+config-unknown-key = Il n'existe pas de paramètre `{ $key }`, essaie l'un de : { $keys }
+config-invalid-value = `{ $value }` n'est pas une valeur valide pour `{ $key }`, valeurs attendues : { $allowed }
+setting-feed_visible = afficher les commandes de ce serveur dans le flux web
+setting-ephemeral_replies = seul l'utilisateur qui lance la commande voit les réponses
+setting-codename_style = écriture des noms de code
+setting-locale = langue des réponses (auto suit la langue Discord de chaque utilisateur)
+setting-history_logging = enregistrer les commandes de ce serveur dans l'historique
+setting-history_retention_days = nombre de jours de conservation de l'historique de ce serveur (default utilise la limite du bot)
+
+privacy-opted-out = Tu t'es désinscrit : tes commandes ne sont plus enregistrées et le flux en direct t'affiche comme Anonyme. Utilise `/privacy delete` pour effacer ce qui a été enregistré avant
+privacy-already-opted-out = Tu es déjà désinscrit
+privacy-opted-in = Tu es réinscrit : tes commandes sont de nouveau enregistrées et affichées dans le flux en direct
+privacy-not-opted-out = Tu n'es pas désinscrit
+privacy-export-dm = Voici tout ce que j'ai enregistré à ton sujet ({ $count ->
+        [one] { $count } commande
+       *[other] { $count } commandes
+    })
+privacy-export-sent = Je t'ai envoyé tes données en message privé
+privacy-export-failed = Je n'ai pas pu t'envoyer de message privé, autorise les messages privés de ce serveur et réessaie
+privacy-deleted = { $count ->
+        [one] { $count } commande enregistrée supprimée
+       *[other] { $count } commandes enregistrées supprimées
+    }
+
+history-pruned = { $total ->
+        [one] { $total } ligne d'historique supprimée
+       *[other] { $total } lignes d'historique supprimées
+    } ({ $expired } expirées, { $trimmed } au-delà de la limite de lignes)
+
+history-exported = { $count ->
+        [one] 1 ligne d'historique exportée
+       *[other] { $count } lignes d'historique exportées
+    }
+history-export-too-large = L'export de { $count } lignes est trop volumineux pour être joint ; utilisez plutôt `discordbot history export` ou le point d'accès REST
+
+stats-title = Utilisation { $days ->
+        [one] du dernier jour
+       *[other] des { $days } derniers jours
+    }
+stats-empty = Aucune commande n'a été exécutée ici pendant cette période
+stats-total = { $count ->
+        [one] 1 commande exécutée
+       *[other] { $count } commandes exécutées
+    }
+stats-peak-day = Jour le plus actif : { $date } ({ $count })
+stats-top-commands = Commandes les plus utilisées
+stats-top-users = Utilisateurs les plus actifs
+stats-busiest-hours = Heures les plus actives (UTC)
+leaderboard-title = Les plus actifs { $days ->
+        [one] du dernier jour
+       *[other] des { $days } derniers jours
+    }
+leaderboard-entry = **{ $name }** : { $count ->
+        [one] 1 commande
+       *[other] { $count } commandes
+    }
+
+## Descriptions des commandes, noms des menus contextuels et options
+
+command-register-description = Enregistre les commandes d'application sur Discord (propriétaire)
+command-codename-description = Génération de noms de code et registre par serveur
+command-codename-generate-description = Génère un nom de code aléatoire
+command-codename-generate-unique-name = unique
+command-codename-generate-unique-description = Relancer jusqu'à ce que le nom de code ne soit pas réservé sur ce serveur
+command-codename-generate-count-name = nombre
+command-codename-generate-count-description = Combien de noms de code proposer (jusqu'à 10)
+command-codename-claim-description = Réserve un nom de code généré sur ce serveur
+command-codename-claim-codename-name = nom-de-code
+command-codename-claim-codename-description = Le nom de code généré à réserver
+command-codename-claim-description-name = description
+command-codename-claim-description-description = À quoi servira le nom de code
+command-codename-claim-owner-name = propriétaire
+command-codename-claim-owner-description = À qui appartient le nom de code (toi par défaut)
+command-codename-list-description = Liste les noms de code réservés sur ce serveur
+command-codename-release-description = Libère un nom de code réservé (le tien, ou n'importe lequel avec Gérer le serveur)
+command-codename-release-codename-name = nom-de-code
+command-codename-release-codename-description = Le nom de code réservé à libérer
+command-reload-description = Recharge le fichier de noms de code sans redémarrer (propriétaire)
+command-avatar-description = Affiche l'avatar de l'utilisateur indiqué
+command-avatar-user-name = utilisateur
+command-avatar-user-description = L'avatar de qui afficher
+command-avatar-mention-name = mentionner
+command-avatar-mention-description = Mentionner l'utilisateur dans la réponse
+command-avatar-size-name = taille
+command-avatar-size-description = Taille de l'image en pixels (16-4096, arrondie à une puissance de deux)
+command-avatar-format-name = format
+command-avatar-format-description = Format de l'image
+command-avatar-source-name = source
+command-avatar-source-description = Avatar global ou avatar de ce serveur
+command-avatar-banner-name = bannière
+command-avatar-banner-description = Afficher aussi la bannière de profil
+command-userinfo-description = Affiche l'âge du compte, la date d'arrivée, les rôles et l'utilisation des commandes
+command-userinfo-user-name = utilisateur
+command-userinfo-user-description = Qui afficher (toi par défaut)
+command-show-avatar-name = Afficher l'avatar
+command-user-info-name = Infos utilisateur
+command-give-codename-name = Donner un nom de code
+command-codename-from-this-message-name = Nom de code depuis ce message
+command-perms-description = Permissions des commandes par serveur (Gérer le serveur)
+command-perms-allow-description = Autorise un rôle, un utilisateur ou un salon à utiliser une commande
+command-perms-allow-command-name = commande
+command-perms-allow-command-description = Nom de la commande, p. ex. "codename claim", ou * pour toutes
+command-perms-allow-role-name = rôle
+command-perms-allow-role-description = Rôle à autoriser
+command-perms-allow-user-name = utilisateur
+command-perms-allow-user-description = Utilisateur à autoriser
+command-perms-allow-channel-name = salon
+command-perms-allow-channel-description = Salon où la commande peut être utilisée
+command-perms-deny-description = Interdit une commande à un rôle, un utilisateur ou un salon
+command-perms-deny-command-name = commande
+command-perms-deny-command-description = Nom de la commande, p. ex. "codename claim", ou * pour toutes
+command-perms-deny-role-name = rôle
+command-perms-deny-role-description = Rôle à interdire
+command-perms-deny-user-name = utilisateur
+command-perms-deny-user-description = Utilisateur à interdire
+command-perms-deny-channel-name = salon
+command-perms-deny-channel-description = Salon où la commande ne peut pas être utilisée
+command-perms-remove-description = Supprime la règle d'un rôle, d'un utilisateur ou d'un salon
+command-perms-remove-command-name = commande
+command-perms-remove-command-description = Nom de la commande de la règle
+command-perms-remove-role-name = rôle
+command-perms-remove-role-description = Rôle de la règle
+command-perms-remove-user-name = utilisateur
+command-perms-remove-user-description = Utilisateur de la règle
+command-perms-remove-channel-name = salon
+command-perms-remove-channel-description = Salon de la règle
+command-perms-list-description = Liste les règles de permission de ce serveur
+command-ratelimits-description = Affiche les limites de débit et combien de fois elles ont bloqué (propriétaire)
+command-config-description = Paramètres du bot par serveur (Gérer le serveur)
+command-config-get-description = Affiche un paramètre, ou tous
+command-config-get-key-name = clé
+command-config-get-key-description = Paramètre à afficher (tous par défaut)
+command-config-set-description = Modifie un paramètre
+command-config-set-key-name = clé
+command-config-set-key-description = Paramètre à modifier
+command-config-set-value-name = valeur
+command-config-set-value-description = Nouvelle valeur
+command-config-reset-description = Rétablit la valeur par défaut d'un paramètre
+command-config-reset-key-name = clé
+command-config-reset-key-description = Paramètre à rétablir
+command-privacy-description = Contrôle ce que le bot conserve à ton sujet
+command-privacy-optout-description = Arrête d'enregistrer tes commandes et masque ton nom dans le flux en direct
+command-privacy-optin-description = Reprend l'enregistrement de tes commandes et l'affichage de ton nom dans le flux en direct
+command-privacy-export-description = T'envoie un fichier JSON de tout ce que le bot a enregistré à ton sujet
+command-privacy-delete-description = Efface toutes les commandes que le bot a enregistrées pour toi
+command-history-description = Maintenance de l'historique des commandes (propriétaire)
+command-history-prune-description = Applique maintenant la politique de conservation de l'historique et indique les lignes supprimées (propriétaire)
+command-history-export-description = Exporte l'historique des commandes en fichier CSV ou JSON Lines (propriétaire)
+command-history-export-format-name = format
+command-history-export-format-description = Format du fichier (csv par défaut)
+command-history-export-days-name = jours
+command-history-export-days-description = Seulement les derniers jours indiqués
+command-history-export-user-name = utilisateur
+command-history-export-user-description = Seulement les commandes de cet utilisateur
+command-history-export-command-name = commande
+command-history-export-command-description = Seulement cette commande et ses sous-commandes
+command-stats-description = Affiche les commandes les plus utilisées, les utilisateurs les plus actifs et l'utilisation quotidienne de ce serveur
+command-stats-days-name = jours
+command-stats-days-description = Nombre de jours à remonter (30 par défaut)
+command-leaderboard-description = Classe les utilisateurs les plus actifs de ce serveur
+command-leaderboard-days-name = jours
+command-leaderboard-days-description = Nombre de jours à remonter (30 par défaut)
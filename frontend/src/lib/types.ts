@@ -7,3 +7,76 @@ export type FeedItem = {
 	command_output: string;
 	test_item: boolean;
 };
+
+/** Message sent on /ws/feed, tagged with its `type` */
+export type FeedEvent =
+	| ({ type: 'command_executed' | 'command_failed' } & FeedItem)
+	| {
+			type: 'guild_joined';
+			item_uuid: string;
+			timestamp: string;
+			guild_id: string;
+			guild_name: string;
+			member_count: number;
+	  }
+	| {
+			type: 'guild_left';
+			item_uuid: string;
+			timestamp: string;
+			guild_id: string;
+			guild_name: string | null;
+	  }
+	| {
+			type: 'member_joined';
+			item_uuid: string;
+			timestamp: string;
+			guild_id: string;
+			user_id: string;
+			user_name: string;
+	  }
+	| {
+			type: 'shard_connected' | 'shard_disconnected';
+			item_uuid: string;
+			timestamp: string;
+			shard_id: number;
+	  }
+	| {
+			type: 'config_changed';
+			item_uuid: string;
+			timestamp: string;
+			guild_id: string;
+			key: string;
+			value: string;
+			changed_by: string;
+	  };
+
+/** Turns any feed event into a card the feed can show */
+export const feedItemFromEvent = (event: FeedEvent): FeedItem => {
+	const card = (author_name: string, command_name: string, command_output: string): FeedItem => ({
+		item_uuid: event.item_uuid,
+		timestamp: event.timestamp,
+		author_id: '',
+		author_name,
+		command_name,
+		command_output,
+		test_item: false
+	});
+	switch (event.type) {
+		case 'command_executed':
+			return event;
+		case 'command_failed':
+			return { ...event, command_name: `${event.command_name} (failed)` };
+		case 'guild_joined':
+			return card('bot', 'joined server', `${event.guild_name} (${event.member_count} members)`);
+		case 'guild_left':
+			return card('bot', 'left server', event.guild_name ?? event.guild_id);
+		case 'member_joined':
+			return { ...card(event.user_name, 'member joined', event.guild_id), author_id: event.user_id };
+		case 'shard_connected':
+			return card('gateway', 'shard connected', `shard ${event.shard_id}`);
+		case 'shard_disconnected':
+			return card('gateway', 'shard disconnected', `shard ${event.shard_id}`);
+		case 'config_changed':
+			return card('config', 'setting changed', `${event.key} = ${event.value}`);
+	}
+};
@@ -0,0 +1,16 @@
+#!/usr/bin/env sh
+# Runs the history store tests against a throwaway PostgreSQL server.
+# Needs initdb, pg_ctl and postgres on PATH; extra arguments go to cargo test.
+# Postgres refuses to run as root, so run this as a regular user.
+set -eu
+
+PORT="${PGPORT:-54329}"
+DATA_DIR="$(mktemp -d)"
+trap 'pg_ctl -D "$DATA_DIR" -m immediate stop >/dev/null 2>&1 || true; rm -rf "$DATA_DIR"' EXIT
+
+initdb -D "$DATA_DIR" -U postgres -A trust >/dev/null
+pg_ctl -D "$DATA_DIR" -o "-p $PORT -k $DATA_DIR -c listen_addresses=localhost" \
+    -l "$DATA_DIR/server.log" -w start >/dev/null
+
+TEST_DATABASE_URL="postgres://postgres@localhost:$PORT/postgres" \
+    cargo test --test history_store_tests "$@"
@@ -0,0 +1,427 @@
+use crate::export::HistoryFilter;
+use crate::settings::hidden_guilds;
+use crate::store::{HistoryGroup, HistoryStore, SqliteHistoryStore, StoreResult};
+use chrono::{DateTime, DurationRound, SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Most buckets one request may span, e.g. a day of minutes
+pub const MAX_BUCKETS: i64 = 1440;
+/// How long computed stats are served from the cache
+pub const STATS_CACHE_SECONDS: u64 = 30;
+
+/// Width of the periods counts are grouped into
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bucket {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Bucket {
+    pub fn parse(bucket: &str) -> Option<Self> {
+        match bucket.to_ascii_lowercase().as_str() {
+            "minute" => Some(Bucket::Minute),
+            "hour" => Some(Bucket::Hour),
+            "day" => Some(Bucket::Day),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bucket::Minute => "minute",
+            Bucket::Hour => "hour",
+            Bucket::Day => "day",
+        }
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            Bucket::Minute => chrono::Duration::minutes(1),
+            Bucket::Hour => chrono::Duration::hours(1),
+            Bucket::Day => chrono::Duration::days(1),
+        }
+    }
+
+    /// Window covered when a request gives no `since`: the last hour of minutes, the
+    /// last day of hours or the last 30 days
+    pub fn default_span(&self) -> chrono::Duration {
+        match self {
+            Bucket::Minute => chrono::Duration::hours(1),
+            Bucket::Hour => chrono::Duration::days(1),
+            Bucket::Day => chrono::Duration::days(30),
+        }
+    }
+
+    /// Length of the timestamp prefix that identifies a bucket, e.g. `2025-03-10T09` for hours
+    fn prefix_len(&self) -> usize {
+        match self {
+            Bucket::Minute => 16,
+            Bucket::Hour => 13,
+            Bucket::Day => 10,
+        }
+    }
+}
+
+/// ### The buckets a stats request covers
+/// `since` is rounded down to the start of its bucket; `until` is exclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub bucket: Bucket,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+impl TimeWindow {
+    /// Builds a window ending at `until` (default `now`) and starting at `since` (default
+    /// the bucket's `default_span` earlier). Fails for empty windows and windows of more
+    /// than `MAX_BUCKETS` buckets.
+    pub fn new(
+        bucket: Bucket,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<Self, String> {
+        let until = until.unwrap_or(now);
+        let since = since.unwrap_or(until - bucket.default_span());
+        let since = since
+            .duration_trunc(bucket.duration())
+            .map_err(|e| format!("Invalid since: {}", e))?;
+        if since >= until {
+            return Err("since must be before until".to_string());
+        }
+        let window = TimeWindow {
+            bucket,
+            since,
+            until,
+        };
+        if window.bucket_count() > MAX_BUCKETS {
+            return Err(format!(
+                "The window spans more than {} {} buckets, use a larger bucket or a shorter window",
+                MAX_BUCKETS,
+                bucket.name()
+            ));
+        }
+        Ok(window)
+    }
+
+    fn bucket_count(&self) -> i64 {
+        let width = self.bucket.duration().num_seconds();
+        ((self.until - self.since).num_seconds() + width - 1) / width
+    }
+
+    /// Start of every bucket in the window, oldest first
+    fn starts(&self) -> Vec<DateTime<Utc>> {
+        (0..self.bucket_count())
+            .map(|index| self.since + self.bucket.duration() * index as i32)
+            .collect()
+    }
+
+    /// The timestamp prefix identifying the bucket that starts at `start`
+    fn key(&self, start: DateTime<Utc>) -> String {
+        start.to_rfc3339()[..self.bucket.prefix_len()].to_string()
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Rows in the window, restricted to one command (and its subcommands) when given.
+/// Rows of hidden guilds (according to the SQLite database at `db_path`) are left out,
+/// and failed and denied attempts are only included with `include_denied`.
+fn window_filter(
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+    include_denied: bool,
+) -> StoreResult<HistoryFilter> {
+    let hidden =
+        hidden_guilds(db_path).map_err(|e| format!("Failed to load guild settings: {}", e))?;
+    Ok(HistoryFilter {
+        since: Some(window.since),
+        until: Some(window.until),
+        command: command.map(str::to_string),
+        include_denied,
+        exclude_guilds: hidden.into_iter().collect(),
+        ..HistoryFilter::default()
+    })
+}
+
+/// One bucket of a count series
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CountPoint {
+    /// RFC 3339 start of the bucket
+    pub start: String,
+    pub count: u64,
+}
+
+/// Uses of one command per bucket
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CommandSeries {
+    pub command: String,
+    pub total: u64,
+    pub points: Vec<CountPoint>,
+}
+
+/// Response of `/api/v1/stats/commands`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CommandCounts {
+    pub bucket: &'static str,
+    pub since: String,
+    pub until: String,
+    /// Busiest command first
+    pub commands: Vec<CommandSeries>,
+}
+
+/// Counts the commands of the SQLite history that ran in each bucket of the window,
+/// per qualified command name
+pub fn command_counts(
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+) -> StoreResult<CommandCounts> {
+    command_counts_from(&SqliteHistoryStore::new(db_path), db_path, window, command)
+}
+
+/// Like `command_counts`, reading the rows from `history`
+pub fn command_counts_from(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+) -> StoreResult<CommandCounts> {
+    let filter = window_filter(db_path, window, command, false)?;
+    let mut by_command: HashMap<String, HashMap<String, u64>> = HashMap::new();
+    for group in history.count_by(
+        &filter,
+        &[
+            HistoryGroup::TimePrefix(window.bucket.prefix_len()),
+            HistoryGroup::Command,
+        ],
+    )? {
+        if let [key, command] = group.keys.as_slice() {
+            by_command
+                .entry(command.clone())
+                .or_default()
+                .insert(key.clone(), group.count);
+        }
+    }
+    let starts = window.starts();
+    let mut commands: Vec<CommandSeries> = by_command
+        .into_iter()
+        .map(|(command, counts)| {
+            let points: Vec<CountPoint> = starts
+                .iter()
+                .map(|start| CountPoint {
+                    start: format_time(*start),
+                    count: counts.get(&window.key(*start)).copied().unwrap_or(0),
+                })
+                .collect();
+            CommandSeries {
+                command,
+                total: points.iter().map(|point| point.count).sum(),
+                points,
+            }
+        })
+        .collect();
+    commands.sort_by(|a, b| b.total.cmp(&a.total).then(a.command.cmp(&b.command)));
+    Ok(CommandCounts {
+        bucket: window.bucket.name(),
+        since: format_time(window.since),
+        until: format_time(window.until),
+        commands,
+    })
+}
+
+/// Response of `/api/v1/stats/users`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UniqueUsers {
+    pub bucket: &'static str,
+    pub since: String,
+    pub until: String,
+    /// Distinct users over the whole window, not the sum of the buckets
+    pub total: u64,
+    pub points: Vec<CountPoint>,
+}
+
+/// Counts the distinct users of the SQLite history who ran a command in each bucket of
+/// the window
+pub fn unique_users(
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+) -> StoreResult<UniqueUsers> {
+    unique_users_from(&SqliteHistoryStore::new(db_path), db_path, window, command)
+}
+
+/// Like `unique_users`, reading the rows from `history`
+pub fn unique_users_from(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+) -> StoreResult<UniqueUsers> {
+    let filter = window_filter(db_path, window, command, false)?;
+    let counts: HashMap<String, u64> = history
+        .count_users_by(
+            &filter,
+            &[HistoryGroup::TimePrefix(window.bucket.prefix_len())],
+        )?
+        .into_iter()
+        .filter_map(|group| Some((group.keys.into_iter().next()?, group.count)))
+        .collect();
+    let total = history
+        .count_users_by(&filter, &[])?
+        .first()
+        .map_or(0, |group| group.count);
+    Ok(UniqueUsers {
+        bucket: window.bucket.name(),
+        since: format_time(window.since),
+        until: format_time(window.until),
+        total,
+        points: window
+            .starts()
+            .into_iter()
+            .map(|start| CountPoint {
+                start: format_time(start),
+                count: counts.get(&window.key(start)).copied().unwrap_or(0),
+            })
+            .collect(),
+    })
+}
+
+/// Outcomes of the invocations in one bucket
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ErrorPoint {
+    pub start: String,
+    pub ok: u64,
+    pub errors: u64,
+    /// Stopped by a `/perms` rule; not part of the error rate
+    pub denied: u64,
+    /// `errors / (ok + errors)`, 0 without invocations
+    pub error_rate: f64,
+}
+
+/// Response of `/api/v1/stats/errors`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ErrorRates {
+    pub bucket: &'static str,
+    pub since: String,
+    pub until: String,
+    pub ok: u64,
+    pub errors: u64,
+    pub denied: u64,
+    pub error_rate: f64,
+    pub points: Vec<ErrorPoint>,
+}
+
+fn error_rate(ok: u64, errors: u64) -> f64 {
+    if ok + errors == 0 {
+        0.0
+    } else {
+        errors as f64 / (ok + errors) as f64
+    }
+}
+
+/// Counts the ok, failed and denied invocations of the SQLite history in each bucket of
+/// the window
+pub fn error_rates(
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+) -> StoreResult<ErrorRates> {
+    error_rates_from(&SqliteHistoryStore::new(db_path), db_path, window, command)
+}
+
+/// Like `error_rates`, reading the rows from `history`
+pub fn error_rates_from(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    window: &TimeWindow,
+    command: Option<&str>,
+) -> StoreResult<ErrorRates> {
+    let filter = window_filter(db_path, window, command, true)?;
+    let mut counts: HashMap<String, (u64, u64, u64)> = HashMap::new();
+    for group in history.count_by(
+        &filter,
+        &[
+            HistoryGroup::TimePrefix(window.bucket.prefix_len()),
+            HistoryGroup::Status,
+        ],
+    )? {
+        if let [key, status] = group.keys.as_slice() {
+            let (ok, errors, denied) = counts.entry(key.clone()).or_default();
+            match status.as_str() {
+                "ok" => *ok += group.count,
+                "error" => *errors += group.count,
+                "denied" => *denied += group.count,
+                _ => {}
+            }
+        }
+    }
+    let points: Vec<ErrorPoint> = window
+        .starts()
+        .into_iter()
+        .map(|start| {
+            let (ok, errors, denied) = counts.get(&window.key(start)).copied().unwrap_or_default();
+            ErrorPoint {
+                start: format_time(start),
+                ok,
+                errors,
+                denied,
+                error_rate: error_rate(ok, errors),
+            }
+        })
+        .collect();
+    let ok = points.iter().map(|point| point.ok).sum();
+    let errors = points.iter().map(|point| point.errors).sum();
+    Ok(ErrorRates {
+        bucket: window.bucket.name(),
+        since: format_time(window.since),
+        until: format_time(window.until),
+        ok,
+        errors,
+        denied: points.iter().map(|point| point.denied).sum(),
+        error_rate: error_rate(ok, errors),
+        points,
+    })
+}
+
+/// ### Values kept for a fixed time after they were computed
+/// Expired entries are dropped whenever a new one is inserted.
+pub struct TtlCache<V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The value stored under `key`, unless it is older than the TTL
+    pub fn get(&self, key: &str) -> Option<V> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, key: String, value: V) {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), value));
+    }
+}
@@ -0,0 +1,90 @@
+/// Base URL of Discord's image CDN
+pub const CDN_BASE: &str = "https://cdn.discordapp.com";
+/// Size used when `/avatar` is not given one
+pub const DEFAULT_IMAGE_SIZE: u16 = 1024;
+
+/// Image formats Discord's CDN can serve. `Gif` is only available for animated images.
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[name = "png"]
+    Png,
+    #[name = "webp"]
+    Webp,
+    #[name = "jpg"]
+    Jpg,
+    #[name = "gif (animated only)"]
+    Gif,
+}
+
+/// Which avatar `/avatar` shows
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AvatarSource {
+    /// The avatar set on the user's account
+    #[name = "global"]
+    Global,
+    /// The server-specific avatar, falling back to the global one
+    #[name = "server"]
+    Server,
+}
+
+/// Rounds `size` down to a power of two within Discord's supported 16..=4096 range
+pub fn normalize_image_size(size: u16) -> u16 {
+    let size = size.clamp(16, 4096);
+    1 << (15 - size.leading_zeros())
+}
+
+/// File extension for an image hash. Without an explicit format animated images are
+/// served as gif and static ones as png; gif is downgraded to png for static images.
+pub fn image_extension(hash: &str, format: Option<ImageFormat>) -> &'static str {
+    let animated = hash.starts_with("a_");
+    match format {
+        Some(ImageFormat::Png) => "png",
+        Some(ImageFormat::Webp) => "webp",
+        Some(ImageFormat::Jpg) => "jpg",
+        Some(ImageFormat::Gif) | None if animated => "gif",
+        Some(ImageFormat::Gif) | None => "png",
+    }
+}
+
+/// CDN URL of a user's global avatar
+pub fn user_avatar_url(user_id: u64, hash: &str, format: Option<ImageFormat>, size: u16) -> String {
+    format!(
+        "{}/avatars/{}/{}.{}?size={}",
+        CDN_BASE,
+        user_id,
+        hash,
+        image_extension(hash, format),
+        normalize_image_size(size)
+    )
+}
+
+/// CDN URL of a member's server-specific avatar
+pub fn guild_member_avatar_url(
+    guild_id: u64,
+    user_id: u64,
+    hash: &str,
+    format: Option<ImageFormat>,
+    size: u16,
+) -> String {
+    format!(
+        "{}/guilds/{}/users/{}/avatars/{}.{}?size={}",
+        CDN_BASE,
+        guild_id,
+        user_id,
+        hash,
+        image_extension(hash, format),
+        normalize_image_size(size)
+    )
+}
+
+/// CDN URL of a user's profile banner
+pub fn user_banner_url(user_id: u64, hash: &str, format: Option<ImageFormat>, size: u16) -> String {
+    format!(
+        "{}/banners/{}/{}.{}?size={}",
+        CDN_BASE,
+        user_id,
+        hash,
+        image_extension(hash, format),
+        normalize_image_size(size)
+    )
+}
@@ -0,0 +1,205 @@
+use crate::SCHEMA_VERSION;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use rusqlite::{Connection, MAIN_DB, OpenFlags};
+use std::path::{Path, PathBuf};
+
+/// Directory snapshots are written to when `BACKUP_DIR` is not set
+pub const DEFAULT_BACKUP_DIR: &str = "./backups";
+/// How often snapshots are taken when `BACKUP_INTERVAL_HOURS` is not set
+pub const DEFAULT_BACKUP_INTERVAL_HOURS: u64 = 24;
+/// How many snapshots are kept when `BACKUP_KEEP` is not set
+pub const DEFAULT_BACKUP_KEEP: usize = 7;
+
+const SNAPSHOT_PREFIX: &str = "history-";
+const SNAPSHOT_SUFFIX: &str = ".db";
+
+/// ### Where, how often and how many database snapshots are kept
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupConfig {
+    pub dir: String,
+    /// 0 disables scheduled backups
+    pub interval_hours: u64,
+    /// Older snapshots beyond this many are deleted after each backup
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        BackupConfig {
+            dir: DEFAULT_BACKUP_DIR.to_string(),
+            interval_hours: DEFAULT_BACKUP_INTERVAL_HOURS,
+            keep: DEFAULT_BACKUP_KEEP,
+        }
+    }
+}
+
+/// File name of a snapshot taken at `now`, e.g. `history-20250102T030405Z.db`.
+/// Names sort in the order the snapshots were taken.
+pub fn snapshot_file_name(now: DateTime<Utc>) -> String {
+    format!(
+        "{}{}{}",
+        SNAPSHOT_PREFIX,
+        now.format("%Y%m%dT%H%M%SZ"),
+        SNAPSHOT_SUFFIX
+    )
+}
+
+/// Copies the database at `db_path` into a new snapshot in `dir` with SQLite's online
+/// backup API, so the bot can keep writing meanwhile. Returns the snapshot's path.
+pub fn backup_database(db_path: &str, dir: &str, now: DateTime<Utc>) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))?;
+    let path = Path::new(dir).join(snapshot_file_name(now));
+    // Write under a temporary name so an interrupted backup never looks like a snapshot
+    let partial = path.with_extension("db.partial");
+    let source = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {}", db_path, e))?;
+    source
+        .backup(MAIN_DB, &partial, None)
+        .map_err(|e| format!("Failed to back up {}: {}", db_path, e))?;
+    std::fs::rename(&partial, &path)
+        .map_err(|e| format!("Failed to move snapshot to {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Snapshots in `dir`, oldest first. A missing directory has none.
+pub fn list_snapshots(dir: &str) -> Result<Vec<PathBuf>, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir, e)),
+    };
+    let mut snapshots: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(SNAPSHOT_SUFFIX)
+                })
+        })
+        .collect();
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Deletes all but the newest `keep` snapshots in `dir` and returns how many were removed
+pub fn rotate_snapshots(dir: &str, keep: usize) -> Result<usize, String> {
+    let snapshots = list_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in &snapshots[..excess] {
+        std::fs::remove_file(path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(excess)
+}
+
+/// Checks that `snapshot` is an intact bot database this build can open: it passes
+/// `PRAGMA integrity_check`, has the history table, and its schema version is not newer
+/// than `SCHEMA_VERSION`. Returns the snapshot's schema version.
+pub fn validate_snapshot(snapshot: &str) -> Result<i64, String> {
+    if !Path::new(snapshot).is_file() {
+        return Err(format!("{} does not exist", snapshot));
+    }
+    let conn = Connection::open_with_flags(snapshot, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {}", snapshot, e))?;
+    let check = |e: rusqlite::Error| format!("{} is not a valid database: {}", snapshot, e);
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(check)?;
+    if integrity != "ok" {
+        return Err(format!(
+            "{} failed the integrity check: {}",
+            snapshot, integrity
+        ));
+    }
+    let has_history: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'command_history')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(check)?;
+    if !has_history {
+        return Err(format!("{} has no command_history table", snapshot));
+    }
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(check)?;
+    // Databases from before schema versioning report 0 and are migrated like version 1
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "{} has schema version {}, newer than the {} this build supports",
+            snapshot, version, SCHEMA_VERSION
+        ));
+    }
+    Ok(version)
+}
+
+/// Validates `snapshot` and swaps it in as the database at `db_path`. The current
+/// database is first saved as a snapshot in `backup_dir`, which is returned. Run this
+/// while the bot is stopped; `db_setup` migrates older snapshots on the next start.
+pub fn restore_snapshot(
+    snapshot: &str,
+    db_path: &str,
+    backup_dir: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<PathBuf>, String> {
+    validate_snapshot(snapshot)?;
+    let previous = if Path::new(db_path).exists() {
+        Some(backup_database(db_path, backup_dir, now)?)
+    } else {
+        None
+    };
+    // Copy next to the target first so the final rename is atomic
+    let staged = format!("{}.restoring", db_path);
+    std::fs::copy(snapshot, &staged)
+        .map_err(|e| format!("Failed to copy {} to {}: {}", snapshot, staged, e))?;
+    std::fs::rename(&staged, db_path)
+        .map_err(|e| format!("Failed to replace {}: {}", db_path, e))?;
+    Ok(previous)
+}
+
+/// Backs up the database and rotates old snapshots, as done by the scheduled task
+pub fn backup_and_rotate(
+    db_path: &str,
+    config: &BackupConfig,
+    now: DateTime<Utc>,
+) -> Result<(PathBuf, usize), String> {
+    let path = backup_database(db_path, &config.dir, now)?;
+    let removed = rotate_snapshots(&config.dir, config.keep)?;
+    Ok((path, removed))
+}
+
+/// Takes a snapshot every `interval_hours`, starting one interval after startup.
+/// An interval of 0 disables the task.
+pub fn spawn_backups(db_path: String, config: BackupConfig) {
+    if config.interval_hours == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(config.interval_hours * 3600);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            let (db_path, config) = (db_path.clone(), config.clone());
+            let result = tokio::task::spawn_blocking(move || {
+                backup_and_rotate(&db_path, &config, Utc::now())
+            })
+            .await;
+            match result {
+                Ok(Ok((path, removed))) => println!(
+                    "{}",
+                    format!(
+                        "Backed up the database to {} ({} old snapshots removed)",
+                        path.display(),
+                        removed
+                    )
+                    .white()
+                ),
+                Ok(Err(e)) => println!("{}", format!("Database backup failed: {}", e).red()),
+                Err(e) => println!("{}", format!("Database backup panicked: {}", e).red()),
+            }
+        }
+    });
+}
@@ -0,0 +1,342 @@
+//! Command line modes of the binary. Running `discordbot` without arguments starts the bot;
+//! any arguments select one of the maintenance modes below instead.
+use colored::Colorize;
+use discordbot::backup::{backup_database, restore_snapshot, validate_snapshot};
+use discordbot::codename_check::{
+    check_codename_data, format_dataset_report, normalize_codename_data,
+    read_codename_data_unchecked, write_codename_data,
+};
+use discordbot::config::BotConfig;
+use discordbot::export::{ExportFormat, HistoryFilter, export_history_from, parse_time_bound};
+use discordbot::registration::clear_fingerprints;
+use discordbot::store::open_history_store;
+use discordbot::{DEFAULT_CODENAME_PATH, DEFAULT_DB_PATH, db_setup};
+use poise::serenity_prelude as serenity;
+
+const USAGE: &str = "\
+usage:
+  discordbot                                   start the bot
+  discordbot codenames check [PATH] [--write]  lint the codename dataset (default ./assets/CodenameData.json);
+                                               --write rewrites it normalized and sorted
+  discordbot commands wipe [--global] [--guild ID]...
+                                               remove registered application commands; without flags
+                                               wipes the global commands and every DEV_GUILD_IDS guild
+  discordbot history export [--format csv|jsonl] [--since DATE] [--until DATE] [--guild ID]
+                            [--user ID] [--command NAME] [--include-denied] [--output PATH]
+                                               stream the history (HISTORY_BACKEND) to PATH (default stdout);
+                                               DATE is YYYY-MM-DD or RFC 3339, --until is exclusive
+  discordbot db backup                         snapshot ./history.db into BACKUP_DIR (default ./backups)
+  discordbot db restore SNAPSHOT               validate SNAPSHOT and swap it in as ./history.db, saving the
+                                               current database to BACKUP_DIR first; stop the bot before";
+
+/// Runs the command line mode selected by `args` (without the program name) and
+/// returns the process exit code.
+pub async fn run(args: &[String]) -> i32 {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["codenames", "check", rest @ ..] => codenames_check(rest),
+        ["commands", "wipe", rest @ ..] => commands_wipe(rest).await,
+        ["history", "export", rest @ ..] => history_export(rest).await,
+        ["db", "backup"] => db_backup(),
+        ["db", "restore", snapshot] => db_restore(snapshot).await,
+        ["help" | "--help" | "-h"] => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+/// `discordbot codenames check [PATH] [--write]`
+fn codenames_check(args: &[&str]) -> i32 {
+    let write = args.contains(&"--write");
+    let path = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .copied()
+        .unwrap_or(DEFAULT_CODENAME_PATH);
+
+    let codename_data = match read_codename_data_unchecked(path) {
+        Ok(codename_data) => codename_data,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+    };
+    let report = check_codename_data(&codename_data);
+    println!("{}", path.bold());
+    println!("{}", format_dataset_report(&report));
+
+    if write {
+        let normalized = normalize_codename_data(&codename_data);
+        if let Err(e) = normalized.validate() {
+            eprintln!("{}", format!("Not writing {}: {}", path, e).red());
+            return 1;
+        }
+        if let Err(e) = write_codename_data(path, &normalized) {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+        println!(
+            "{}",
+            format!(
+                "Wrote normalized dataset to {} ({} adjectives, {} animals)",
+                path,
+                normalized.adjectives.len(),
+                normalized.animals.len()
+            )
+            .green()
+        );
+        return 0;
+    }
+
+    if report.has_errors() { 1 } else { 0 }
+}
+
+/// `discordbot commands wipe [--global] [--guild ID]...`
+async fn commands_wipe(args: &[&str]) -> i32 {
+    dotenvy::dotenv().ok();
+    let mut global = false;
+    let mut guild_ids: Vec<u64> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--global" => global = true,
+            "--guild" => match args.next().and_then(|id| id.parse().ok()) {
+                Some(id) => guild_ids.push(id),
+                None => {
+                    eprintln!("{}", "--guild needs a numeric guild ID".red());
+                    return 2;
+                }
+            },
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    if !global && guild_ids.is_empty() {
+        global = true;
+        match BotConfig::from_env() {
+            Ok(config) => guild_ids = config.dev_guild_ids,
+            Err(e) => {
+                eprintln!("{}", e.red());
+                return 1;
+            }
+        }
+    }
+
+    let token = match std::env::var("DISCORD_TOKEN") {
+        Ok(token) => token,
+        Err(_) => {
+            eprintln!("{}", "Expected a token in the environment".red());
+            return 1;
+        }
+    };
+    let http = serenity::Http::new(&token);
+    match http.get_current_application_info().await {
+        Ok(info) => http.set_application_id(info.id),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Failed to fetch application info: {}", e).red()
+            );
+            return 1;
+        }
+    }
+
+    let mut exit_code = 0;
+    if global {
+        match serenity::Command::set_global_commands(&http, vec![]).await {
+            Ok(_) => println!("{}", "Wiped global commands".green()),
+            Err(e) => {
+                eprintln!("{}", format!("Failed to wipe global commands: {}", e).red());
+                exit_code = 1;
+            }
+        }
+    }
+    for guild_id in guild_ids {
+        match serenity::GuildId::new(guild_id)
+            .set_commands(&http, vec![])
+            .await
+        {
+            Ok(_) => println!(
+                "{}",
+                format!("Wiped commands in guild {}", guild_id).green()
+            ),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Failed to wipe commands in guild {}: {}", guild_id, e).red()
+                );
+                exit_code = 1;
+            }
+        }
+    }
+
+    // The stored fingerprints no longer match what Discord has; re-register on next startup
+    if std::path::Path::new(DEFAULT_DB_PATH).exists() {
+        db_setup(DEFAULT_DB_PATH).await;
+        if let Err(e) = clear_fingerprints(DEFAULT_DB_PATH) {
+            eprintln!(
+                "{}",
+                format!("Failed to clear command fingerprints: {}", e).red()
+            );
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
+
+/// Backup settings from the environment, for the `db` modes
+fn backup_config() -> Result<discordbot::backup::BackupConfig, String> {
+    dotenvy::dotenv().ok();
+    BotConfig::from_env().map(|config| config.backup)
+}
+
+/// `discordbot db backup`
+fn db_backup() -> i32 {
+    let result = backup_config()
+        .and_then(|config| backup_database(DEFAULT_DB_PATH, &config.dir, chrono::Utc::now()));
+    match result {
+        Ok(path) => {
+            println!(
+                "{}",
+                format!("Backed up {} to {}", DEFAULT_DB_PATH, path.display()).green()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e.red());
+            1
+        }
+    }
+}
+
+/// `discordbot db restore SNAPSHOT`
+async fn db_restore(snapshot: &str) -> i32 {
+    let config = match backup_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+    };
+    let version = match validate_snapshot(snapshot) {
+        Ok(version) => version,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+    };
+    println!("{} has schema version {}", snapshot, version);
+    match restore_snapshot(snapshot, DEFAULT_DB_PATH, &config.dir, chrono::Utc::now()) {
+        Ok(previous) => {
+            if let Some(previous) = previous {
+                println!("Saved the previous database to {}", previous.display());
+            }
+            // Bring older snapshots up to the current schema right away
+            db_setup(DEFAULT_DB_PATH).await;
+            println!(
+                "{}",
+                format!("Restored {} from {}", DEFAULT_DB_PATH, snapshot).green()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e.red());
+            1
+        }
+    }
+}
+
+/// `discordbot history export [options]`
+async fn history_export(args: &[&str]) -> i32 {
+    let mut format = ExportFormat::Csv;
+    let mut filter = HistoryFilter::default();
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        if arg == "--include-denied" {
+            filter.include_denied = true;
+            continue;
+        }
+        let Some(&value) = args.next() else {
+            eprintln!("{}", USAGE);
+            return 2;
+        };
+        let parsed = match arg {
+            "--format" => ExportFormat::parse(value)
+                .map(|parsed| format = parsed)
+                .ok_or_else(|| format!("Unknown export format {:?}, use csv or jsonl", value)),
+            "--since" => parse_time_bound(value).map(|time| filter.since = Some(time)),
+            "--until" => parse_time_bound(value).map(|time| filter.until = Some(time)),
+            "--guild" => {
+                filter.guild_id = Some(value.to_string());
+                Ok(())
+            }
+            "--user" => {
+                filter.user_id = Some(value.to_string());
+                Ok(())
+            }
+            "--command" => {
+                filter.command = Some(value.to_string());
+                Ok(())
+            }
+            "--output" => {
+                output = Some(value);
+                Ok(())
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        };
+        if let Err(e) = parsed {
+            eprintln!("{}", e.red());
+            return 2;
+        }
+    }
+
+    dotenvy::dotenv().ok();
+    let backend = match BotConfig::from_env() {
+        Ok(config) => config.history_backend,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+    };
+    let output = output.map(str::to_string);
+    // The Postgres client blocks, so the whole export runs off the async runtime
+    let result = tokio::task::spawn_blocking(move || {
+        let history = open_history_store(&backend, DEFAULT_DB_PATH)?;
+        match output {
+            Some(path) => std::fs::File::create(&path)
+                .map_err(|e| format!("Failed to create {}: {}", path, e))
+                .and_then(|file| {
+                    let mut writer = std::io::BufWriter::new(file);
+                    export_history_from(&*history, DEFAULT_DB_PATH, &filter, format, &mut writer)
+                }),
+            None => {
+                let mut writer = std::io::BufWriter::new(std::io::stdout().lock());
+                export_history_from(&*history, DEFAULT_DB_PATH, &filter, format, &mut writer)
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("History export panicked: {}", e)));
+    match result {
+        Ok(count) => {
+            // stdout may hold the export itself, so report on stderr
+            eprintln!("{}", format!("Exported {} rows", count).green());
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e.red());
+            1
+        }
+    }
+}
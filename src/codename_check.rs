@@ -0,0 +1,222 @@
+use crate::{CodenameBlocklist, CodenameData};
+use std::collections::HashMap;
+use std::fmt;
+
+/// What is wrong with a single dataset entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The entry is empty or only whitespace
+    Empty,
+    /// Leading/trailing whitespace, repeated spaces, or non-space whitespace
+    Whitespace,
+    /// The entry contains characters outside ASCII
+    NonAscii,
+    /// The entry is not all lowercase like the rest of the dataset
+    Casing,
+    /// The entry repeats an earlier one (ignoring case and surrounding whitespace)
+    Duplicate { first_index: usize },
+}
+
+/// A suspicious entry found by `check_codename_data`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatasetIssue {
+    pub list: &'static str,
+    pub index: usize,
+    pub entry: String,
+    pub kind: IssueKind,
+}
+
+impl IssueKind {
+    /// Non-ASCII entries are only reported; everything else fails the check
+    pub fn is_error(&self) -> bool {
+        !matches!(self, IssueKind::NonAscii)
+    }
+}
+
+impl fmt::Display for DatasetIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match &self.kind {
+            IssueKind::Empty => "empty entry".to_string(),
+            IssueKind::Whitespace => "stray whitespace".to_string(),
+            IssueKind::NonAscii => "non-ASCII characters".to_string(),
+            IssueKind::Casing => "not lowercase".to_string(),
+            IssueKind::Duplicate { first_index } => {
+                format!("duplicate of entry {}", first_index)
+            }
+        };
+        write!(
+            f,
+            "{}[{}] {:?}: {}",
+            self.list, self.index, self.entry, what
+        )
+    }
+}
+
+/// Statistics and lint results for a codename dataset
+#[derive(Clone, Debug)]
+pub struct DatasetReport {
+    pub adjective_count: usize,
+    pub animal_count: usize,
+    /// Every adjective-animal pair, ignoring the blocklist
+    pub combination_count: u64,
+    /// Pairs left once the blocklist is applied
+    pub allowed_combination_count: u64,
+    /// Bits of entropy in a uniformly chosen allowed codename
+    pub entropy_bits: f64,
+    pub issues: Vec<DatasetIssue>,
+}
+
+impl DatasetReport {
+    /// True if any issue should fail the check (see `IssueKind::is_error`)
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|issue| issue.kind.is_error())
+    }
+}
+
+/// Lints the adjective and animal lists and computes dataset statistics
+pub fn check_codename_data(codename_data: &CodenameData) -> DatasetReport {
+    let mut issues = Vec::new();
+    check_list("adjectives", &codename_data.adjectives, &mut issues);
+    check_list("animals", &codename_data.animals, &mut issues);
+
+    let combination_count =
+        codename_data.adjectives.len() as u64 * codename_data.animals.len() as u64;
+    let mut allowed_combination_count = 0u64;
+    for adjective in &codename_data.adjectives {
+        for animal in &codename_data.animals {
+            if codename_data.blocklist.allows(adjective, animal) {
+                allowed_combination_count += 1;
+            }
+        }
+    }
+    let entropy_bits = if allowed_combination_count == 0 {
+        0.0
+    } else {
+        (allowed_combination_count as f64).log2()
+    };
+
+    DatasetReport {
+        adjective_count: codename_data.adjectives.len(),
+        animal_count: codename_data.animals.len(),
+        combination_count,
+        allowed_combination_count,
+        entropy_bits,
+        issues,
+    }
+}
+
+fn check_list(list: &'static str, entries: &[String], issues: &mut Vec<DatasetIssue>) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let mut push = |kind| {
+            issues.push(DatasetIssue {
+                list,
+                index,
+                entry: entry.clone(),
+                kind,
+            })
+        };
+        if entry.trim().is_empty() {
+            push(IssueKind::Empty);
+            continue;
+        }
+        if normalize_whitespace(entry) != *entry {
+            push(IssueKind::Whitespace);
+        }
+        if !entry.is_ascii() {
+            push(IssueKind::NonAscii);
+        }
+        if entry.to_lowercase() != *entry {
+            push(IssueKind::Casing);
+        }
+        let key = normalize_entry(entry);
+        match seen.get(&key) {
+            Some(&first_index) => push(IssueKind::Duplicate { first_index }),
+            None => {
+                seen.insert(key, index);
+            }
+        }
+    }
+}
+
+fn normalize_whitespace(entry: &str) -> String {
+    entry.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn normalize_entry(entry: &str) -> String {
+    normalize_whitespace(entry).to_lowercase()
+}
+
+fn normalize_list(entries: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = entries
+        .iter()
+        .map(|entry| normalize_entry(entry))
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Returns a copy of the dataset with every entry trimmed, lowercased and whitespace
+/// collapsed, empty entries removed, and each list sorted and deduplicated.
+/// Non-ASCII entries are kept as they are usually intentional (e.g. "vicuña").
+pub fn normalize_codename_data(codename_data: &CodenameData) -> CodenameData {
+    let mut combinations: Vec<(String, String)> = codename_data
+        .blocklist
+        .combinations
+        .iter()
+        .map(|(adjective, animal)| (normalize_entry(adjective), normalize_entry(animal)))
+        .filter(|(adjective, animal)| !adjective.is_empty() && !animal.is_empty())
+        .collect();
+    combinations.sort();
+    combinations.dedup();
+
+    CodenameData {
+        animals: normalize_list(&codename_data.animals),
+        adjectives: normalize_list(&codename_data.adjectives),
+        blocklist: CodenameBlocklist {
+            words: normalize_list(&codename_data.blocklist.words),
+            combinations,
+        },
+    }
+}
+
+/// Reads a codename file without validating it, so broken files can still be linted
+pub fn read_codename_data_unchecked(path: &str) -> Result<CodenameData, String> {
+    let data =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", path, e))
+}
+
+/// Writes the dataset to `path` as pretty-printed JSON
+pub fn write_codename_data(path: &str, codename_data: &CodenameData) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(codename_data)
+        .map_err(|e| format!("Failed to serialize codename data: {}", e))?;
+    std::fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+pub fn format_dataset_report(report: &DatasetReport) -> String {
+    let mut output = format!(
+        "adjectives:   {}\nanimals:      {}\ncombinations: {} ({} allowed by the blocklist)\nentropy:      {:.2} bits",
+        report.adjective_count,
+        report.animal_count,
+        report.combination_count,
+        report.allowed_combination_count,
+        report.entropy_bits
+    );
+    if report.issues.is_empty() {
+        output.push_str("\nno issues found");
+    } else {
+        output.push_str(&format!("\n{} issue(s):", report.issues.len()));
+        for issue in &report.issues {
+            let level = if issue.kind.is_error() {
+                "error"
+            } else {
+                "warning"
+            };
+            output.push_str(&format!("\n  {}: {}", level, issue));
+        }
+    }
+    output
+}
@@ -1,76 +1,1393 @@
 // CodenameData is defined in `main.rs` and referenced as `crate::CodenameData` where needed.
+use crate::handler::publish_event;
+use colored::Colorize;
+use discordbot::avatar::{
+    AvatarSource, DEFAULT_IMAGE_SIZE, ImageFormat, guild_member_avatar_url, user_avatar_url,
+    user_banner_url,
+};
+use discordbot::events::{FeedEvent, delete_user_events};
+use discordbot::export::{
+    CappedBuffer, ExportFormat, HistoryFilter, export_file_name, export_history_from,
+};
+use discordbot::i18n::{DEFAULT_LOCALE, tr, tr_args};
+use discordbot::permissions::{
+    ALL_COMMANDS, PermissionRule, RuleTarget, evaluate, format_denied_response,
+    format_permission_list_response, list_rules, remove_rule, rules_for_command, set_rule,
+};
+use discordbot::privacy::{anonymize, export_user_data_from, set_opted_out};
+use discordbot::ratelimit::{
+    Invocation, check_rate_limits, format_rate_limits_response, format_slow_down_response,
+    throttle_counts,
+};
+use discordbot::registration::clear_fingerprints;
+use discordbot::registry::{
+    MAX_UNIQUE_ATTEMPTS, canonical_codename, claim_codename, claimed_codenames,
+    format_claim_response, format_codename_list_response, generate_codename_avoiding,
+    list_claimed_codenames, release_codename,
+};
+use discordbot::response::{BotResponse, CODENAME_COLOR};
+use discordbot::retention::prune_store;
+use discordbot::settings::{
+    GuildSettings, SettingKey, format_setting_line, format_settings_response, reset_setting,
+    store_setting,
+};
+use discordbot::stats::{
+    DEFAULT_STATS_DAYS, MAX_STATS_DAYS, format_leaderboard_response, format_stats_response,
+    leaderboard_from, usage_stats_from,
+};
+use discordbot::userinfo::{UserInfo, userinfo_response};
 use discordbot::{
-    BotError, Context, FeedItem, format_codename_response, format_register_response,
-    log_command_usage,
+    BotError, BotState, CommandStatus, Context, FeedItem, HistoryEntry, MAX_CODENAME_BATCH,
+    avatar_response, codename_response, format_codename_batch_response,
+    format_favorite_codename_response, format_given_codename_response, format_register_response,
+    format_reload_response, generate_codename_batch, generate_codename_seeded,
+    history_logging_enabled, log_command_usage, log_history_entry, log_history_entry_at,
+    reload_codename_data, settings_for_guild, user_opted_out,
 };
+use fluent::fluent_args;
+use poise::CreateReply;
 use poise::serenity_prelude as serenity;
 use serenity::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
 
-/// Helper to send a text response and log it to the DB and broadcast to WebSocket clients.
-async fn send_and_log(ctx: Context<'_>, response: String) -> Result<(), BotError> {
-    ctx.say(response.clone()).await?;
-    let data = ctx.data();
-    let command_name = ctx.command().name.to_string();
+/// Helper to send a response as an embed, log its text fallback to the DB and broadcast
+/// it to WebSocket clients. Plain strings become description-only embeds.
+async fn send_and_log(ctx: Context<'_>, response: impl Into<BotResponse>) -> Result<(), BotError> {
+    let response = response.into();
+    let ephemeral = guild_settings(ctx).await?.ephemeral_replies;
+    ctx.send(
+        CreateReply::default()
+            .embed(response.to_embed())
+            .ephemeral(ephemeral),
+    )
+    .await?;
+    log_and_broadcast(ctx, response.to_text()).await;
+    Ok(())
+}
+
+/// Logs a response to the DB and broadcasts it to WebSocket clients without sending it,
+/// for commands that reply with something other than plain text. The guild's
+/// `history_logging` and `feed_visible` settings can turn off either step. Users who
+/// opted out are never logged and show up anonymized in the feed.
+async fn log_and_broadcast(ctx: Context<'_>, response: String) {
+    let command_name = ctx.command().qualified_name.to_string();
     let author_id = ctx.author().id.to_string();
     let author_name = ctx.author().name.clone();
+    let settings = guild_settings(ctx).await.unwrap_or_default();
+    let opted_out = author_opted_out(ctx).await;
+    // The stored row and the feed item share the timestamp clients resume the feed from
+    let now = chrono::Utc::now();
 
     // Log to database
-    log_command_usage(&data.db_path, &ctx, &command_name, &response).await;
+    if settings.history_logging && !opted_out {
+        log_command_usage(&ctx, &command_name, &response, now).await;
+    }
+    if !settings.feed_visible {
+        return;
+    }
 
     // Broadcast to WebSocket clients
     let feed_item = FeedItem {
         item_uuid: uuid::Uuid::new_v4().to_string(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
+        timestamp: now.to_rfc3339(),
         author_id,
         author_name,
         command_name,
         command_output: response,
         test_item: false,
     };
-    crate::websocket::broadcast_command_usage(feed_item);
+    let feed_item = if opted_out {
+        anonymize(feed_item)
+    } else {
+        feed_item
+    };
+    crate::websocket::broadcast_event(FeedEvent::CommandExecuted(feed_item));
+}
 
+/// Whether the invoking user opted out with `/privacy optout`
+async fn author_opted_out(ctx: Context<'_>) -> bool {
+    user_opted_out(ctx.data(), &ctx.author().id.to_string()).await
+}
+
+/// Sends an ephemeral reply without logging or broadcasting it
+async fn reply_privately(
+    ctx: Context<'_>,
+    response: impl Into<BotResponse>,
+) -> Result<(), BotError> {
+    ctx.send(
+        CreateReply::default()
+            .embed(response.into().to_embed())
+            .ephemeral(true),
+    )
+    .await?;
     Ok(())
 }
 
-/// Registers application commands on discord
-#[poise::command(slash_command)]
+/// Settings of the guild the command runs in, from the cache or the database.
+/// Outside guilds this is `GuildSettings::default()`.
+async fn guild_settings(ctx: Context<'_>) -> Result<Arc<GuildSettings>, BotError> {
+    match ctx.guild_id() {
+        Some(guild_id) => settings_for_guild(ctx.data(), &guild_id.to_string()).await,
+        None => Ok(Arc::default()),
+    }
+}
+
+/// Locale for replies: the guild's `locale` setting when one is picked, else the
+/// invoking user's Discord locale, else `DEFAULT_LOCALE`
+async fn locale(ctx: Context<'_>) -> String {
+    let guild_locale = guild_settings(ctx)
+        .await
+        .ok()
+        .and_then(|settings| settings.locale.clone());
+    guild_locale
+        .or_else(|| ctx.locale().map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// Framework-wide check run before any command: the guild's `/perms` rules first, then
+/// the configured rate limits. Administrators bypass both.
+pub async fn command_check(ctx: Context<'_>) -> Result<bool, BotError> {
+    if author_permissions(ctx).is_some_and(|p| p.administrator()) {
+        return Ok(true);
+    }
+    Ok(permission_check(ctx).await? && rate_limit_check(ctx).await?)
+}
+
+/// Records commands that returned an error in the history with the error status, so
+/// they count towards the error rates, then reports the error as poise does by default
+pub async fn on_error(error: poise::FrameworkError<'_, BotState, BotError>) {
+    if let poise::FrameworkError::Command { error: e, ctx, .. } = &error {
+        let now = chrono::Utc::now();
+        let guild_id = ctx.guild_id().map(|id| id.to_string());
+        let author_id = ctx.author().id.to_string();
+        if history_logging_enabled(ctx.data(), guild_id.as_deref(), &author_id).await {
+            let entry = HistoryEntry::from_context(ctx, &e.to_string(), CommandStatus::Error);
+            log_history_entry_at(&ctx.data().history_writer, entry, now).await;
+        }
+        let failed = FeedEvent::CommandFailed(FeedItem {
+            item_uuid: uuid::Uuid::new_v4().to_string(),
+            timestamp: now.to_rfc3339(),
+            author_id,
+            author_name: ctx.author().name.clone(),
+            command_name: ctx.command().qualified_name.clone(),
+            command_output: e.to_string(),
+            test_item: false,
+        });
+        publish_event(ctx.data(), guild_id.as_deref(), failed).await;
+    }
+    if let Err(e) = poise::builtins::on_error(error).await {
+        println!("{}", format!("Failed to report an error: {}", e).red());
+    }
+}
+
+/// Enforces the guild's `/perms` rules. DMs, `/perms` and `/privacy` are never gated.
+/// Denied attempts get an ephemeral reply and are recorded in history with the denied
+/// status, unless the guild turned `history_logging` off.
+async fn permission_check(ctx: Context<'_>) -> Result<bool, BotError> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+    let qualified_name = ctx.command().qualified_name.clone();
+    let exempt = ["perms", "privacy"];
+    if exempt
+        .iter()
+        .any(|name| qualified_name == *name || qualified_name.starts_with(&format!("{} ", name)))
+    {
+        return Ok(true);
+    }
+    let db_path = ctx.data().db_path.clone();
+    let guild = guild_id.to_string();
+    let rule_name = qualified_name.clone();
+    let rules =
+        tokio::task::spawn_blocking(move || rules_for_command(&db_path, &guild, &rule_name))
+            .await??;
+    if rules.is_empty() {
+        return Ok(true);
+    }
+    // @everyone shares the guild's ID and is never listed in a member's roles
+    let mut role_ids = vec![guild_id.to_string()];
+    if let Some(member) = ctx.author_member().await {
+        role_ids.extend(member.roles.iter().map(|role| role.to_string()));
+    }
+    let allowed = evaluate(
+        &rules,
+        &ctx.author().id.to_string(),
+        &role_ids,
+        &ctx.channel_id().to_string(),
+    );
+    if !allowed {
+        let response = format_denied_response(&locale(ctx).await, &qualified_name);
+        ctx.send(
+            CreateReply::default()
+                .embed(BotResponse::new(response.clone()).to_embed())
+                .ephemeral(true),
+        )
+        .await?;
+        let author_id = ctx.author().id.to_string();
+        if history_logging_enabled(ctx.data(), Some(&guild_id.to_string()), &author_id).await {
+            let entry = HistoryEntry::from_context(&ctx, &response, CommandStatus::Denied);
+            log_history_entry(&ctx.data().history_writer, entry).await;
+        }
+    }
+    Ok(allowed)
+}
+
+/// Takes a token from every user, channel and guild bucket configured for the command.
+/// Throttled invocations get an ephemeral "slow down" reply and are counted in
+/// `throttle_counts` rather than written to the history.
+async fn rate_limit_check(ctx: Context<'_>) -> Result<bool, BotError> {
+    let rules = ctx.data().config.rate_limits.clone();
+    if rules.is_empty() {
+        return Ok(true);
+    }
+    let db_path = ctx.data().db_path.clone();
+    let qualified_name = ctx.command().qualified_name.clone();
+    let user_id = ctx.author().id.to_string();
+    let channel_id = ctx.channel_id().to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string());
+    let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+    let invocation_name = qualified_name.clone();
+    let throttled = tokio::task::spawn_blocking(move || {
+        let invocation = Invocation {
+            qualified_name: &invocation_name,
+            user_id: &user_id,
+            channel_id: &channel_id,
+            guild_id: guild_id.as_deref(),
+        };
+        check_rate_limits(&db_path, &rules, &invocation, now)
+    })
+    .await??;
+    let Some(throttled) = throttled else {
+        return Ok(true);
+    };
+    let response = format_slow_down_response(&locale(ctx).await, &qualified_name, &throttled);
+    ctx.send(
+        CreateReply::default()
+            .embed(BotResponse::new(response).to_embed())
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(false)
+}
+
+/// Registers application commands on discord (owner use)
+#[poise::command(slash_command, owners_only)]
 pub async fn register(ctx: Context<'_>) -> Result<(), BotError> {
     poise::builtins::register_application_commands_buttons(ctx).await?;
-    let response = format_register_response();
+    // Manual changes invalidate the stored fingerprints; re-register on next startup
+    let db_path = ctx.data().db_path.clone();
+    tokio::task::spawn_blocking(move || clear_fingerprints(&db_path)).await??;
+    let response = format_register_response(&locale(ctx).await);
     send_and_log(ctx, response).await?;
     Ok(())
 }
 
+/// Codename generation and the per-server codename registry
+#[poise::command(
+    slash_command,
+    subcommands("generate", "claim", "list", "release"),
+    subcommand_required
+)]
+pub async fn codename(_ctx: Context<'_>) -> Result<(), BotError> {
+    Ok(())
+}
+
 /// Generates and displays a random codename
 #[poise::command(
     slash_command,
     description_localized("en-US", "Generates a random codename")
 )]
-pub async fn codename(ctx: Context<'_>) -> Result<(), BotError> {
+pub async fn generate(
+    ctx: Context<'_>,
+    #[description = "Re-roll until the codename is not claimed in this server"] unique: Option<
+        bool,
+    >,
+    #[description = "How many codenames to brainstorm (up to 10)"]
+    #[min = 1]
+    #[max = 10]
+    count: Option<u8>,
+) -> Result<(), BotError> {
     let codename_data = crate::CODENAME_DATA
         .get()
         .expect("Codename data not initialized");
-    let codename = generate_codename(codename_data)?;
-    let response = format_codename_response(&codename);
+    let taken = match (unique.unwrap_or(false), ctx.guild_id()) {
+        (true, Some(guild_id)) => {
+            let db_path = ctx.data().db_path.clone();
+            let guild_id = guild_id.to_string();
+            tokio::task::spawn_blocking(move || claimed_codenames(&db_path, &guild_id)).await??
+        }
+        _ => HashSet::new(),
+    };
+    let count = count.map_or(1, usize::from).clamp(1, MAX_CODENAME_BATCH);
+    if count > 1 {
+        return codename_batch_session(ctx, taken, count).await;
+    }
+    let codename = if taken.is_empty() {
+        generate_codename(&codename_data)?
+    } else {
+        generate_codename_avoiding(&codename_data, &taken, MAX_UNIQUE_ATTEMPTS)?
+    };
+    let style = guild_settings(ctx).await?.codename_style;
+    let response = codename_response(&locale(ctx).await, &style.apply(&codename));
     send_and_log(ctx, response).await?;
     Ok(())
 }
 
-/// Displays the avatar URL of the specified user
-#[poise::command(prefix_command, slash_command)]
-pub async fn avatar(ctx: Context<'_>, user: serenity::User, mention: bool) -> Result<(), BotError> {
-    let url = user
-        .avatar_url()
-        .unwrap_or_else(|| user.default_avatar_url());
-    if mention {
-        let response = format!("{}'s avatar: {}", user.mention(), url);
+/// A button press on a codename batch message
+enum BatchAction {
+    Reroll(usize),
+    Pick(usize),
+    RerollAll,
+}
+
+/// Parses the part of a batch button's custom id that follows the session prefix
+fn parse_batch_action(action: &str) -> Option<BatchAction> {
+    match action.split_once(':') {
+        Some(("reroll", index)) => index.parse().ok().map(BatchAction::Reroll),
+        Some(("pick", index)) => index.parse().ok().map(BatchAction::Pick),
+        None if action == "reroll_all" => Some(BatchAction::RerollAll),
+        _ => None,
+    }
+}
+
+fn codename_batch_response(
+    locale: &str,
+    codenames: &[String],
+    favorite: Option<usize>,
+) -> BotResponse {
+    let list = codenames
+        .iter()
+        .enumerate()
+        .map(|(index, codename)| {
+            if favorite == Some(index) {
+                format!("{}. **{}** ⭐", index + 1, codename)
+            } else {
+                format!("{}. {}", index + 1, codename)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (title, footer) = match favorite {
+        Some(index) => (
+            tr_args(
+                locale,
+                "codename-batch-favorite-title",
+                &fluent_args!["codename" => codenames[index].as_str()],
+            ),
+            tr(locale, "codename-batch-favorite-footer"),
+        ),
+        None => (
+            tr(locale, "codename-batch-title"),
+            tr(locale, "codename-batch-footer"),
+        ),
+    };
+    BotResponse::new(list)
+        .title(title)
+        .color(CODENAME_COLOR)
+        .footer(footer)
+}
+
+fn codename_batch_buttons(
+    locale: &str,
+    prefix: &str,
+    count: usize,
+) -> Vec<serenity::CreateActionRow> {
+    let buttons = |action: &str, emoji: char, style: serenity::ButtonStyle| {
+        (0..count)
+            .map(|index| {
+                serenity::CreateButton::new(format!("{}:{}:{}", prefix, action, index))
+                    .style(style)
+                    .emoji(emoji)
+                    .label((index + 1).to_string())
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut rows: Vec<serenity::CreateActionRow> = Vec::new();
+    for chunk in buttons("reroll", '🎲', serenity::ButtonStyle::Secondary).chunks(5) {
+        rows.push(serenity::CreateActionRow::Buttons(chunk.to_vec()));
+    }
+    for chunk in buttons("pick", '⭐', serenity::ButtonStyle::Success).chunks(5) {
+        rows.push(serenity::CreateActionRow::Buttons(chunk.to_vec()));
+    }
+    rows.push(serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}:reroll_all", prefix))
+            .style(serenity::ButtonStyle::Primary)
+            .label(tr(locale, "codename-batch-reroll-all")),
+    ]));
+    rows
+}
+
+/// Sends a batch of codenames with re-roll and pick buttons, and handles button presses
+/// from the invoking user until a favorite is picked or the buttons time out.
+async fn codename_batch_session(
+    ctx: Context<'_>,
+    taken: HashSet<String>,
+    count: usize,
+) -> Result<(), BotError> {
+    let codename_data = crate::CODENAME_DATA
+        .get()
+        .expect("Codename data not initialized");
+    let mut codenames = generate_codename_batch(&codename_data, count, &taken)?;
+    // Codenames are kept unstyled so the taken/re-roll checks compare like with like
+    let style = guild_settings(ctx).await?.codename_style;
+    let styled = |codenames: &[String]| -> Vec<String> {
+        codenames
+            .iter()
+            .map(|codename| style.apply(codename))
+            .collect()
+    };
+    let prefix = format!("{}:", ctx.id());
+    let locale = locale(ctx).await;
+
+    let reply = CreateReply::default()
+        .ephemeral(guild_settings(ctx).await?.ephemeral_replies)
+        .embed(codename_batch_response(&locale, &styled(&codenames), None).to_embed())
+        .components(codename_batch_buttons(
+            &locale,
+            &ctx.id().to_string(),
+            count,
+        ));
+    let handle = ctx.send(reply).await?;
+    log_and_broadcast(
+        ctx,
+        format_codename_batch_response(&locale, &styled(&codenames)),
+    )
+    .await;
+
+    while let Some(mci) = serenity::ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(std::time::Duration::from_secs(120))
+        .filter({
+            let prefix = prefix.clone();
+            move |mci| mci.data.custom_id.starts_with(&prefix)
+        })
+        .await
+    {
+        let action = parse_batch_action(&mci.data.custom_id[prefix.len()..]);
+        let mut favorite = None;
+        match action {
+            Some(BatchAction::Reroll(index)) if index < codenames.len() => {
+                let mut avoid = taken.clone();
+                avoid.extend(codenames.iter().cloned());
+                codenames[index] =
+                    generate_codename_avoiding(&codename_data, &avoid, MAX_UNIQUE_ATTEMPTS)?;
+            }
+            Some(BatchAction::RerollAll) => {
+                codenames = generate_codename_batch(&codename_data, count, &taken)?;
+            }
+            Some(BatchAction::Pick(index)) if index < codenames.len() => {
+                favorite = Some(index);
+            }
+            _ => {}
+        }
+
+        let message = serenity::CreateInteractionResponseMessage::new()
+            .embed(codename_batch_response(&locale, &styled(&codenames), favorite).to_embed());
+        let message = match favorite {
+            Some(_) => message.components(vec![]),
+            None => message.components(codename_batch_buttons(
+                &locale,
+                &ctx.id().to_string(),
+                count,
+            )),
+        };
+        mci.create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(message),
+        )
+        .await?;
+
+        if let Some(index) = favorite {
+            log_and_broadcast(
+                ctx,
+                format_favorite_codename_response(&locale, &style.apply(&codenames[index])),
+            )
+            .await;
+            return Ok(());
+        }
+    }
+
+    // Timed out without a pick: leave the final list in place but drop the buttons
+    handle
+        .edit(
+            ctx,
+            CreateReply::default()
+                .embed(codename_batch_response(&locale, &styled(&codenames), None).to_embed())
+                .components(vec![]),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Claims a generated codename in this server so nobody else can use it
+#[poise::command(slash_command, guild_only)]
+pub async fn claim(
+    ctx: Context<'_>,
+    #[description = "The generated codename to claim"] codename: String,
+    #[description = "What the codename will be used for"] description: Option<String>,
+    #[description = "Who owns the codename (defaults to you)"] owner: Option<serenity::User>,
+) -> Result<(), BotError> {
+    let codename_data = crate::CODENAME_DATA
+        .get()
+        .expect("Codename data not initialized");
+    let locale = locale(ctx).await;
+    // Claims are stored in the canonical spelling so every style of a name collides
+    let Some(codename) = canonical_codename(&codename_data, &codename) else {
+        let response = tr_args(
+            &locale,
+            "codename-unknown",
+            &fluent_args!["codename" => codename.trim()],
+        );
+        send_and_log(ctx, response).await?;
+        return Ok(());
+    };
+    let owner = owner.unwrap_or_else(|| ctx.author().clone());
+    let description = description.unwrap_or_default();
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let (claim_name, owner_id, owner_name, claim_description) = (
+        codename.clone(),
+        owner.id.to_string(),
+        owner.name.clone(),
+        description.clone(),
+    );
+    let claimed = tokio::task::spawn_blocking(move || {
+        claim_codename(
+            &db_path,
+            &guild_id,
+            &claim_name,
+            &owner_id,
+            &owner_name,
+            &claim_description,
+        )
+    })
+    .await??;
+    let response = if claimed {
+        format_claim_response(&locale, &codename, &owner.name, &description)
+    } else {
+        tr_args(
+            &locale,
+            "codename-already-claimed",
+            &fluent_args!["codename" => codename.as_str()],
+        )
+    };
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Lists the codenames claimed in this server
+#[poise::command(slash_command, guild_only)]
+pub async fn list(ctx: Context<'_>) -> Result<(), BotError> {
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let entries =
+        tokio::task::spawn_blocking(move || list_claimed_codenames(&db_path, &guild_id)).await??;
+    let response = format_codename_list_response(&locale(ctx).await, &entries);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Releases a claimed codename (your own, or any with Manage Server)
+#[poise::command(slash_command, guild_only)]
+pub async fn release(
+    ctx: Context<'_>,
+    #[description = "The claimed codename to release"] codename: String,
+) -> Result<(), BotError> {
+    let can_release_any = author_permissions(ctx).is_some_and(|p| p.manage_guild());
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let owner_id = (!can_release_any).then(|| ctx.author().id.to_string());
+    // Names no longer in the dataset can still be released by their stored spelling
+    let release_name = crate::CODENAME_DATA
+        .get()
+        .and_then(|codename_data| canonical_codename(&codename_data, &codename))
+        .unwrap_or_else(|| codename.trim().to_string());
+    let released = tokio::task::spawn_blocking(move || {
+        release_codename(&db_path, &guild_id, &release_name, owner_id.as_deref())
+    })
+    .await??;
+    let args = fluent_args!["codename" => codename.trim()];
+    let id = if released {
+        "codename-released"
+    } else {
+        "codename-not-yours"
+    };
+    let response = tr_args(&locale(ctx).await, id, &args);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Reloads the codename data file without restarting the bot (owner use)
+#[poise::command(slash_command, owners_only)]
+pub async fn reload(ctx: Context<'_>) -> Result<(), BotError> {
+    let codename_path = ctx.data().codename_path.clone();
+    let locale = locale(ctx).await;
+    let response = match reload_codename_data(&codename_path).await {
+        Ok(codename_data) => format_reload_response(&locale, &codename_data),
+        Err(e) => tr_args(
+            &locale,
+            "codename-reload-failed",
+            &fluent_args!["error" => e.to_string()],
+        ),
+    };
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Shows the most used commands, most active users and daily usage of this server
+#[poise::command(slash_command, guild_only)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "How many days back to look (defaults to 30)"]
+    #[min = 1]
+    #[max = 365]
+    days: Option<u32>,
+) -> Result<(), BotError> {
+    let days = days.unwrap_or(DEFAULT_STATS_DAYS).clamp(1, MAX_STATS_DAYS);
+    let history = ctx.data().history.clone();
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let stats = tokio::task::spawn_blocking(move || {
+        usage_stats_from(&*history, &db_path, &guild_id, days, chrono::Utc::now())
+    })
+    .await??;
+    let response = format_stats_response(&locale(ctx).await, &stats);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Ranks the most active users of this server
+#[poise::command(slash_command, guild_only)]
+pub async fn leaderboard(
+    ctx: Context<'_>,
+    #[description = "How many days back to look (defaults to 30)"]
+    #[min = 1]
+    #[max = 365]
+    days: Option<u32>,
+) -> Result<(), BotError> {
+    let days = days.unwrap_or(DEFAULT_STATS_DAYS).clamp(1, MAX_STATS_DAYS);
+    let history = ctx.data().history.clone();
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let entries = tokio::task::spawn_blocking(move || {
+        leaderboard_from(&*history, &db_path, &guild_id, days, chrono::Utc::now())
+    })
+    .await??;
+    let response = format_leaderboard_response(&locale(ctx).await, days, &entries);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Shows the configured rate limits and how often they were hit (owner use)
+#[poise::command(slash_command, owners_only)]
+pub async fn ratelimits(ctx: Context<'_>) -> Result<(), BotError> {
+    let db_path = ctx.data().db_path.clone();
+    let since = (chrono::Utc::now() - chrono::Duration::days(7))
+        .format("%Y-%m-%d")
+        .to_string();
+    let counts = tokio::task::spawn_blocking(move || throttle_counts(&db_path, &since)).await??;
+    let response =
+        format_rate_limits_response(&locale(ctx).await, &ctx.data().config.rate_limits, &counts);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Command history maintenance (owner use)
+#[poise::command(
+    slash_command,
+    owners_only,
+    subcommands("history_prune", "history_export"),
+    subcommand_required
+)]
+pub async fn history(_ctx: Context<'_>) -> Result<(), BotError> {
+    Ok(())
+}
+
+/// Applies the history retention policy now and reports the rows removed (owner use)
+#[poise::command(slash_command, owners_only, rename = "prune")]
+pub async fn history_prune(ctx: Context<'_>) -> Result<(), BotError> {
+    let db_path = ctx.data().db_path.clone();
+    let history = ctx.data().history.clone();
+    let policy = ctx.data().config.retention;
+    let report = tokio::task::spawn_blocking(move || {
+        prune_store(&*history, &db_path, &policy, chrono::Utc::now())
+    })
+    .await??;
+    let response = tr_args(
+        &locale(ctx).await,
+        "history-pruned",
+        &fluent_args![
+            "total" => report.total(),
+            "expired" => report.expired,
+            "trimmed" => report.trimmed
+        ],
+    );
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Largest export `/history export` attaches; bigger ones need the CLI or the REST endpoint
+const MAX_EXPORT_ATTACHMENT_BYTES: usize = 8 * 1024 * 1024;
+
+/// Exports the command history as a CSV or JSON Lines file (owner use)
+#[poise::command(slash_command, owners_only, rename = "export")]
+pub async fn history_export(
+    ctx: Context<'_>,
+    #[description = "File format (defaults to csv)"] format: Option<ExportFormat>,
+    #[description = "Only the last this many days"]
+    #[min = 1]
+    days: Option<u32>,
+    #[description = "Only this user's commands"] user: Option<serenity::User>,
+    #[description = "Only this command and its subcommands"] command: Option<String>,
+) -> Result<(), BotError> {
+    let format = format.unwrap_or(ExportFormat::Csv);
+    let now = chrono::Utc::now();
+    let filter = HistoryFilter {
+        since: days.map(|days| now - chrono::Duration::days(days as i64)),
+        user_id: user.map(|user| user.id.to_string()),
+        command: command.map(|command| command.trim().trim_start_matches('/').to_string()),
+        ..HistoryFilter::default()
+    };
+    let db_path = ctx.data().db_path.clone();
+    let history = ctx.data().history.clone();
+    // Ok(Err(count)) when the export doesn't fit in an attachment
+    let export = tokio::task::spawn_blocking(move || {
+        let mut file = CappedBuffer::new(MAX_EXPORT_ATTACHMENT_BYTES);
+        match export_history_from(&*history, &db_path, &filter, format, &mut file) {
+            Ok(count) => Ok(Ok((count, file.into_inner()))),
+            Err(_) if file.overflowed() => history.count(&filter).map(Err),
+            Err(e) => Err(e),
+        }
+    })
+    .await??;
+    let locale = locale(ctx).await;
+    let (count, file) = match export {
+        Ok(export) => export,
+        Err(count) => {
+            let response = tr_args(
+                &locale,
+                "history-export-too-large",
+                &fluent_args!["count" => count],
+            );
+            send_and_log(ctx, response).await?;
+            return Ok(());
+        }
+    };
+    let response = tr_args(&locale, "history-exported", &fluent_args!["count" => count]);
+    ctx.send(
+        CreateReply::default()
+            .content(response.clone())
+            .attachment(serenity::CreateAttachment::bytes(
+                file,
+                export_file_name(format, now),
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+    log_and_broadcast(ctx, response).await;
+    Ok(())
+}
+
+/// Per-server bot settings (Manage Server)
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "config",
+    subcommands("config_get", "config_set", "config_reset"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn config_command(_ctx: Context<'_>) -> Result<(), BotError> {
+    Ok(())
+}
+
+async fn autocomplete_setting_key<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = &'static str> + 'a {
+    SettingKey::ALL
+        .into_iter()
+        .map(|key| key.name())
+        .filter(move |name| name.contains(&partial.to_lowercase()))
+}
+
+/// Shows one setting, or all of them
+#[poise::command(slash_command, rename = "get")]
+pub async fn config_get(
+    ctx: Context<'_>,
+    #[description = "Setting to show (defaults to all)"]
+    #[autocomplete = "autocomplete_setting_key"]
+    key: Option<String>,
+) -> Result<(), BotError> {
+    let settings = guild_settings(ctx).await?;
+    let locale = locale(ctx).await;
+    let response = match key.as_deref().map(|name| (name, SettingKey::parse(name))) {
+        None => format_settings_response(&locale, &settings),
+        Some((_, Some(key))) => format_setting_line(&locale, key, &settings),
+        Some((name, None)) => unknown_setting_response(&locale, name),
+    };
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Changes a setting
+#[poise::command(slash_command, rename = "set")]
+pub async fn config_set(
+    ctx: Context<'_>,
+    #[description = "Setting to change"]
+    #[autocomplete = "autocomplete_setting_key"]
+    key: String,
+    #[description = "New value"] value: String,
+) -> Result<(), BotError> {
+    let locale = locale(ctx).await;
+    let Some(key) = SettingKey::parse(&key) else {
+        send_and_log(ctx, unknown_setting_response(&locale, &key)).await?;
+        return Ok(());
+    };
+    let value = match key.validate(&value) {
+        Ok(value) => value,
+        Err(_) => {
+            let response = tr_args(
+                &locale,
+                "config-invalid-value",
+                &fluent_args![
+                    "value" => value.trim(),
+                    "key" => key.name(),
+                    "allowed" => key.allowed_values().join(", ")
+                ],
+            );
+            send_and_log(ctx, response).await?;
+            return Ok(());
+        }
+    };
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let updated_by = ctx.author().id.to_string();
+    let (store_id, stored) = (guild_id.clone(), value.clone());
+    tokio::task::spawn_blocking(move || {
+        store_setting(&db_path, &store_id, key, &stored, &updated_by)
+    })
+    .await??;
+    ctx.data().settings.invalidate(&guild_id);
+    publish_config_change(ctx, &guild_id, key, &value).await;
+    // A new locale setting applies to this reply already
+    let response = tr_args(
+        &self::locale(ctx).await,
+        "config-updated",
+        &fluent_args!["key" => key.name(), "value" => value],
+    );
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Resets a setting to its default
+#[poise::command(slash_command, rename = "reset")]
+pub async fn config_reset(
+    ctx: Context<'_>,
+    #[description = "Setting to reset"]
+    #[autocomplete = "autocomplete_setting_key"]
+    key: String,
+) -> Result<(), BotError> {
+    let Some(key) = SettingKey::parse(&key) else {
+        send_and_log(ctx, unknown_setting_response(&locale(ctx).await, &key)).await?;
+        return Ok(());
+    };
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let reset_id = guild_id.clone();
+    tokio::task::spawn_blocking(move || reset_setting(&db_path, &reset_id, key)).await??;
+    ctx.data().settings.invalidate(&guild_id);
+    let default = key.value_in(&GuildSettings::default());
+    publish_config_change(ctx, &guild_id, key, &default).await;
+    let response = tr_args(
+        &locale(ctx).await,
+        "config-reset",
+        &fluent_args!["key" => key.name(), "value" => default],
+    );
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Publishes a `ConfigChanged` event for a setting that was just stored
+async fn publish_config_change(ctx: Context<'_>, guild_id: &str, key: SettingKey, value: &str) {
+    let event = FeedEvent::ConfigChanged {
+        item_uuid: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        guild_id: guild_id.to_string(),
+        key: key.name().to_string(),
+        value: value.to_string(),
+        changed_by: ctx.author().id.to_string(),
+    };
+    publish_event(ctx.data(), Some(guild_id), event).await;
+}
+
+fn unknown_setting_response(locale: &str, name: &str) -> String {
+    let names: Vec<&str> = SettingKey::ALL.iter().map(|key| key.name()).collect();
+    tr_args(
+        locale,
+        "config-unknown-key",
+        &fluent_args!["key" => name, "keys" => names.join(", ")],
+    )
+}
+
+/// Controls what the bot stores about you
+#[poise::command(
+    slash_command,
+    subcommands("privacy_optout", "privacy_optin", "privacy_export", "privacy_delete"),
+    subcommand_required
+)]
+pub async fn privacy(_ctx: Context<'_>) -> Result<(), BotError> {
+    Ok(())
+}
+
+/// Stops logging your commands and hides your name from the live feed
+#[poise::command(slash_command, rename = "optout")]
+pub async fn privacy_optout(ctx: Context<'_>) -> Result<(), BotError> {
+    set_privacy_opt_out(ctx, true).await
+}
+
+/// Resumes logging your commands and showing your name in the live feed
+#[poise::command(slash_command, rename = "optin")]
+pub async fn privacy_optin(ctx: Context<'_>) -> Result<(), BotError> {
+    set_privacy_opt_out(ctx, false).await
+}
+
+async fn set_privacy_opt_out(ctx: Context<'_>, opted_out: bool) -> Result<(), BotError> {
+    let db_path = ctx.data().db_path.clone();
+    let user_id = ctx.author().id.to_string();
+    let changed =
+        tokio::task::spawn_blocking(move || set_opted_out(&db_path, &user_id, opted_out)).await??;
+    let id = match (opted_out, changed) {
+        (true, true) => "privacy-opted-out",
+        (true, false) => "privacy-already-opted-out",
+        (false, true) => "privacy-opted-in",
+        (false, false) => "privacy-not-opted-out",
+    };
+    reply_privately(ctx, tr(&locale(ctx).await, id)).await
+}
+
+/// Sends you a JSON file of everything the bot has logged about you
+#[poise::command(slash_command, rename = "export")]
+pub async fn privacy_export(ctx: Context<'_>) -> Result<(), BotError> {
+    // Commands still queued in the history writer belong in the export
+    ctx.data().history_writer.flush().await;
+    let db_path = ctx.data().db_path.clone();
+    let history = ctx.data().history.clone();
+    let user_id = ctx.author().id.to_string();
+    let export =
+        tokio::task::spawn_blocking(move || export_user_data_from(&*history, &db_path, &user_id))
+            .await??;
+    let locale = locale(ctx).await;
+    let rows = export.history.len();
+    let file = serenity::CreateAttachment::bytes(
+        serde_json::to_vec_pretty(&export)?,
+        format!("history-{}.json", export.user_id),
+    );
+    let message = serenity::CreateMessage::new()
+        .content(tr_args(
+            &locale,
+            "privacy-export-dm",
+            &fluent_args!["count" => rows],
+        ))
+        .add_file(file);
+    let id = match ctx.author().direct_message(ctx, message).await {
+        Ok(_) => "privacy-export-sent",
+        Err(_) => "privacy-export-failed",
+    };
+    reply_privately(ctx, tr(&locale, id)).await
+}
+
+/// Erases every command the bot has logged for you
+#[poise::command(slash_command, rename = "delete")]
+pub async fn privacy_delete(ctx: Context<'_>) -> Result<(), BotError> {
+    // Queued commands would otherwise be written after the delete and come back
+    ctx.data().history_writer.flush().await;
+    let history = ctx.data().history.clone();
+    let db_path = ctx.data().db_path.clone();
+    let user_id = ctx.author().id.to_string();
+    // Feed events that name the user go too, but the reply only counts commands
+    let removed = tokio::task::spawn_blocking(move || {
+        delete_user_events(&db_path, &user_id)
+            .map_err(|e| format!("Failed to delete feed events: {}", e))?;
+        history.delete_user(&user_id)
+    })
+    .await??;
+    let response = tr_args(
+        &locale(ctx).await,
+        "privacy-deleted",
+        &fluent_args!["count" => removed],
+    );
+    reply_privately(ctx, response).await
+}
+
+/// Per-server command permissions (Manage Server)
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("perms_allow", "perms_deny", "perms_remove", "perms_list"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn perms(_ctx: Context<'_>) -> Result<(), BotError> {
+    Ok(())
+}
+
+/// Allows a role, user or channel to use a command
+#[poise::command(slash_command, rename = "allow")]
+pub async fn perms_allow(
+    ctx: Context<'_>,
+    #[description = "Command name, e.g. \"codename claim\", or * for every command"]
+    command: String,
+    #[description = "Role to allow"] role: Option<serenity::Role>,
+    #[description = "User to allow"] user: Option<serenity::User>,
+    #[description = "Channel the command may be used in"] channel: Option<serenity::Channel>,
+) -> Result<(), BotError> {
+    set_permission(ctx, command, role, user, channel, true).await
+}
+
+/// Denies a role, user or channel the use of a command
+#[poise::command(slash_command, rename = "deny")]
+pub async fn perms_deny(
+    ctx: Context<'_>,
+    #[description = "Command name, e.g. \"codename claim\", or * for every command"]
+    command: String,
+    #[description = "Role to deny"] role: Option<serenity::Role>,
+    #[description = "User to deny"] user: Option<serenity::User>,
+    #[description = "Channel the command may not be used in"] channel: Option<serenity::Channel>,
+) -> Result<(), BotError> {
+    set_permission(ctx, command, role, user, channel, false).await
+}
+
+/// Removes the allow or deny rule for a role, user or channel
+#[poise::command(slash_command, rename = "remove")]
+pub async fn perms_remove(
+    ctx: Context<'_>,
+    #[description = "Command name the rule was set for"] command: String,
+    #[description = "Role of the rule"] role: Option<serenity::Role>,
+    #[description = "User of the rule"] user: Option<serenity::User>,
+    #[description = "Channel of the rule"] channel: Option<serenity::Channel>,
+) -> Result<(), BotError> {
+    let command = command.trim().to_string();
+    let locale = locale(ctx).await;
+    let Some((target, target_id)) = rule_target(role, user, channel) else {
+        send_and_log(ctx, tr(&locale, "perms-pick-one-target")).await?;
+        return Ok(());
+    };
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let remove_command = command.clone();
+    let remove_id = target_id.clone();
+    let removed = tokio::task::spawn_blocking(move || {
+        remove_rule(&db_path, &guild_id, &remove_command, target, &remove_id)
+    })
+    .await??;
+    let args = fluent_args![
+        "command" => command,
+        "target" => target.mention(&target_id)
+    ];
+    let id = if removed {
+        "perms-removed"
+    } else {
+        "perms-no-rule"
+    };
+    let response = tr_args(&locale, id, &args);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Lists the permission rules set in this server
+#[poise::command(slash_command, rename = "list")]
+pub async fn perms_list(ctx: Context<'_>) -> Result<(), BotError> {
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let rules = tokio::task::spawn_blocking(move || list_rules(&db_path, &guild_id)).await??;
+    send_and_log(
+        ctx,
+        format_permission_list_response(&locale(ctx).await, &rules),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn set_permission(
+    ctx: Context<'_>,
+    command: String,
+    role: Option<serenity::Role>,
+    user: Option<serenity::User>,
+    channel: Option<serenity::Channel>,
+    allow: bool,
+) -> Result<(), BotError> {
+    let command = command.trim().trim_start_matches('/').to_string();
+    let locale = locale(ctx).await;
+    if command != ALL_COMMANDS && !command_names(ctx).contains(&command) {
+        let response = tr_args(
+            &locale,
+            "perms-unknown-command",
+            &fluent_args!["command" => command],
+        );
         send_and_log(ctx, response).await?;
+        return Ok(());
+    }
+    let Some((target, target_id)) = rule_target(role, user, channel) else {
+        send_and_log(ctx, tr(&locale, "perms-pick-one-target")).await?;
+        return Ok(());
+    };
+    let rule = PermissionRule {
+        command,
+        target,
+        target_id,
+        allow,
+    };
+    let db_path = ctx.data().db_path.clone();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let created_by = ctx.author().id.to_string();
+    let stored = rule.clone();
+    tokio::task::spawn_blocking(move || set_rule(&db_path, &guild_id, &stored, &created_by))
+        .await??;
+    let id = if allow {
+        "perms-now-allowed"
     } else {
-        send_and_log(ctx, url).await?;
+        "perms-now-denied"
+    };
+    let response = tr_args(
+        &locale,
+        id,
+        &fluent_args![
+            "command" => rule.command.as_str(),
+            "target" => rule.target.mention(&rule.target_id)
+        ],
+    );
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// The single target given to a `/perms` subcommand, or None unless exactly one was
+fn rule_target(
+    role: Option<serenity::Role>,
+    user: Option<serenity::User>,
+    channel: Option<serenity::Channel>,
+) -> Option<(RuleTarget, String)> {
+    match (role, user, channel) {
+        (Some(role), None, None) => Some((RuleTarget::Role, role.id.to_string())),
+        (None, Some(user), None) => Some((RuleTarget::User, user.id.to_string())),
+        (None, None, Some(channel)) => Some((RuleTarget::Channel, channel.id().to_string())),
+        _ => None,
     }
+}
+
+/// Qualified names of every registered command and subcommand
+fn command_names(ctx: Context<'_>) -> HashSet<String> {
+    fn collect(commands: &[poise::Command<BotState, BotError>], names: &mut HashSet<String>) {
+        for command in commands {
+            names.insert(command.qualified_name.clone());
+            collect(&command.subcommands, names);
+        }
+    }
+    let mut names = HashSet::new();
+    collect(&ctx.framework().options().commands, &mut names);
+    names
+}
+
+/// Displays the avatar of the specified user
+#[poise::command(prefix_command, slash_command)]
+pub async fn avatar(
+    ctx: Context<'_>,
+    #[description = "Whose avatar to show"] user: serenity::User,
+    #[description = "Mention the user in the reply"] mention: Option<bool>,
+    #[description = "Image size in pixels (16-4096, rounded to a power of two)"]
+    #[min = 16]
+    #[max = 4096]
+    size: Option<u16>,
+    #[description = "Image format"] format: Option<ImageFormat>,
+    #[description = "Global avatar or this server's avatar"] source: Option<AvatarSource>,
+    #[description = "Also show the user's profile banner"] banner: Option<bool>,
+) -> Result<(), BotError> {
+    send_avatar(ctx, user, mention, size, format, source, banner).await
+}
+
+/// User context-menu entry showing the user's avatar with the default options
+#[poise::command(context_menu_command = "Show avatar")]
+pub async fn avatar_context_menu(ctx: Context<'_>, user: serenity::User) -> Result<(), BotError> {
+    send_avatar(ctx, user, None, None, None, None, None).await
+}
+
+async fn send_avatar(
+    ctx: Context<'_>,
+    user: serenity::User,
+    mention: Option<bool>,
+    size: Option<u16>,
+    format: Option<ImageFormat>,
+    source: Option<AvatarSource>,
+    banner: Option<bool>,
+) -> Result<(), BotError> {
+    let size = size.unwrap_or(DEFAULT_IMAGE_SIZE);
+    let global_url = || match &user.avatar {
+        Some(hash) => user_avatar_url(user.id.get(), &hash.to_string(), format, size),
+        None => user.default_avatar_url(),
+    };
+    let url = match (source, ctx.guild_id()) {
+        (Some(AvatarSource::Server), Some(guild_id)) => {
+            let member = guild_id.member(ctx, user.id).await.ok();
+            match member.and_then(|member| member.avatar) {
+                Some(hash) => guild_member_avatar_url(
+                    guild_id.get(),
+                    user.id.get(),
+                    &hash.to_string(),
+                    format,
+                    size,
+                ),
+                None => global_url(),
+            }
+        }
+        _ => global_url(),
+    };
+    // Banners are only included in the full user object, not the one resolved for options
+    let banner_url = if banner.unwrap_or(false) {
+        let full_user = ctx.http().get_user(user.id).await?;
+        full_user
+            .banner
+            .map(|hash| user_banner_url(user.id.get(), &hash.to_string(), format, size))
+    } else {
+        None
+    };
+    let mention = mention.unwrap_or(false).then(|| user.mention().to_string());
+    let locale = locale(ctx).await;
+    let mut response = avatar_response(
+        &locale,
+        &user.name,
+        mention.as_deref(),
+        &url,
+        banner_url.as_deref(),
+    );
+    if banner.unwrap_or(false) && banner_url.is_none() {
+        response = response.footer(tr_args(
+            &locale,
+            "avatar-no-banner",
+            &fluent_args!["name" => user.name.as_str()],
+        ));
+    }
+    send_and_log(ctx, response).await?;
     Ok(())
 }
 
+/// User context-menu entry that generates a codename for the selected user
+#[poise::command(context_menu_command = "Give codename")]
+pub async fn give_codename(ctx: Context<'_>, user: serenity::User) -> Result<(), BotError> {
+    let codename_data = crate::CODENAME_DATA
+        .get()
+        .expect("Codename data not initialized");
+    let codename = generate_codename(&codename_data)?;
+    let style = guild_settings(ctx).await?.codename_style;
+    let locale = locale(ctx).await;
+    let response = BotResponse::new(format_given_codename_response(
+        &locale,
+        &user.mention().to_string(),
+        &style.apply(&codename),
+    ))
+    .title(tr(&locale, "codename-title"))
+    .color(CODENAME_COLOR);
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Message context-menu entry that seeds the codename generator with the message text,
+/// so the same message always gets the same codename
+#[poise::command(context_menu_command = "Codename from this message")]
+pub async fn codename_from_message(
+    ctx: Context<'_>,
+    message: serenity::Message,
+) -> Result<(), BotError> {
+    let codename_data = crate::CODENAME_DATA
+        .get()
+        .expect("Codename data not initialized");
+    // Messages without text (attachments, embeds) fall back to their ID as the seed
+    let seed = if message.content.trim().is_empty() {
+        message.id.to_string()
+    } else {
+        message.content.clone()
+    };
+    let codename = generate_codename_seeded(&codename_data, &seed)?;
+    let style = guild_settings(ctx).await?.codename_style;
+    let locale = locale(ctx).await;
+    let response = codename_response(&locale, &style.apply(&codename)).footer(tr_args(
+        &locale,
+        "codename-seeded-footer",
+        &fluent_args!["name" => message.author.name.as_str()],
+    ));
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Shows account age, server join date, roles and command usage of a user
+#[poise::command(prefix_command, slash_command)]
+pub async fn userinfo(
+    ctx: Context<'_>,
+    #[description = "Who to show (defaults to you)"] user: Option<serenity::User>,
+) -> Result<(), BotError> {
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    send_userinfo(ctx, user).await
+}
+
+/// User context-menu variant of `/userinfo`
+#[poise::command(context_menu_command = "User info")]
+pub async fn userinfo_context_menu(ctx: Context<'_>, user: serenity::User) -> Result<(), BotError> {
+    send_userinfo(ctx, user).await
+}
+
+async fn send_userinfo(ctx: Context<'_>, user: serenity::User) -> Result<(), BotError> {
+    let member = match ctx.guild_id() {
+        Some(guild_id) => guild_id.member(ctx, user.id).await.ok(),
+        None => None,
+    };
+    let history = ctx.data().history.clone();
+    let filter = HistoryFilter {
+        user_id: Some(user.id.to_string()),
+        ..HistoryFilter::default()
+    };
+    let commands_run = tokio::task::spawn_blocking(move || history.count(&filter)).await??;
+    let avatar_url = user.face();
+    let info = UserInfo {
+        user_id: user.id.get(),
+        user_name: &user.name,
+        avatar_url: &avatar_url,
+        joined_at: member
+            .as_ref()
+            .and_then(|member| member.joined_at)
+            .and_then(|joined_at| chrono::DateTime::from_timestamp(joined_at.unix_timestamp(), 0)),
+        roles: member
+            .as_ref()
+            .map(|member| {
+                member
+                    .roles
+                    .iter()
+                    .map(|role| role.mention().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        commands_run,
+    };
+    let response = userinfo_response(&locale(ctx).await, &info, chrono::Utc::now());
+    send_and_log(ctx, response).await?;
+    Ok(())
+}
+
+/// Permissions of the invoking member, as resolved by Discord for this interaction.
+/// `None` outside guilds and for prefix commands.
+fn author_permissions(ctx: Context<'_>) -> Option<serenity::Permissions> {
+    match ctx {
+        poise::Context::Application(app_ctx) => app_ctx
+            .interaction
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions),
+        poise::Context::Prefix(_) => None,
+    }
+}
+
 /// Generate a random codename from the provided CodenameData
 fn generate_codename(codename_data: &crate::CodenameData) -> Result<String, String> {
     discordbot::generate_codename(codename_data)
@@ -78,7 +1395,25 @@ fn generate_codename(codename_data: &crate::CodenameData) -> Result<String, Stri
 
 #[cfg(test)]
 mod tests {
-    use crate::commands::generate_codename;
+    use crate::commands::{BatchAction, generate_codename, parse_batch_action};
+
+    #[test]
+    fn test_parse_batch_action() {
+        assert!(matches!(
+            parse_batch_action("reroll:3"),
+            Some(BatchAction::Reroll(3))
+        ));
+        assert!(matches!(
+            parse_batch_action("pick:0"),
+            Some(BatchAction::Pick(0))
+        ));
+        assert!(matches!(
+            parse_batch_action("reroll_all"),
+            Some(BatchAction::RerollAll)
+        ));
+        assert!(parse_batch_action("pick:x").is_none());
+        assert!(parse_batch_action("boop").is_none());
+    }
 
     #[test]
     fn test_codename_file_loaded() {
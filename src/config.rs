@@ -0,0 +1,149 @@
+use crate::backup::BackupConfig;
+use crate::history_writer::WriterConfig;
+use crate::ratelimit::{RateLimitRule, parse_rate_limits};
+use crate::retention::{DEFAULT_PRUNE_INTERVAL_HOURS, RetentionPolicy};
+use crate::store::HistoryBackend;
+
+/// ### Runtime configuration read from environment variables (and `.env` via dotenvy)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BotConfig {
+    /// `DEV_GUILD_IDS`: comma-separated guild IDs. When set, commands are registered in
+    /// these guilds only (instant propagation) instead of globally.
+    pub dev_guild_ids: Vec<u64>,
+    /// `RATE_LIMITS`: token-bucket limits, see `parse_rate_limits` for the format.
+    /// Unset or empty means no limits.
+    pub rate_limits: Vec<RateLimitRule>,
+    /// `HISTORY_MAX_AGE_DAYS` and `HISTORY_MAX_ROWS`: how much command history to keep.
+    /// Unset keeps everything.
+    pub retention: RetentionPolicy,
+    /// `HISTORY_PRUNE_INTERVAL_HOURS`: how often the history is pruned, 0 disables
+    /// pruning. Defaults to `DEFAULT_PRUNE_INTERVAL_HOURS`.
+    pub prune_interval_hours: u64,
+    /// `BACKUP_DIR`, `BACKUP_INTERVAL_HOURS` and `BACKUP_KEEP`: scheduled database
+    /// snapshots, see `BackupConfig` for the defaults
+    pub backup: BackupConfig,
+    /// `API_TOKEN`: bearer token for the web server's history export and metrics
+    /// routes, which are disabled while it is unset. The feed and stats routes need none.
+    pub api_token: Option<String>,
+    /// `HISTORY_BACKEND` (`sqlite`, `postgres` or `memory`) and `DATABASE_URL`: where the
+    /// command history is stored. Defaults to the SQLite database.
+    pub history_backend: HistoryBackend,
+    /// `HISTORY_BATCH_SIZE`, `HISTORY_FLUSH_MS` and `HISTORY_QUEUE_CAPACITY`: how new
+    /// history rows are batched, see `WriterConfig` for the defaults
+    pub history_writer: WriterConfig,
+    /// `FEED_MEMBER_EVENTS`: publish member joins to the feed. Needs the privileged
+    /// Server Members intent enabled in the developer portal. Defaults to false.
+    pub feed_member_events: bool,
+}
+
+impl BotConfig {
+    /// Reads the configuration from the process environment
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Reads the configuration through `lookup`, so tests don't have to touch the
+    /// process environment
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        Ok(BotConfig {
+            dev_guild_ids: parse_id_list("DEV_GUILD_IDS", lookup("DEV_GUILD_IDS"))?,
+            rate_limits: parse_rate_limits(&lookup("RATE_LIMITS").unwrap_or_default())
+                .map_err(|e| format!("RATE_LIMITS: {}", e))?,
+            retention: RetentionPolicy {
+                max_age_days: parse_number("HISTORY_MAX_AGE_DAYS", lookup("HISTORY_MAX_AGE_DAYS"))?,
+                max_rows: parse_number("HISTORY_MAX_ROWS", lookup("HISTORY_MAX_ROWS"))?,
+            },
+            prune_interval_hours: parse_number(
+                "HISTORY_PRUNE_INTERVAL_HOURS",
+                lookup("HISTORY_PRUNE_INTERVAL_HOURS"),
+            )?
+            .unwrap_or(DEFAULT_PRUNE_INTERVAL_HOURS),
+            backup: {
+                let defaults = BackupConfig::default();
+                BackupConfig {
+                    dir: lookup("BACKUP_DIR")
+                        .filter(|dir| !dir.trim().is_empty())
+                        .unwrap_or(defaults.dir),
+                    interval_hours: parse_number(
+                        "BACKUP_INTERVAL_HOURS",
+                        lookup("BACKUP_INTERVAL_HOURS"),
+                    )?
+                    .unwrap_or(defaults.interval_hours),
+                    keep: parse_positive("BACKUP_KEEP", lookup("BACKUP_KEEP"))?
+                        .unwrap_or(defaults.keep),
+                }
+            },
+            api_token: lookup("API_TOKEN")
+                .map(|token| token.trim().to_string())
+                .filter(|token| !token.is_empty()),
+            history_backend: HistoryBackend::parse(
+                lookup("HISTORY_BACKEND").as_deref(),
+                lookup("DATABASE_URL").as_deref(),
+            )?,
+            history_writer: {
+                let defaults = WriterConfig::default();
+                WriterConfig {
+                    batch_size: parse_positive("HISTORY_BATCH_SIZE", lookup("HISTORY_BATCH_SIZE"))?
+                        .unwrap_or(defaults.batch_size),
+                    flush_interval_ms: parse_number(
+                        "HISTORY_FLUSH_MS",
+                        lookup("HISTORY_FLUSH_MS"),
+                    )?
+                    .unwrap_or(defaults.flush_interval_ms),
+                    queue_capacity: parse_positive(
+                        "HISTORY_QUEUE_CAPACITY",
+                        lookup("HISTORY_QUEUE_CAPACITY"),
+                    )?
+                    .unwrap_or(defaults.queue_capacity),
+                }
+            },
+            feed_member_events: parse_flag("FEED_MEMBER_EVENTS", lookup("FEED_MEMBER_EVENTS"))?,
+        })
+    }
+}
+
+/// Parses an optional whole number; blank values count as unset
+fn parse_number<T: std::str::FromStr>(
+    key: &str,
+    value: Option<String>,
+) -> Result<Option<T>, String> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(number) => number
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} is not a whole number: {:?}", key, number)),
+    }
+}
+
+/// Parses an optional count that has to be at least 1
+fn parse_positive(key: &str, value: Option<String>) -> Result<Option<usize>, String> {
+    match parse_number(key, value)? {
+        Some(0) => Err(format!("{} must be at least 1", key)),
+        count => Ok(count),
+    }
+}
+
+/// Parses an optional on/off switch; blank values count as off
+fn parse_flag(key: &str, value: Option<String>) -> Result<bool, String> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(false),
+        Some(flag) if ["true", "1", "yes"].contains(&flag.to_lowercase().as_str()) => Ok(true),
+        Some(flag) if ["false", "0", "no"].contains(&flag.to_lowercase().as_str()) => Ok(false),
+        Some(flag) => Err(format!("{} is not true or false: {:?}", key, flag)),
+    }
+}
+
+/// Parses a comma-separated list of Discord IDs, ignoring blanks
+fn parse_id_list(key: &str, value: Option<String>) -> Result<Vec<u64>, String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<u64>()
+                .map_err(|_| format!("{} contains an invalid ID: {:?}", key, id))
+        })
+        .collect()
+}
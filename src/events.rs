@@ -0,0 +1,336 @@
+use crate::privacy::{ANONYMOUS_NAME, anonymize, opted_out_users};
+use crate::settings::hidden_guilds;
+use crate::store::HistoryStore;
+use crate::{FeedItem, load_feed_items};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Every `type` tag a `FeedEvent` can have
+pub const FEED_EVENT_TYPES: [&str; 8] = [
+    "command_executed",
+    "command_failed",
+    "guild_joined",
+    "guild_left",
+    "member_joined",
+    "shard_connected",
+    "shard_disconnected",
+    "config_changed",
+];
+
+/// Most events sent again to a client resuming the feed
+pub const FEED_REPLAY_LIMIT: usize = 100;
+
+/// ### Something that happened, as shown in the live feed
+/// Serialized with a `type` tag next to the fields. Command events keep the `FeedItem`
+/// fields, so clients that only know `FeedItem` can still read them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedEvent {
+    /// A command ran and replied
+    CommandExecuted(FeedItem),
+    /// A command returned an error, `command_output` holds the error
+    CommandFailed(FeedItem),
+    /// The bot was added to a guild
+    GuildJoined {
+        item_uuid: String,
+        timestamp: String,
+        guild_id: String,
+        guild_name: String,
+        member_count: u64,
+    },
+    /// The bot was kicked or banned from a guild, or the guild was deleted
+    GuildLeft {
+        item_uuid: String,
+        timestamp: String,
+        guild_id: String,
+        /// None when the guild was not cached
+        guild_name: Option<String>,
+    },
+    /// Someone joined a guild the bot is in (needs `FEED_MEMBER_EVENTS`)
+    MemberJoined {
+        item_uuid: String,
+        timestamp: String,
+        guild_id: String,
+        user_id: String,
+        user_name: String,
+    },
+    /// A gateway shard connected or resumed
+    ShardConnected {
+        item_uuid: String,
+        timestamp: String,
+        shard_id: u32,
+    },
+    /// A gateway shard lost its connection
+    ShardDisconnected {
+        item_uuid: String,
+        timestamp: String,
+        shard_id: u32,
+    },
+    /// A guild setting was changed or reset with `/config`
+    ConfigChanged {
+        item_uuid: String,
+        timestamp: String,
+        guild_id: String,
+        key: String,
+        value: String,
+        changed_by: String,
+    },
+}
+
+impl FeedEvent {
+    /// The `type` tag of the event
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FeedEvent::CommandExecuted(_) => "command_executed",
+            FeedEvent::CommandFailed(_) => "command_failed",
+            FeedEvent::GuildJoined { .. } => "guild_joined",
+            FeedEvent::GuildLeft { .. } => "guild_left",
+            FeedEvent::MemberJoined { .. } => "member_joined",
+            FeedEvent::ShardConnected { .. } => "shard_connected",
+            FeedEvent::ShardDisconnected { .. } => "shard_disconnected",
+            FeedEvent::ConfigChanged { .. } => "config_changed",
+        }
+    }
+
+    /// The timestamp parsed, None if it isn't RFC 3339
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(self.timestamp())
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    pub fn timestamp(&self) -> &str {
+        match self {
+            FeedEvent::CommandExecuted(item) | FeedEvent::CommandFailed(item) => &item.timestamp,
+            FeedEvent::GuildJoined { timestamp, .. }
+            | FeedEvent::GuildLeft { timestamp, .. }
+            | FeedEvent::MemberJoined { timestamp, .. }
+            | FeedEvent::ShardConnected { timestamp, .. }
+            | FeedEvent::ShardDisconnected { timestamp, .. }
+            | FeedEvent::ConfigChanged { timestamp, .. } => timestamp,
+        }
+    }
+
+    /// The user the event is about, if any
+    pub fn user_id(&self) -> Option<&str> {
+        match self {
+            FeedEvent::CommandExecuted(item) | FeedEvent::CommandFailed(item) => {
+                Some(&item.author_id)
+            }
+            FeedEvent::MemberJoined { user_id, .. } => Some(user_id),
+            FeedEvent::ConfigChanged { changed_by, .. } => Some(changed_by),
+            _ => None,
+        }
+    }
+
+    /// Command events, which the command history already records
+    pub fn is_command(&self) -> bool {
+        matches!(
+            self,
+            FeedEvent::CommandExecuted(_) | FeedEvent::CommandFailed(_)
+        )
+    }
+
+    /// Removes the user the event is about so opted-out users cannot be identified
+    pub fn anonymized(self) -> FeedEvent {
+        match self {
+            FeedEvent::CommandExecuted(item) => FeedEvent::CommandExecuted(anonymize(item)),
+            FeedEvent::CommandFailed(item) => FeedEvent::CommandFailed(anonymize(item)),
+            FeedEvent::MemberJoined {
+                item_uuid,
+                timestamp,
+                guild_id,
+                ..
+            } => FeedEvent::MemberJoined {
+                item_uuid,
+                timestamp,
+                guild_id,
+                user_id: String::new(),
+                user_name: ANONYMOUS_NAME.to_string(),
+            },
+            FeedEvent::ConfigChanged {
+                item_uuid,
+                timestamp,
+                guild_id,
+                key,
+                value,
+                ..
+            } => FeedEvent::ConfigChanged {
+                item_uuid,
+                timestamp,
+                guild_id,
+                key,
+                value,
+                changed_by: String::new(),
+            },
+            event => event,
+        }
+    }
+}
+
+/// ### Which events a feed client receives
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeedFilter {
+    /// `type` tags to send, every type when empty
+    pub types: Vec<String>,
+}
+
+impl FeedFilter {
+    /// Parses a comma-separated list of event types; unset or blank means all of them
+    pub fn parse(types: Option<&str>) -> Result<Self, String> {
+        let types = types
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| match FEED_EVENT_TYPES.contains(&kind) {
+                true => Ok(kind.to_string()),
+                false => Err(format!(
+                    "Unknown event type {:?}, use {}",
+                    kind,
+                    FEED_EVENT_TYPES.join(", ")
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FeedFilter { types })
+    }
+
+    pub fn matches(&self, event: &FeedEvent) -> bool {
+        self.types.is_empty() || self.types.iter().any(|kind| kind == event.kind())
+    }
+}
+
+/// Stores an event in `feed_events`. `guild_id` is the guild it belongs to, if any.
+pub fn record_event(
+    db_path: &str,
+    guild_id: Option<&str>,
+    event: &FeedEvent,
+) -> rusqlite::Result<()> {
+    let payload = serde_json::to_string(event)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    let conn = Connection::open(db_path)?;
+    conn.execute(
+        "INSERT INTO feed_events (timestamp, kind, guild_id, user_id, payload) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            event.timestamp(),
+            event.kind(),
+            guild_id,
+            event.user_id(),
+            payload
+        ],
+    )?;
+    Ok(())
+}
+
+/// The `limit` most recent stored events, oldest first
+pub fn recent_events(db_path: &str, limit: usize) -> rusqlite::Result<Vec<FeedEvent>> {
+    events_after(db_path, &HashSet::new(), None, limit)
+}
+
+/// The `limit` most recent stored events later than `after`, oldest first. Events of
+/// `hidden_guilds` are left out.
+pub fn events_after(
+    db_path: &str,
+    hidden_guilds: &HashSet<String>,
+    after: Option<DateTime<Utc>>,
+    limit: usize,
+) -> rusqlite::Result<Vec<FeedEvent>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT payload FROM feed_events WHERE (?1 IS NULL OR timestamp > ?1)
+         AND (guild_id IS NULL OR guild_id NOT IN (SELECT value FROM json_each(?2)))
+         ORDER BY timestamp DESC, id DESC LIMIT ?3",
+    )?;
+    let after = after.map(|after| after.to_rfc3339());
+    let hidden = serde_json::to_string(hidden_guilds).unwrap_or_default();
+    let rows = stmt.query_map(rusqlite::params![after, hidden, limit as i64], |row| {
+        let payload: String = row.get(0)?;
+        serde_json::from_str(&payload).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+        })
+    })?;
+    let mut events = rows.collect::<rusqlite::Result<Vec<FeedEvent>>>()?;
+    events.reverse();
+    Ok(events)
+}
+
+/// Erases every stored event about a user and returns how many were removed
+pub fn delete_user_events(db_path: &str, user_id: &str) -> rusqlite::Result<usize> {
+    let conn = Connection::open(db_path)?;
+    conn.execute("DELETE FROM feed_events WHERE user_id = ?1", [user_id])
+}
+
+/// Removes stored events older than `max_age_days` days before `now`, if set. Events of
+/// the guilds in `overrides` expire after that many days instead.
+pub fn prune_events(
+    db_path: &str,
+    max_age_days: Option<u32>,
+    overrides: &HashMap<String, u32>,
+    now: DateTime<Utc>,
+) -> rusqlite::Result<usize> {
+    let cutoff = |days: u32| (now - chrono::Duration::days(days as i64)).to_rfc3339();
+    let mut conn = Connection::open(db_path)?;
+    let tx = conn.transaction()?;
+    let mut removed = 0;
+    for (guild_id, days) in overrides {
+        removed += tx.execute(
+            "DELETE FROM feed_events WHERE guild_id = ?1 AND timestamp < ?2",
+            rusqlite::params![guild_id, cutoff(*days)],
+        )?;
+    }
+    if let Some(days) = max_age_days {
+        let overridden: Vec<&String> = overrides.keys().collect();
+        removed += tx.execute(
+            "DELETE FROM feed_events WHERE timestamp < ?1 AND (guild_id IS NULL
+             OR guild_id NOT IN (SELECT value FROM json_each(?2)))",
+            rusqlite::params![
+                cutoff(days),
+                serde_json::to_string(&overridden).unwrap_or_default()
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(removed)
+}
+
+/// The `x` most recent feed events, oldest first: commands from `history` and the other
+/// events from the SQLite database at `db_path`. Users who opted out are anonymized, and
+/// guilds that turned `feed_visible` off are left out.
+pub fn recent_feed(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    x: i64,
+) -> Result<Vec<FeedEvent>, String> {
+    feed_after(history, db_path, None, x.max(0) as usize)
+}
+
+/// Like `recent_feed`, but only the events later than `after`, for clients that resume
+/// the feed where they left off
+pub fn feed_after(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    after: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Result<Vec<FeedEvent>, String> {
+    let opted_out =
+        opted_out_users(db_path).map_err(|e| format!("Failed to load privacy opt-outs: {}", e))?;
+    let hidden =
+        hidden_guilds(db_path).map_err(|e| format!("Failed to load guild settings: {}", e))?;
+    let mut events: Vec<FeedEvent> =
+        load_feed_items(history, db_path, &hidden, after, limit as i64)?.into();
+    events.extend(
+        events_after(db_path, &hidden, after, limit)
+            .map_err(|e| format!("Failed to load feed events: {}", e))?
+            .into_iter()
+            .map(|event| match event.user_id() {
+                Some(user_id) if opted_out.contains(user_id) => event.anonymized(),
+                _ => event,
+            }),
+    );
+    // Both lists are sorted; the stable sort keeps commands first on equal timestamps
+    events.sort_by(|a, b| a.timestamp().cmp(b.timestamp()));
+    let skip = events.len().saturating_sub(limit);
+    Ok(events.split_off(skip))
+}
@@ -0,0 +1,205 @@
+use crate::privacy::{ANONYMOUS_OUTPUT, opted_out_users};
+use crate::store::{HistoryQuery, HistoryRow, HistoryStore, SqliteHistoryStore};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::io::Write;
+
+/// File format of a history export
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[name = "csv"]
+    Csv,
+    #[name = "jsonl"]
+    JsonLines,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" | "ndjson" => Some(ExportFormat::JsonLines),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonLines => "application/x-ndjson",
+        }
+    }
+}
+
+/// ### Which command_history rows to export
+/// Unset fields don't filter. Denied and failed attempts are only included with
+/// `include_denied`; `include_errors` includes the failed ones alone.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Rows at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Rows before this time
+    pub until: Option<DateTime<Utc>>,
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    /// Qualified command name; a parent name (`codename`) also matches its subcommands
+    pub command: Option<String>,
+    pub include_denied: bool,
+    pub include_errors: bool,
+    /// Rows of these guilds are left out
+    pub exclude_guilds: Vec<String>,
+}
+
+/// Parses a `since`/`until` bound: an RFC 3339 timestamp, or a `YYYY-MM-DD` date meaning
+/// midnight UTC at the start of that day
+pub fn parse_time_bound(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("{:?} is not a YYYY-MM-DD date or RFC 3339 timestamp", value))
+}
+
+const CSV_HEADER: &str = "id,timestamp,user_id,username,guild_id,command,output,status";
+
+/// Quotes a CSV field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(row: &HistoryRow) -> String {
+    [
+        row.id.to_string(),
+        csv_field(&row.timestamp),
+        csv_field(&row.user_id),
+        csv_field(&row.username),
+        csv_field(row.guild_id.as_deref().unwrap_or_default()),
+        csv_field(&row.command),
+        csv_field(&row.output),
+        csv_field(&row.status),
+    ]
+    .join(",")
+}
+
+/// ### An in-memory export that stops at `limit` bytes
+/// Writes past the limit fail, so an export too large to use stops early instead of
+/// being buffered in full.
+pub struct CappedBuffer {
+    bytes: Vec<u8>,
+    limit: usize,
+    overflowed: bool,
+}
+
+impl CappedBuffer {
+    pub fn new(limit: usize) -> Self {
+        CappedBuffer {
+            bytes: Vec::new(),
+            limit,
+            overflowed: false,
+        }
+    }
+
+    /// Whether a write was refused because it would have gone past the limit
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl Write for CappedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.bytes.len() + buf.len() > self.limit {
+            self.overflowed = true;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::FileTooLarge,
+                format!("export exceeds {} bytes", self.limit),
+            ));
+        }
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the rows of the SQLite history at `db_path` that match `filter` to `writer`
+/// (see `export_history_from`)
+pub fn export_history<W: Write>(
+    db_path: &str,
+    filter: &HistoryFilter,
+    format: ExportFormat,
+    writer: &mut W,
+) -> Result<usize, String> {
+    export_history_from(
+        &SqliteHistoryStore::new(db_path),
+        db_path,
+        filter,
+        format,
+        writer,
+    )
+}
+
+/// Writes the rows of `history` matching `filter` to `writer`, oldest first, one row at
+/// a time so exports of any size run in constant memory. Authors who opted out with
+/// `/privacy optout` (according to the SQLite database at `db_path`) have their ID and
+/// name left empty and their output hidden. Returns the number of rows written.
+pub fn export_history_from<W: Write>(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    filter: &HistoryFilter,
+    format: ExportFormat,
+    writer: &mut W,
+) -> Result<usize, String> {
+    let opted_out =
+        opted_out_users(db_path).map_err(|e| format!("Failed to load privacy opt-outs: {}", e))?;
+    let write_error = |e: std::io::Error| format!("Failed to write export: {}", e);
+    if format == ExportFormat::Csv {
+        writeln!(writer, "{}", CSV_HEADER).map_err(write_error)?;
+    }
+    let query = HistoryQuery {
+        filter: filter.clone(),
+        ..HistoryQuery::default()
+    };
+    let count = history.query(&query, &mut |mut row| {
+        if opted_out.contains(&row.user_id) {
+            row.user_id.clear();
+            row.username.clear();
+            row.output = ANONYMOUS_OUTPUT.to_string();
+        }
+        match format {
+            ExportFormat::Csv => writeln!(writer, "{}", csv_line(&row)).map_err(write_error),
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut *writer, &row)
+                    .map_err(|e| format!("Failed to write export: {}", e))?;
+                writeln!(writer).map_err(write_error)
+            }
+        }
+    })?;
+    writer.flush().map_err(write_error)?;
+    Ok(count)
+}
+
+/// File name for an export taken at `now`, e.g. `history-20250102T030405Z.csv`
+pub fn export_file_name(format: ExportFormat, now: DateTime<Utc>) -> String {
+    format!(
+        "history-{}.{}",
+        now.format("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    )
+}
@@ -0,0 +1,106 @@
+use colored::Colorize;
+use discordbot::events::{FeedEvent, record_event};
+use discordbot::{BotError, BotState, settings_for_guild, user_opted_out};
+use poise::serenity_prelude as serenity;
+use serenity::{ConnectionStage, FullEvent};
+
+/// Gateway event handler: turns guild, member and shard events into feed events.
+/// Member joins only arrive when `FEED_MEMBER_EVENTS` enabled the members intent.
+pub async fn event_handler(
+    _ctx: &serenity::Context,
+    event: &FullEvent,
+    _framework: poise::FrameworkContext<'_, BotState, BotError>,
+    data: &BotState,
+) -> Result<(), BotError> {
+    let item_uuid = uuid::Uuid::new_v4().to_string();
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let (guild_id, event) = match event {
+        // is_new is only Some(true) for guilds added while the bot is running
+        FullEvent::GuildCreate {
+            guild,
+            is_new: Some(true),
+        } => (
+            Some(guild.id.to_string()),
+            FeedEvent::GuildJoined {
+                item_uuid,
+                timestamp,
+                guild_id: guild.id.to_string(),
+                guild_name: guild.name.clone(),
+                member_count: guild.member_count,
+            },
+        ),
+        // Unavailable guilds are outages, not the bot leaving
+        FullEvent::GuildDelete { incomplete, full } if !incomplete.unavailable => (
+            Some(incomplete.id.to_string()),
+            FeedEvent::GuildLeft {
+                item_uuid,
+                timestamp,
+                guild_id: incomplete.id.to_string(),
+                guild_name: full.as_ref().map(|guild| guild.name.clone()),
+            },
+        ),
+        FullEvent::GuildMemberAddition { new_member } => (
+            Some(new_member.guild_id.to_string()),
+            FeedEvent::MemberJoined {
+                item_uuid,
+                timestamp,
+                guild_id: new_member.guild_id.to_string(),
+                user_id: new_member.user.id.to_string(),
+                user_name: new_member.user.name.clone(),
+            },
+        ),
+        FullEvent::ShardStageUpdate { event } => {
+            let shard_id = event.shard_id.0;
+            let event = match event.new {
+                ConnectionStage::Connected => FeedEvent::ShardConnected {
+                    item_uuid,
+                    timestamp,
+                    shard_id,
+                },
+                ConnectionStage::Disconnected => FeedEvent::ShardDisconnected {
+                    item_uuid,
+                    timestamp,
+                    shard_id,
+                },
+                _ => return Ok(()),
+            };
+            (None, event)
+        }
+        _ => return Ok(()),
+    };
+    publish_event(data, guild_id.as_deref(), event).await;
+    Ok(())
+}
+
+/// Broadcasts `event` to WebSocket clients and stores it in `feed_events`. The guild it
+/// belongs to can turn off either step with `feed_visible` and `history_logging`.
+/// Users who opted out show up anonymized. Command events are only broadcast, the
+/// command history already has them.
+pub async fn publish_event(data: &BotState, guild_id: Option<&str>, event: FeedEvent) {
+    let settings = match guild_id {
+        Some(guild_id) => settings_for_guild(data, guild_id).await.unwrap_or_default(),
+        None => Default::default(),
+    };
+    let event = match event.user_id() {
+        Some(user_id) if user_opted_out(data, user_id).await => event.anonymized(),
+        _ => event,
+    };
+    if settings.history_logging && !event.is_command() {
+        let db_path = data.db_path.clone();
+        let guild_id = guild_id.map(str::to_string);
+        let stored = event.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            record_event(&db_path, guild_id.as_deref(), &stored).map_err(|e| e.to_string())
+        })
+        .await;
+        if let Err(e) = result.map_err(|e| e.to_string()).and_then(|result| result) {
+            println!(
+                "{}",
+                format!("Failed to store {} event: {}", event.kind(), e).red()
+            );
+        }
+    }
+    if settings.feed_visible {
+        crate::websocket::broadcast_event(event);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::HistoryEntry;
+use crate::store::HistoryStore;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+
+/// Rows written per transaction when `HISTORY_BATCH_SIZE` is not set
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+/// Longest wait for a batch to fill when `HISTORY_FLUSH_MS` is not set
+pub const DEFAULT_FLUSH_INTERVAL_MS: u64 = 500;
+/// Entries waiting to be written when `HISTORY_QUEUE_CAPACITY` is not set
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1000;
+
+/// ### How the history writer batches its inserts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriterConfig {
+    /// Most rows written in one transaction, at least 1
+    pub batch_size: usize,
+    /// A batch is written this long after its first entry arrived, even when not full
+    pub flush_interval_ms: u64,
+    /// Entries the queue holds; `HistoryWriter::log` waits while it is full
+    pub queue_capacity: usize,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        WriterConfig {
+            batch_size: DEFAULT_BATCH_SIZE,
+            flush_interval_ms: DEFAULT_FLUSH_INTERVAL_MS,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+        }
+    }
+}
+
+enum Message {
+    Entry(HistoryEntry, DateTime<Utc>),
+    /// Write everything queued before this message, then answer
+    Flush(oneshot::Sender<()>),
+}
+
+#[derive(Default)]
+struct Counters {
+    written: AtomicU64,
+    failed: AtomicU64,
+    batches: AtomicU64,
+    full_waits: AtomicU64,
+}
+
+/// ### Queue and throughput figures of a `HistoryWriter`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WriterMetrics {
+    /// Entries waiting to be written right now
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Rows stored since startup
+    pub written: u64,
+    /// Rows lost because their batch could not be stored, or the writer had stopped
+    pub failed: u64,
+    /// Transactions committed since startup
+    pub batches: u64,
+    /// Times `log` found the queue full and had to wait for room
+    pub full_waits: u64,
+}
+
+/// ### Handle to the background task that stores command history
+/// Commands queue their entries and return without waiting for the database; the task
+/// writes them in batches. Clones share the same queue.
+#[derive(Clone)]
+pub struct HistoryWriter {
+    tx: mpsc::Sender<Message>,
+    counters: Arc<Counters>,
+}
+
+impl HistoryWriter {
+    /// Starts the writer task for `history`. Needs a running Tokio runtime.
+    pub fn spawn(history: Arc<dyn HistoryStore>, config: WriterConfig) -> Self {
+        let (tx, rx) = mpsc::channel(config.queue_capacity.max(1));
+        let counters = Arc::new(Counters::default());
+        tokio::spawn(run(history, config, rx, counters.clone()));
+        HistoryWriter { tx, counters }
+    }
+
+    /// Queues `entry`, timestamped now. While the queue is full this waits for room, so
+    /// a database that can't keep up slows commands down instead of losing history.
+    pub async fn log(&self, entry: HistoryEntry) {
+        self.log_at(entry, Utc::now()).await;
+    }
+
+    /// Like `log`, with the timestamp the row is stored with, so a row can share it with
+    /// the feed event broadcast for the same command
+    pub async fn log_at(&self, entry: HistoryEntry, timestamp: DateTime<Utc>) {
+        let message = Message::Entry(entry, timestamp);
+        let stopped = match self.tx.try_send(message) {
+            Ok(()) => false,
+            Err(TrySendError::Full(message)) => {
+                self.counters.full_waits.fetch_add(1, Ordering::Relaxed);
+                self.tx.send(message).await.is_err()
+            }
+            Err(TrySendError::Closed(_)) => true,
+        };
+        if stopped {
+            self.counters.failed.fetch_add(1, Ordering::Relaxed);
+            println!("{}", "History writer stopped, entry dropped".red());
+        }
+    }
+
+    /// Waits until everything queued so far is written (or has failed)
+    pub async fn flush(&self) {
+        let (ack, done) = oneshot::channel();
+        if self.tx.send(Message::Flush(ack)).await.is_ok() {
+            let _ = done.await;
+        }
+    }
+
+    pub fn metrics(&self) -> WriterMetrics {
+        let counter = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        WriterMetrics {
+            queue_depth: self.tx.max_capacity() - self.tx.capacity(),
+            queue_capacity: self.tx.max_capacity(),
+            written: counter(&self.counters.written),
+            failed: counter(&self.counters.failed),
+            batches: counter(&self.counters.batches),
+            full_waits: counter(&self.counters.full_waits),
+        }
+    }
+}
+
+/// Collects entries into batches of up to `batch_size`, each written at most
+/// `flush_interval_ms` after its first entry arrived, until every handle is dropped
+async fn run(
+    history: Arc<dyn HistoryStore>,
+    config: WriterConfig,
+    mut rx: mpsc::Receiver<Message>,
+    counters: Arc<Counters>,
+) {
+    let interval = std::time::Duration::from_millis(config.flush_interval_ms);
+    while let Some(first) = rx.recv().await {
+        let deadline = tokio::time::Instant::now() + interval;
+        let mut batch = Vec::new();
+        let mut flushes = Vec::new();
+        let mut next = Some(first);
+        while let Some(message) = next.take() {
+            match message {
+                Message::Entry(entry, timestamp) => batch.push((entry, timestamp)),
+                Message::Flush(ack) => flushes.push(ack),
+            }
+            if batch.len() >= config.batch_size.max(1) || !flushes.is_empty() {
+                break;
+            }
+            // Ok(None) means every handle is gone; the batch is still written below
+            if let Ok(Some(message)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+                next = Some(message);
+            }
+        }
+        if !batch.is_empty() {
+            write_batch(&history, batch, &counters).await;
+        }
+        for ack in flushes {
+            let _ = ack.send(());
+        }
+    }
+}
+
+async fn write_batch(
+    history: &Arc<dyn HistoryStore>,
+    batch: Vec<(HistoryEntry, DateTime<Utc>)>,
+    counters: &Counters,
+) {
+    let rows = batch.len() as u64;
+    let history = history.clone();
+    let result = tokio::task::spawn_blocking(move || history.insert_batch(&batch)).await;
+    let error = match result {
+        Ok(Ok(())) => {
+            counters.written.fetch_add(rows, Ordering::Relaxed);
+            counters.batches.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        Ok(Err(e)) => e,
+        Err(e) => e.to_string(),
+    };
+    counters.failed.fetch_add(rows, Ordering::Relaxed);
+    println!(
+        "{}",
+        format!("Failed to log {} history entries: {}", rows, error).red()
+    );
+}
@@ -0,0 +1,187 @@
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource};
+use once_cell::sync::Lazy;
+use std::collections::{BTreeSet, HashMap};
+use unic_langid::LanguageIdentifier;
+
+/// Directory holding one sub-directory of `.ftl` files per locale, e.g. `en-US/main.ftl`
+pub const DEFAULT_LOCALES_PATH: &str = "./assets/locales";
+/// Locale used when neither the guild nor the user picked a supported one, and for
+/// messages missing from another locale
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// ### Fluent message catalogs for every locale found under the locales directory
+pub struct Catalog {
+    bundles: HashMap<String, FluentBundle<FluentResource>>,
+    /// Message IDs of each locale, for completeness checks
+    message_ids: HashMap<String, BTreeSet<String>>,
+}
+
+impl Catalog {
+    /// Loads every `<locale>/*.ftl` file below `dir`. Fails on unreadable files, syntax
+    /// errors, duplicate messages and when `DEFAULT_LOCALE` is missing.
+    pub fn load(dir: &str) -> Result<Catalog, String> {
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir, e))?;
+        let mut bundles = HashMap::new();
+        let mut message_ids = HashMap::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to read {}: {}", dir, e))?
+                .path();
+            if !path.is_dir() {
+                continue;
+            }
+            let locale = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let langid: LanguageIdentifier = locale
+                .parse()
+                .map_err(|e| format!("Invalid locale directory {:?}: {}", locale, e))?;
+            let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+            // Replies are Discord markdown, not bidirectional UI text
+            bundle.set_use_isolating(false);
+            let mut ids = BTreeSet::new();
+            let mut files: Vec<_> = std::fs::read_dir(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .filter_map(|file| file.ok().map(|file| file.path()))
+                .filter(|file| file.extension().is_some_and(|ext| ext == "ftl"))
+                .collect();
+            files.sort();
+            for file in files {
+                let source = std::fs::read_to_string(&file)
+                    .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+                let resource = FluentResource::try_new(source).map_err(|(_, errors)| {
+                    format!("Failed to parse {}: {:?}", file.display(), errors)
+                })?;
+                for entry in resource.entries() {
+                    if let fluent_syntax::ast::Entry::Message(message) = entry {
+                        ids.insert(message.id.name.to_string());
+                    }
+                }
+                bundle
+                    .add_resource(resource)
+                    .map_err(|errors| format!("Failed to load {}: {:?}", file.display(), errors))?;
+            }
+            bundles.insert(locale.clone(), bundle);
+            message_ids.insert(locale, ids);
+        }
+        if !bundles.contains_key(DEFAULT_LOCALE) {
+            return Err(format!("{} has no {} catalog", dir, DEFAULT_LOCALE));
+        }
+        Ok(Catalog {
+            bundles,
+            message_ids,
+        })
+    }
+
+    /// Every loaded locale, sorted
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.bundles.keys().map(String::as_str).collect();
+        locales.sort();
+        locales
+    }
+
+    /// Message IDs defined by `locale`
+    pub fn message_ids(&self, locale: &str) -> BTreeSet<String> {
+        self.message_ids.get(locale).cloned().unwrap_or_default()
+    }
+
+    /// The loaded locale to use for a requested one: an exact match, else a locale of the
+    /// same language (`en-GB` uses `en-US`), else `DEFAULT_LOCALE`
+    pub fn resolve(&self, requested: &str) -> &str {
+        if let Some((locale, _)) = self.bundles.get_key_value(requested) {
+            return locale;
+        }
+        let language = requested.split('-').next().unwrap_or_default();
+        self.locales()
+            .into_iter()
+            .find(|locale| locale.split('-').next() == Some(language))
+            .unwrap_or(DEFAULT_LOCALE)
+    }
+
+    /// Formats a message of exactly `locale`, without any fallback
+    pub fn get(&self, locale: &str, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let bundle = self.bundles.get(locale)?;
+        let pattern = bundle.get_message(id)?.value()?;
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, args, &mut errors);
+        Some(text.into_owned())
+    }
+
+    /// Formats a message in the best match for `locale`, falling back to
+    /// `DEFAULT_LOCALE` and finally to the message ID itself
+    pub fn tr(&self, locale: &str, id: &str, args: Option<&FluentArgs>) -> String {
+        self.get(self.resolve(locale), id, args)
+            .or_else(|| self.get(DEFAULT_LOCALE, id, args))
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+/// Catalog loaded from `DEFAULT_LOCALES_PATH` on first use
+pub static CATALOG: Lazy<Catalog> = Lazy::new(|| {
+    Catalog::load(DEFAULT_LOCALES_PATH).unwrap_or_else(|e| panic!("Failed to load locales: {}", e))
+});
+
+/// Formats a message without arguments from the global catalog
+pub fn tr(locale: &str, id: &str) -> String {
+    CATALOG.tr(locale, id, None)
+}
+
+/// Formats a message with arguments from the global catalog,
+/// e.g. `tr_args(locale, "codename-generated", &fluent_args!["codename" => name])`
+pub fn tr_args(locale: &str, id: &str, args: &FluentArgs) -> String {
+    CATALOG.tr(locale, id, Some(args))
+}
+
+/// Message ID prefix of a command: its qualified name in lowercase with dashes,
+/// e.g. `command-codename-generate` or `command-show-avatar`
+pub fn command_message_id(qualified_name: &str) -> String {
+    format!(
+        "command-{}",
+        qualified_name.to_lowercase().replace(' ', "-")
+    )
+}
+
+/// Fills in Discord name and description localizations of `commands`, their
+/// subcommands and parameters from every non-default locale in `catalog`.
+/// - slash commands: `<command id>-description`
+/// - context menus: `<command id>-name`
+/// - parameters: `<command id>-<parameter>-name` and `<command id>-<parameter>-description`
+pub fn localize_commands<U, E>(catalog: &Catalog, commands: &mut [poise::Command<U, E>]) {
+    for command in commands {
+        let id = command_message_id(&command.qualified_name);
+        for locale in catalog.locales() {
+            if locale == DEFAULT_LOCALE {
+                continue;
+            }
+            let localize = |map: &mut HashMap<String, String>, message_id: String| {
+                if let Some(text) = catalog.get(locale, &message_id, None) {
+                    map.insert(locale.to_string(), text);
+                }
+            };
+            if command.context_menu_action.is_some() {
+                localize(&mut command.name_localizations, format!("{}-name", id));
+            } else {
+                localize(
+                    &mut command.description_localizations,
+                    format!("{}-description", id),
+                );
+            }
+            for parameter in &mut command.parameters {
+                let parameter_id = format!("{}-{}", id, parameter.name);
+                localize(
+                    &mut parameter.name_localizations,
+                    format!("{}-name", parameter_id),
+                );
+                localize(
+                    &mut parameter.description_localizations,
+                    format!("{}-description", parameter_id),
+                );
+            }
+        }
+        localize_commands(catalog, &mut command.subcommands);
+    }
+}
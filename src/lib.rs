@@ -1,26 +1,245 @@
 use colored::Colorize;
-use once_cell::sync::OnceCell;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
 
+use fluent::fluent_args;
+use history_writer::HistoryWriter;
+use i18n::{tr, tr_args};
+use response::{BotResponse, CODENAME_COLOR, USER_COLOR};
+use store::{HistoryQuery, HistoryStore, SqliteHistoryStore};
+
+pub mod analytics;
+pub mod avatar;
+pub mod backup;
+pub mod codename_check;
+pub mod config;
+pub mod events;
+pub mod export;
+pub mod history_writer;
+pub mod i18n;
+pub mod permissions;
+pub mod privacy;
+pub mod ratelimit;
+pub mod registration;
+pub mod registry;
+pub mod response;
+pub mod retention;
+pub mod settings;
+pub mod stats;
+pub mod store;
+pub mod userinfo;
 pub mod web;
 pub mod websocket;
 
-pub fn format_register_response() -> String {
-    "Registered application commands".to_string()
+pub fn format_register_response(locale: &str) -> String {
+    tr(locale, "register-done")
+}
+
+pub fn format_codename_response(locale: &str, codename: &str) -> String {
+    tr_args(
+        locale,
+        "codename-generated",
+        &fluent_args!["codename" => codename],
+    )
 }
 
-pub fn format_codename_response(codename: &str) -> String {
-    format!("Your generated codename is:\n **{}!**", codename)
+/// Embed version of `format_codename_response`
+pub fn codename_response(locale: &str, codename: &str) -> BotResponse {
+    BotResponse::new(format_codename_response(locale, codename))
+        .title(tr(locale, "codename-title"))
+        .color(CODENAME_COLOR)
 }
 
-#[derive(serde::Deserialize, Debug)]
+/// Embed showing a user's avatar inline. `mention` is included in the description when set.
+/// With a banner, the banner becomes the main image and the avatar moves to the thumbnail.
+pub fn avatar_response(
+    locale: &str,
+    user_name: &str,
+    mention: Option<&str>,
+    avatar_url: &str,
+    banner_url: Option<&str>,
+) -> BotResponse {
+    let title = |name: &str| tr_args(locale, "avatar-title", &fluent_args!["name" => name]);
+    let response = BotResponse::new(mention.map(title).unwrap_or_default())
+        .title(title(user_name))
+        .color(USER_COLOR);
+    match banner_url {
+        Some(banner_url) => response.thumbnail(avatar_url).image(banner_url),
+        None => response.image(avatar_url),
+    }
+}
+
+pub fn format_given_codename_response(locale: &str, mention: &str, codename: &str) -> String {
+    tr_args(
+        locale,
+        "codename-given",
+        &fluent_args!["mention" => mention, "codename" => codename],
+    )
+}
+
+pub fn format_codename_batch_response(locale: &str, codenames: &[String]) -> String {
+    let mut response = tr(locale, "codename-batch-header");
+    for (index, codename) in codenames.iter().enumerate() {
+        response.push_str(&format!("\n{}. **{}**", index + 1, codename));
+    }
+    response
+}
+
+pub fn format_favorite_codename_response(locale: &str, codename: &str) -> String {
+    tr_args(
+        locale,
+        "codename-favorite",
+        &fluent_args!["codename" => codename],
+    )
+}
+
+pub fn format_reload_response(locale: &str, codename_data: &CodenameData) -> String {
+    tr_args(
+        locale,
+        "codename-reloaded",
+        &fluent_args![
+            "adjectives" => codename_data.adjectives.len(),
+            "animals" => codename_data.animals.len()
+        ],
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CodenameData {
     pub animals: Vec<String>,
     pub adjectives: Vec<String>,
+    /// Words and adjective-animal pairs that must never be generated
+    #[serde(default, skip_serializing_if = "CodenameBlocklist::is_empty")]
+    pub blocklist: CodenameBlocklist,
+}
+
+/// ### Content-safety blocklist loaded from the `blocklist` key of the codename file
+/// `words` are matched against every word of the adjective and the animal (hyphenated
+/// entries are split), `combinations` against exact `[adjective, animal]` pairs.
+/// All matching ignores case.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CodenameBlocklist {
+    #[serde(default)]
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub combinations: Vec<(String, String)>,
+}
+
+impl CodenameBlocklist {
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.combinations.is_empty()
+    }
+
+    /// Returns true if the adjective-animal pair is allowed by the blocklist
+    pub fn allows(&self, adjective: &str, animal: &str) -> bool {
+        let has_blocked_word = [adjective, animal]
+            .iter()
+            .flat_map(|part| part.split(|c: char| c.is_whitespace() || c == '-'))
+            .any(|word| {
+                self.words
+                    .iter()
+                    .any(|blocked| blocked.eq_ignore_ascii_case(word))
+            });
+        let is_blocked_pair = self
+            .combinations
+            .iter()
+            .any(|(blocked_adj, blocked_animal)| {
+                blocked_adj.eq_ignore_ascii_case(adjective)
+                    && blocked_animal.eq_ignore_ascii_case(animal)
+            });
+        !has_blocked_word && !is_blocked_pair
+    }
+}
+
+/// How many times `generate_codename` re-rolls a blocked pair before giving up
+pub const MAX_BLOCKLIST_REROLLS: usize = 100;
+
+impl CodenameData {
+    /// Checks that both word lists are non-empty and contain no blank entries
+    pub fn validate(&self) -> Result<(), String> {
+        for (list_name, list) in [("adjectives", &self.adjectives), ("animals", &self.animals)] {
+            if list.is_empty() {
+                return Err(format!("{} list is empty", list_name));
+            }
+            if let Some(index) = list.iter().position(|entry| entry.trim().is_empty()) {
+                return Err(format!("{} entry {} is blank", list_name, index));
+            }
+        }
+        if self
+            .blocklist
+            .words
+            .iter()
+            .any(|word| word.trim().is_empty())
+        {
+            return Err("blocklist contains a blank word".to_string());
+        }
+        if self
+            .blocklist
+            .combinations
+            .iter()
+            .any(|(adjective, animal)| adjective.trim().is_empty() || animal.trim().is_empty())
+        {
+            return Err("blocklist contains a blank combination".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// ### Atomically replaceable holder for the loaded codename data
+/// Readers get a cheap `Arc` snapshot, so a reload never disturbs a command
+/// that is already generating a codename.
+pub struct CodenameStore {
+    inner: RwLock<Option<Arc<CodenameData>>>,
+}
+
+impl CodenameStore {
+    pub const fn new() -> Self {
+        CodenameStore {
+            inner: RwLock::new(None),
+        }
+    }
+
+    /// Returns the current codename data, or `None` if nothing has been loaded yet
+    pub fn get(&self) -> Option<Arc<CodenameData>> {
+        self.inner
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Swaps in new codename data and returns the previous value
+    pub fn replace(&self, codename_data: CodenameData) -> Option<Arc<CodenameData>> {
+        self.swap(Arc::new(codename_data))
+    }
+
+    fn swap(&self, codename_data: Arc<CodenameData>) -> Option<Arc<CodenameData>> {
+        let mut guard = self
+            .inner
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        guard.replace(codename_data)
+    }
+
+    /// Re-reads and validates the file at `path`. The current data is only replaced
+    /// when the new file parses and validates; otherwise the old data stays in place.
+    /// Returns the data this call installed, even if another reload replaced it since.
+    pub fn reload_from_path(&self, path: &str) -> Result<Arc<CodenameData>, String> {
+        let codename_data = Arc::new(load_codename_data(path)?);
+        self.swap(Arc::clone(&codename_data));
+        Ok(codename_data)
+    }
+}
+
+impl Default for CodenameStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+pub const DEFAULT_CODENAME_PATH: &str = "./assets/CodenameData.json";
+
 /// ### Database data structure
 pub struct DbData {
     pub db: Connection,
@@ -32,10 +251,21 @@ pub const DEFAULT_DB_PATH: &str = "./history.db";
 pub struct BotState {
     /// Path to the SQLite DB file (we open per-call to avoid sharing Connection across threads)
     pub db_path: String,
+    /// Path to the codename JSON file, re-read by `/reload` and on SIGHUP
+    pub codename_path: String,
+    /// Configuration read from the environment at startup
+    pub config: config::BotConfig,
+    /// Per-guild settings changed with `/config`
+    pub settings: settings::SettingsCache,
+    /// Where command_history is kept, SQLite or Postgres depending on `HISTORY_BACKEND`
+    pub history: Arc<dyn HistoryStore>,
+    /// Queue new history rows go through, written to `history` in batches
+    pub history_writer: HistoryWriter,
 }
 
-/// FeedItem represents a Discord command usage event
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// FeedItem represents a Discord command usage event, sent in the feed as a
+/// `events::FeedEvent`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FeedItem {
     pub item_uuid: String,
     pub timestamp: String,
@@ -46,8 +276,9 @@ pub struct FeedItem {
     pub test_item: bool,
 }
 
-/// Public global storing the codename data. Initialized during framework setup.
-pub static CODENAME_DATA: OnceCell<CodenameData> = OnceCell::new();
+/// Public global storing the codename data. Initialized during framework setup and
+/// replaced in place whenever the data file is reloaded.
+pub static CODENAME_DATA: CodenameStore = CodenameStore::new();
 
 /// ### the Bot's Error type
 pub type BotError = Box<dyn std::error::Error + Send + Sync>;
@@ -55,6 +286,49 @@ pub type BotError = Box<dyn std::error::Error + Send + Sync>;
 /// ### The Bot's Context type
 pub type Context<'a> = poise::Context<'a, BotState, BotError>;
 
+/// Settings of a guild, from the cache or the database
+pub async fn settings_for_guild(
+    data: &BotState,
+    guild_id: &str,
+) -> Result<Arc<settings::GuildSettings>, BotError> {
+    if let Some(settings) = data.settings.get(guild_id) {
+        return Ok(settings);
+    }
+    let db_path = data.db_path.clone();
+    let load_id = guild_id.to_string();
+    let settings =
+        tokio::task::spawn_blocking(move || settings::load_guild_settings(&db_path, &load_id))
+            .await??;
+    Ok(data.settings.insert(guild_id, settings))
+}
+
+/// Whether a user opted out with `/privacy optout`. Errors count as opted out so a
+/// broken database never leaks someone's activity.
+pub async fn user_opted_out(data: &BotState, user_id: &str) -> bool {
+    let db_path = data.db_path.clone();
+    let user_id = user_id.to_string();
+    tokio::task::spawn_blocking(move || privacy::is_opted_out(&db_path, &user_id))
+        .await
+        .map_or(true, |opted_out| opted_out.unwrap_or(true))
+}
+
+/// Whether what `user_id` runs in `guild_id` (None in DMs) goes into the history: the
+/// guild keeps `history_logging` on and the user didn't opt out. Settings that fail to
+/// load count as the defaults, like everywhere else.
+pub async fn history_logging_enabled(
+    data: &BotState,
+    guild_id: Option<&str>,
+    user_id: &str,
+) -> bool {
+    let logging = match guild_id {
+        Some(guild_id) => settings_for_guild(data, guild_id)
+            .await
+            .map_or(true, |settings| settings.history_logging),
+        None => true,
+    };
+    logging && !user_opted_out(data, user_id).await
+}
+
 /// ### Sets up the SQLite database and returns the DbData
 pub async fn db_setup(path: &str) -> DbData {
     println!("{}", "Setting up the database...".white().on_blue());
@@ -70,68 +344,259 @@ pub async fn db_setup(path: &str) -> DbData {
             command     TEXT NOT NULL,
             output      TEXT NOT NULL
         );
+        CREATE TABLE IF NOT EXISTS codename_registry (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            guild_id    TEXT NOT NULL,
+            codename    TEXT NOT NULL COLLATE NOCASE,
+            owner_id    TEXT NOT NULL,
+            owner_name  TEXT NOT NULL,
+            description TEXT NOT NULL,
+            claimed_at  TEXT NOT NULL,
+            UNIQUE (guild_id, codename)
+        );
+        CREATE TABLE IF NOT EXISTS bot_meta (
+            key         TEXT PRIMARY KEY,
+            value       TEXT NOT NULL
+        );
     ",
     ) {
-        Ok(_) => println!("{}", "Database setup complete.".white().on_blue()),
+        Ok(_) => {}
         Err(e) => panic!("Failed to set up database: {}", e),
     }
+    if let Err(e) = migrate_schema(&db) {
+        panic!("Failed to migrate database: {}", e);
+    }
+    println!("{}", "Database setup complete.".white().on_blue());
 
     DbData { db }
 }
 
+/// Schema version written to `PRAGMA user_version` once `db_setup` has run.
+/// Version 1 is the table layout created by the `CREATE TABLE IF NOT EXISTS` batch.
+pub const SCHEMA_VERSION: i64 = 1 + MIGRATIONS.len() as i64;
+
+/// Upgrades applied in order on top of the base schema; entry `i` moves a database
+/// from version `i + 1` to `i + 2`. Never edit an entry once released, append a new one.
+const MIGRATIONS: &[&str] = &[
+    // 2: status and guild of each history row, per-guild command permission rules
+    "
+    ALTER TABLE command_history ADD COLUMN status TEXT NOT NULL DEFAULT 'ok';
+    ALTER TABLE command_history ADD COLUMN guild_id TEXT;
+    CREATE TABLE command_permissions (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        guild_id    TEXT NOT NULL,
+        command     TEXT NOT NULL,
+        target_kind TEXT NOT NULL,
+        target_id   TEXT NOT NULL,
+        allow       INTEGER NOT NULL,
+        created_by  TEXT NOT NULL,
+        created_at  TEXT NOT NULL,
+        UNIQUE (guild_id, command, target_kind, target_id)
+    );
+    ",
+    // 3: persistent rate limit buckets and daily throttle counters
+    "
+    CREATE TABLE rate_limit_buckets (
+        bucket     TEXT PRIMARY KEY,
+        tokens     REAL NOT NULL,
+        updated_at REAL NOT NULL
+    );
+    CREATE TABLE throttle_counts (
+        day     TEXT NOT NULL,
+        command TEXT NOT NULL,
+        scope   TEXT NOT NULL,
+        count   INTEGER NOT NULL,
+        PRIMARY KEY (day, command, scope)
+    );
+    ",
+    // 4: per-guild settings edited with /config
+    "
+    CREATE TABLE guild_settings (
+        guild_id   TEXT NOT NULL,
+        key        TEXT NOT NULL,
+        value      TEXT NOT NULL,
+        updated_by TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        PRIMARY KEY (guild_id, key)
+    );
+    ",
+    // 5: users who opted out of history logging with /privacy optout
+    "
+    CREATE TABLE privacy_optouts (
+        user_id      TEXT PRIMARY KEY,
+        opted_out_at TEXT NOT NULL
+    );
+    ",
+    // 6: indexes for the feed, per-user lookups, per-command stats and retention pruning
+    "
+    CREATE INDEX idx_command_history_timestamp ON command_history (timestamp);
+    CREATE INDEX idx_command_history_user_id ON command_history (user_id);
+    CREATE INDEX idx_command_history_command ON command_history (command);
+    ",
+    // 7: feed events other than commands (guilds, members, shards, config changes)
+    "
+    CREATE TABLE feed_events (
+        id        INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp TEXT NOT NULL,
+        kind      TEXT NOT NULL,
+        guild_id  TEXT,
+        user_id   TEXT,
+        payload   TEXT NOT NULL
+    );
+    CREATE INDEX idx_feed_events_timestamp ON feed_events (timestamp);
+    CREATE INDEX idx_feed_events_user_id ON feed_events (user_id);
+    ",
+    // 8: `/codename` became `/codename generate`, rename the rows logged before so the
+    // history and stats count them as the same command
+    "
+    UPDATE command_history SET command = 'codename generate' WHERE command = 'codename';
+    ",
+];
+
+/// Applies every migration the database has not seen yet, each in its own transaction
+fn migrate_schema(db: &Connection) -> rusqlite::Result<()> {
+    let current: i64 = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    // Fresh databases and ones created before versioning existed report 0
+    let current = current.max(1);
+    for (index, migration) in MIGRATIONS.iter().enumerate() {
+        let version = index as i64 + 2;
+        if version <= current {
+            continue;
+        }
+        db.execute_batch(&format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            migration, version
+        ))?;
+    }
+    Ok(())
+}
+
+/// Reads the schema version of the database at `path`
+pub fn schema_version(path: &str) -> rusqlite::Result<i64> {
+    let conn = Connection::open(path)?;
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Outcome of a command invocation as recorded in command_history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    /// The command ran
+    Ok,
+    /// A permission rule stopped the command from running
+    Denied,
+    /// The command ran but returned an error
+    Error,
+}
+
+impl CommandStatus {
+    /// Value stored in the `status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandStatus::Ok => "ok",
+            CommandStatus::Denied => "denied",
+            CommandStatus::Error => "error",
+        }
+    }
+}
+
+/// ### One row of command_history
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub author_id: String,
+    pub author_name: String,
+    /// None for commands run in DMs
+    pub guild_id: Option<String>,
+    pub command_name: String,
+    pub command_output: String,
+    pub status: CommandStatus,
+}
+
+impl HistoryEntry {
+    /// A successful invocation outside any guild
+    pub fn new(
+        author_id: &str,
+        author_name: &str,
+        command_name: &str,
+        command_output: &str,
+    ) -> Self {
+        HistoryEntry {
+            author_id: author_id.to_string(),
+            author_name: author_name.to_string(),
+            guild_id: None,
+            command_name: command_name.to_string(),
+            command_output: command_output.to_string(),
+            status: CommandStatus::Ok,
+        }
+    }
+
+    /// Builds the entry for the invoking user and guild of `ctx`
+    pub fn from_context(ctx: &Context<'_>, command_output: &str, status: CommandStatus) -> Self {
+        HistoryEntry {
+            guild_id: ctx.guild_id().map(|id| id.to_string()),
+            status,
+            ..HistoryEntry::new(
+                &ctx.author().id.to_string(),
+                &ctx.author().name,
+                &ctx.command().qualified_name,
+                command_output,
+            )
+        }
+    }
+}
+
+/// Async function that queues a history entry on the writer; it is stored in the
+/// writer's next batch
+pub async fn log_history_entry(writer: &HistoryWriter, entry: HistoryEntry) {
+    log_history_entry_at(writer, entry, chrono::Utc::now()).await;
+}
+
+/// Like `log_history_entry`, stored with `timestamp` instead of the current time
+pub async fn log_history_entry_at(
+    writer: &HistoryWriter,
+    entry: HistoryEntry,
+    timestamp: chrono::DateTime<chrono::Utc>,
+) {
+    println!(
+        "{}",
+        format!(
+            "Logging command usage:\n  user_id={}\n  username={}\n  command={}\n  status={}",
+            entry.author_id,
+            entry.author_name,
+            entry.command_name,
+            entry.status.as_str()
+        )
+        .white()
+    );
+    writer.log_at(entry, timestamp).await;
+}
+
 /// Async function that logs command usage for a given author. Extracted so tests can
 /// call the same async path as `log_command_usage` without needing a `Context`.
 pub async fn log_command_usage_with_author(
-    db_path: &str,
+    writer: &HistoryWriter,
     author_id: &str,
     author_name: &str,
     command_name: &str,
     command_output: &str,
 ) {
-    let db_path = db_path.to_string();
-    let author_id = author_id.to_string();
-    let author_name = author_name.to_string();
-    let command_name = command_name.to_string();
-    let command_output = command_output.to_string();
-    println!(
-        "{}",
-        format!(
-            "Logging command usage:\n  user_id={}\n  username={}\n  command={}",
-            author_id, author_name, command_name
-        )
-        .white()
-    );
-    tokio::task::spawn_blocking(move || {
-        // Perform the synchronous DB insert in a blocking task
-        insert_command_history_sync(
-            &db_path,
-            &author_id,
-            &author_name,
-            &command_name,
-            &command_output,
-        )
-        .expect("Failed to log command usage");
-    })
-    .await
-    .ok();
+    log_history_entry(
+        writer,
+        HistoryEntry::new(author_id, author_name, command_name, command_output),
+    )
+    .await;
 }
 
-/// Helper that accepts a `poise::Context` to extract the author and delegate
-/// to `log_command_usage_with_author`.
+/// Helper that accepts a `poise::Context` to extract the author and guild and delegate
+/// to `log_history_entry_at`. `timestamp` is the one the feed item gets too.
 pub async fn log_command_usage(
-    db_path: &str,
     ctx: &poise::Context<'_, BotState, BotError>,
     command_name: &str,
     command_output: &str,
+    timestamp: chrono::DateTime<chrono::Utc>,
 ) {
-    log_command_usage_with_author(
-        db_path,
-        &ctx.author().id.to_string(),
-        &ctx.author().name,
-        command_name,
-        command_output,
-    )
-    .await;
+    let mut entry = HistoryEntry::from_context(ctx, command_output, CommandStatus::Ok);
+    entry.command_name = command_name.to_string();
+    log_history_entry_at(&ctx.data().history_writer, entry, timestamp).await;
 }
 
 // Crate-public helper that performs the DB insert synchronously. Extracted so tests
@@ -142,33 +607,91 @@ pub fn insert_command_history_sync(
     author_name: &str,
     command_name: &str,
     command_output: &str,
-) -> rusqlite::Result<()> {
-    let conn = Connection::open(db_path)?;
-    let timestamp = chrono::Utc::now().to_rfc3339();
-    conn.execute(
-        "INSERT INTO command_history (timestamp, user_id, username, command, output) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![timestamp, author_id, author_name, command_name, command_output],
-    )?;
-    Ok(())
+) -> Result<(), String> {
+    insert_history_entry_sync(
+        db_path,
+        &HistoryEntry::new(author_id, author_name, command_name, command_output),
+    )
 }
 
-/// Generate a random codename for the codename command
+/// Synchronous insert of a full history entry, including guild and status, into the
+/// SQLite history
+pub fn insert_history_entry_sync(db_path: &str, entry: &HistoryEntry) -> Result<(), String> {
+    SqliteHistoryStore::new(db_path).insert(entry, chrono::Utc::now())
+}
+
+/// Generate a random codename for the codename command.
+/// Pairs rejected by the data's blocklist are re-rolled up to `MAX_BLOCKLIST_REROLLS` times.
 pub fn generate_codename(codename_data: &CodenameData) -> Result<String, String> {
+    pick_codename(codename_data, |len| (rand::random::<u64>() as usize) % len)
+}
+
+/// Generate a codename deterministically from `seed`: the same seed text always yields
+/// the same codename for the same dataset. ChaCha8's output is fixed across rand
+/// versions, unlike `StdRng`'s, so upgrades don't change existing codenames.
+pub fn generate_codename_seeded(
+    codename_data: &CodenameData,
+    seed: &str,
+) -> Result<String, String> {
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(fnv1a_64(seed.as_bytes()));
+    pick_codename(codename_data, |len| rng.random_range(0..len))
+}
+
+/// Picks an allowed adjective-animal pair using `next_index(len)` to choose list positions
+fn pick_codename(
+    codename_data: &CodenameData,
+    mut next_index: impl FnMut(usize) -> usize,
+) -> Result<String, String> {
     if codename_data.adjectives.is_empty() || codename_data.animals.is_empty() {
         return Err("Codename generation failed".to_string());
     }
-    let adj_index = (rand::random::<u64>() as usize) % codename_data.adjectives.len();
-    let animal_index = (rand::random::<u64>() as usize) % codename_data.animals.len();
-    let adjective = &codename_data.adjectives[adj_index];
-    let animal = &codename_data.animals[animal_index];
-    Ok(format!(
-        "{} {}",
-        capitalize_first(adjective),
-        capitalize_first(animal)
-    ))
-}
-
-fn capitalize_first(s: &str) -> String {
+    for _ in 0..MAX_BLOCKLIST_REROLLS {
+        let adjective = &codename_data.adjectives[next_index(codename_data.adjectives.len())];
+        let animal = &codename_data.animals[next_index(codename_data.animals.len())];
+        if codename_data.blocklist.allows(adjective, animal) {
+            return Ok(format!(
+                "{} {}",
+                capitalize_first(adjective),
+                capitalize_first(animal)
+            ));
+        }
+    }
+    Err("Codename generation failed: every attempt hit the blocklist".to_string())
+}
+
+/// FNV-1a hash, used instead of `DefaultHasher` because its output is stable across
+/// Rust releases, so seeded codenames don't change when the toolchain is updated
+pub(crate) fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Largest number of codenames `/codename generate` hands out in one batch
+pub const MAX_CODENAME_BATCH: usize = 10;
+
+/// Generate `count` distinct codenames, none of which appear in `taken` (canonical names)
+pub fn generate_codename_batch(
+    codename_data: &CodenameData,
+    count: usize,
+    taken: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    let mut taken = taken.clone();
+    let mut codenames = Vec::with_capacity(count);
+    for _ in 0..count {
+        let codename = registry::generate_codename_avoiding(
+            codename_data,
+            &taken,
+            registry::MAX_UNIQUE_ATTEMPTS,
+        )?;
+        taken.insert(codename.clone());
+        codenames.push(codename);
+    }
+    Ok(codenames)
+}
+
+pub(crate) fn capitalize_first(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
         None => String::new(),
@@ -178,64 +701,193 @@ fn capitalize_first(s: &str) -> String {
 
 /// for the frontend to autoload the most recent history of commands.
 pub fn load_recent_commands(db_path: &str, x: i64) -> Result<VecDeque<FeedItem>, String> {
-    let conn = Connection::open(db_path).map_err(|e| format!("Failed to open SQLite DB: {}", e))?;
-    // check if the database is empty and return an empty vector if it is
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM command_history", [], |row| row.get(0))
-        .expect("Failed to count rows in command_history");
-    if count == 0 {
-        return Ok(VecDeque::new());
-    }
+    load_recent_feed(&SqliteHistoryStore::new(db_path), db_path, x)
+}
 
-    let mut stmt = conn
-        .prepare("SELECT timestamp, user_id, username, command, output FROM command_history ORDER BY timestamp DESC LIMIT ?1")
-        .map_err(|e| format!("Failed to prepare SQL statement: {}", e))?;
-    let rows = stmt
-        .query_map(rusqlite::params![x], |row| {
-            Ok(FeedItem {
-                item_uuid: uuid::Uuid::new_v4().to_string(),
-                timestamp: row.get(0).unwrap_or_else(|_| "unknown".to_string()),
-                author_id: row.get(1).unwrap_or_else(|_| "unknown".to_string()),
-                author_name: row.get(2).unwrap_or_else(|_| "unknown".to_string()),
-                command_name: row.get(3).unwrap_or_else(|_| "unknown".to_string()),
-                command_output: row.get(4).unwrap_or_else(|_| "unknown".to_string()),
-                test_item: false,
-            })
+/// The `x` most recent commands of `history` as feed items, oldest first, failed ones
+/// included. Authors who opted out in the SQLite database at `db_path` are anonymized,
+/// and guilds hidden from the feed left out.
+pub fn load_recent_feed(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    x: i64,
+) -> Result<VecDeque<FeedItem>, String> {
+    let hidden = settings::hidden_guilds(db_path)
+        .map_err(|e| format!("Failed to load guild settings: {}", e))?;
+    Ok(load_feed_items(history, db_path, &hidden, None, x)?
+        .into_iter()
+        .filter_map(|event| match event {
+            events::FeedEvent::CommandExecuted(item) | events::FeedEvent::CommandFailed(item) => {
+                Some(item)
+            }
+            _ => None,
         })
-        .map_err(|e| format!("Failed to query SQL database: {}", e))?;
+        .collect())
+}
+
+/// `item_uuid` of a replayed history row: a custom (version 8) uuid holding the row id,
+/// so a row keeps its uuid however often it is replayed
+fn history_item_uuid(row_id: i64) -> String {
+    let bytes = uuid::Uuid::from_u64_pair(0, row_id as u64).into_bytes();
+    uuid::Builder::from_custom_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
+/// Like `load_recent_feed`, but only the commands later than `after`, as
+/// `CommandExecuted` events or `CommandFailed` for the ones that returned an error.
+/// Denied attempts and the commands of `hidden_guilds` are left out.
+pub fn load_feed_items(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    hidden_guilds: &HashSet<String>,
+    after: Option<chrono::DateTime<chrono::Utc>>,
+    x: i64,
+) -> Result<VecDeque<events::FeedEvent>, String> {
+    let opted_out = privacy::opted_out_users(db_path)
+        .map_err(|e| format!("Failed to load privacy opt-outs: {}", e))?;
+    let query = HistoryQuery {
+        filter: export::HistoryFilter {
+            // `since` is inclusive, but the command at `after` itself was already sent.
+            // Timestamps are stored with nanosecond precision, so the next one is the
+            // first that wasn't.
+            since: after.map(|after| after + chrono::Duration::nanoseconds(1)),
+            include_errors: true,
+            exclude_guilds: hidden_guilds.iter().cloned().collect(),
+            ..export::HistoryFilter::default()
+        },
+        newest_first: true,
+        limit: Some(x.max(0) as usize),
+    };
     let mut items = VecDeque::new();
-    for row in rows {
-        items.push_front(row.map_err(|e| format!("Failed to parse row: {}", e))?);
-    }
+    history.query(&query, &mut |row| {
+        let failed = row.status == CommandStatus::Error.as_str();
+        let item = FeedItem {
+            item_uuid: history_item_uuid(row.id),
+            timestamp: row.timestamp,
+            author_id: row.user_id,
+            author_name: row.username,
+            command_name: row.command,
+            command_output: row.output,
+            test_item: false,
+        };
+        // Rows logged before the author opted out stay until `/privacy delete`
+        let item = if opted_out.contains(&item.author_id) {
+            privacy::anonymize(item)
+        } else {
+            item
+        };
+        items.push_front(if failed {
+            events::FeedEvent::CommandFailed(item)
+        } else {
+            events::FeedEvent::CommandExecuted(item)
+        });
+        Ok(())
+    })?;
     println!("Loaded {} recent commands", items.len());
     Ok(items)
 }
 
-/// Load codename data from a JSON file into the global `CODENAME_DATA` OnceCell.
+/// Number of commands a user has run according to the SQLite command_history. Denied
+/// attempts are not counted.
+pub fn count_commands_for_user(db_path: &str, user_id: &str) -> Result<u64, String> {
+    SqliteHistoryStore::new(db_path).count(&export::HistoryFilter {
+        user_id: Some(user_id.to_string()),
+        ..export::HistoryFilter::default()
+    })
+}
+
+/// Reads, parses and validates a codename JSON file without touching `CODENAME_DATA`.
+pub fn load_codename_data(path: &str) -> Result<CodenameData, String> {
+    let data =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let codename_data: CodenameData =
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+    codename_data
+        .validate()
+        .map_err(|e| format!("Invalid codename data in {}: {}", path, e))?;
+    Ok(codename_data)
+}
+
+/// Load codename data from a JSON file into the global `CODENAME_DATA` store.
 /// This is the crate-public version so tests and the binary can call it.
+/// Panics if the file cannot be loaded, since the bot cannot run without it.
 pub async fn codename_data_setup_from_path(path: &str) {
     let codename_path = path.to_string();
     tokio::task::spawn_blocking(move || {
         println!("{}", "Loading codename data...".white().on_green());
-        let data =
-            std::fs::read_to_string(&codename_path).expect("Failed to read CodenameData.json");
-        let codenamedata: CodenameData =
-            serde_json::from_str(&data).expect("Failed to parse CodenameData.json");
-        CODENAME_DATA
-            .set(codenamedata)
-            .expect("CODENAME_DATA was already initialized");
+        let codenamedata =
+            load_codename_data(&codename_path).expect("Failed to load codename data");
+        CODENAME_DATA.replace(codenamedata);
         println!("{}", "Codename data loaded.".white().on_green());
     })
     .await
     .expect("spawn_blocking failed when loading codename data");
 }
 
-/// read x most recent commands from the database and return them as a vector of FeedItems
+/// Reload the global `CODENAME_DATA` from `path`, keeping the previous data if the
+/// new file is missing or invalid.
+pub async fn reload_codename_data(path: &str) -> Result<Arc<CodenameData>, String> {
+    let codename_path = path.to_string();
+    let result =
+        tokio::task::spawn_blocking(move || CODENAME_DATA.reload_from_path(&codename_path))
+            .await
+            .map_err(|e| format!("spawn_blocking failed when reloading codename data: {}", e))?;
+    match &result {
+        Ok(data) => println!(
+            "{}",
+            format!(
+                "Codename data reloaded: {} adjectives, {} animals",
+                data.adjectives.len(),
+                data.animals.len()
+            )
+            .white()
+            .on_green()
+        ),
+        Err(e) => println!(
+            "{}",
+            format!("Codename data reload failed, keeping previous data: {}", e).red()
+        ),
+    }
+    result
+}
+
+/// Spawns a task that reloads the codename data from `path` every time the process
+/// receives SIGHUP.
+#[cfg(unix)]
+pub fn spawn_codename_reload_on_sighup(path: String) {
+    use tokio::signal::unix::{SignalKind, signal};
+    tokio::spawn(async move {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                eprintln!("Failed to install SIGHUP handler: {}", e);
+                return;
+            }
+        };
+        while hangups.recv().await.is_some() {
+            println!(
+                "{}",
+                "SIGHUP received, reloading codename data..."
+                    .white()
+                    .on_green()
+            );
+            let _ = reload_codename_data(&path).await;
+        }
+    });
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fnv1a_64_known_vectors() {
+        assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a_64(b"foobar"), 0x85944171f73967e8);
+    }
+
     #[test]
     fn test_capitalize_first() {
         // normal lowercase
@@ -1,41 +1,95 @@
 use colored::Colorize;
-use discordbot::{
-    BotError, BotState, CODENAME_DATA, CodenameData, DEFAULT_DB_PATH, FeedItem, db_setup,
-    load_recent_commands,
-};
+use discordbot::config::BotConfig;
+// Used by the `web` and `websocket` modules through `crate::analytics`, `crate::events`,
+// `crate::export`, `crate::history_writer` and `crate::store`
+use discordbot::history_writer::HistoryWriter;
+use discordbot::registration::{RegistrationTarget, register_commands};
+use discordbot::store::{HistoryStore, open_history_store};
+use discordbot::{BotError, BotState, CODENAME_DATA, CodenameData, db_setup};
+use discordbot::{analytics, events, export, history_writer, store};
 use dotenvy::dotenv;
 use poise::serenity_prelude as serenity;
 use serenity::prelude::*;
 use std::env;
+use std::sync::Arc;
+mod cli;
 mod commands;
+mod handler;
 pub mod web;
 pub mod websocket;
 
 #[tokio::main]
 async fn main() {
+    // Any command line arguments select a maintenance mode instead of starting the bot
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args).await);
+    }
     dotenv().ok();
     // Configure the client with your Discord bot token in the environment.
     let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    let config = BotConfig::from_env().expect("Invalid bot configuration");
     println!("{}", "Bot starting...".black().on_yellow());
+    // Open the command history on the configured backend and start its writer
+    let backend = config.history_backend.clone();
+    let history = tokio::task::spawn_blocking(move || {
+        open_history_store(&backend, discordbot::DEFAULT_DB_PATH)
+    })
+    .await
+    .expect("Opening the command history panicked")
+    .expect("Failed to open the command history");
+    println!("Command history backend: {}", history.backend());
+    let history_writer = HistoryWriter::spawn(history.clone(), config.history_writer);
+    let setup_writer = history_writer.clone();
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES
+    let mut intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT
         | GatewayIntents::GUILDS;
+    // Member joins need the privileged Server Members intent, so they are opt-in
+    if config.feed_member_events {
+        intents |= GatewayIntents::GUILD_MEMBERS;
+    }
+
+    // Load the message catalogs up front so a broken .ftl file stops the bot here
+    let catalog = &*discordbot::i18n::CATALOG;
+    println!("Loaded locales: {}", catalog.locales().join(", "));
+    let mut commands = vec![
+        // Add commands here
+        commands::register(),
+        commands::codename(),
+        commands::avatar(),
+        commands::reload(),
+        commands::userinfo(),
+        commands::userinfo_context_menu(),
+        commands::avatar_context_menu(),
+        commands::give_codename(),
+        commands::codename_from_message(),
+        commands::perms(),
+        commands::ratelimits(),
+        commands::config_command(),
+        commands::privacy(),
+        commands::history(),
+        commands::stats(),
+        commands::leaderboard(),
+    ];
+    discordbot::i18n::localize_commands(catalog, &mut commands);
 
     let framework = poise::Framework::<BotState, BotError>::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                // Add commands here
-                commands::register(),
-                commands::codename(),
-                commands::avatar(),
-            ],
+            commands,
+            command_check: Some(|ctx| Box::pin(commands::command_check(ctx))),
+            on_error: |error| Box::pin(commands::on_error(error)),
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(handler::event_handler(ctx, event, framework, data))
+            },
             ..Default::default()
         })
-        .setup(|_ctx, _ready, _framework| {
+        .setup(move |_ctx, _ready, _framework| {
             println!("{}", "Running framework setup...".white().on_cyan());
-            Box::pin(async move { run_setup(_ctx, _ready, _framework).await })
+            Box::pin(async move {
+                run_setup(_ctx, _ready, _framework, config, history, setup_writer).await
+            })
         })
         .build();
 
@@ -46,6 +100,14 @@ async fn main() {
         .await
         .expect("Err creating client");
 
+    // Stop the shards on Ctrl-C or SIGTERM so the history still queued gets written below
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        println!("{}", "Shutting down...".black().on_yellow());
+        shard_manager.shutdown_all().await;
+    });
+
     // Finally, start a single shard, and start listening to events.
     //
     // Shards will automatically attempt to reconnect, and will perform exponential backoff until
@@ -61,31 +123,98 @@ async fn main() {
             println!("{}", format!("Discord client failed: {:?}", error).red());
         }
     }
+    history_writer.flush().await;
+    println!("{}", "Command history flushed".white());
 }
+
+/// Resolves on Ctrl-C, or on SIGTERM, which is what `docker stop` sends. A signal that
+/// can't be listened for never resolves.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                eprintln!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 /// Framework setup function
-/// - Registers application commands globally
-/// - Loads codename data from JSON file
-/// - Sets up the SQLite database
+/// - Sets up the SQLite database and starts the history pruning and backup tasks
+/// - Registers application commands in the dev guilds, or globally when none are
+///   configured, skipping the call when the command set is unchanged
+/// - Loads codename data from JSON file and installs the SIGHUP reload handler
 /// - Returns the initial BotState
 async fn run_setup(
     ctx: &Context,
     _ready: &serenity::Ready,
     _framework: &poise::Framework<BotState, BotError>,
+    config: BotConfig,
+    history: Arc<dyn HistoryStore>,
+    history_writer: HistoryWriter,
 ) -> Result<BotState, BotError> {
-    // Register application commands globally
-    poise::builtins::register_globally(ctx, &_framework.options().commands).await?;
-    //load codename data
-    discordbot::codename_data_setup_from_path("./assets/CodenameData.json").await;
     // Ensure the DB file and schema exist
     db_setup(discordbot::DEFAULT_DB_PATH).await;
+    // Prune the history now and on every interval from then on
+    discordbot::retention::spawn_history_pruning(
+        history.clone(),
+        discordbot::DEFAULT_DB_PATH.to_string(),
+        config.retention,
+        config.prune_interval_hours,
+    );
+    discordbot::backup::spawn_backups(
+        discordbot::DEFAULT_DB_PATH.to_string(),
+        config.backup.clone(),
+    );
+    // Register application commands where the config asks for them
+    let target = RegistrationTarget::from_dev_guilds(&config.dev_guild_ids);
+    register_commands(
+        ctx,
+        &_framework.options().commands,
+        &target,
+        discordbot::DEFAULT_DB_PATH,
+    )
+    .await?;
+    //load codename data and reload it whenever the process receives SIGHUP
+    discordbot::codename_data_setup_from_path(discordbot::DEFAULT_CODENAME_PATH).await;
+    #[cfg(unix)]
+    discordbot::spawn_codename_reload_on_sighup(discordbot::DEFAULT_CODENAME_PATH.to_string());
+    let api_token = config.api_token.clone();
+    let web_state = web::AppState::new(
+        history.clone(),
+        history_writer.clone(),
+        discordbot::DEFAULT_DB_PATH,
+    );
     tokio::spawn(async move {
         println!("{}", "Starting web server...".white().on_cyan());
-        web::setup_web_server("3000").await;
+        web::setup_web_server("3000", api_token, web_state).await;
     });
     println!("{}", "Framework setup complete.".white().on_cyan());
     // Confirm everything finished and the bot is running
     println!("{}", "Bot is running!".white().on_bright_magenta());
     Ok(BotState {
         db_path: discordbot::DEFAULT_DB_PATH.to_string(),
+        codename_path: discordbot::DEFAULT_CODENAME_PATH.to_string(),
+        config,
+        settings: discordbot::settings::SettingsCache::new(),
+        history,
+        history_writer,
     })
 }
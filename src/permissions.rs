@@ -0,0 +1,201 @@
+use crate::i18n::{tr, tr_args};
+use fluent::fluent_args;
+use rusqlite::Connection;
+
+/// Command name that makes a rule apply to every command
+pub const ALL_COMMANDS: &str = "*";
+
+/// What a permission rule matches against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleTarget {
+    Role,
+    User,
+    Channel,
+}
+
+impl RuleTarget {
+    /// Value stored in the `target_kind` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleTarget::Role => "role",
+            RuleTarget::User => "user",
+            RuleTarget::Channel => "channel",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "role" => Some(RuleTarget::Role),
+            "user" => Some(RuleTarget::User),
+            "channel" => Some(RuleTarget::Channel),
+            _ => None,
+        }
+    }
+
+    /// Discord mention syntax for an ID of this kind
+    pub fn mention(&self, id: &str) -> String {
+        match self {
+            RuleTarget::Role => format!("<@&{}>", id),
+            RuleTarget::User => format!("<@{}>", id),
+            RuleTarget::Channel => format!("<#{}>", id),
+        }
+    }
+}
+
+/// An allow or deny entry in a guild's `command_permissions`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PermissionRule {
+    /// Qualified command name, a parent command name, or `ALL_COMMANDS`
+    pub command: String,
+    pub target: RuleTarget,
+    pub target_id: String,
+    pub allow: bool,
+}
+
+/// Returns true if a rule written for `rule_command` covers the invoked command:
+/// an exact match, a parent of it (`codename` covers `codename claim`), or `*`.
+pub fn rule_applies(rule_command: &str, qualified_name: &str) -> bool {
+    rule_command == ALL_COMMANDS
+        || rule_command == qualified_name
+        || qualified_name
+            .strip_prefix(rule_command)
+            .is_some_and(|rest| rest.starts_with(' '))
+}
+
+/// Decides whether a command may run given the rules that apply to it.
+/// - Any matching deny rule wins.
+/// - If there are user or role allow rules, the user must match one of them.
+/// - If there are channel allow rules, the command must be run in one of those channels.
+/// - Commands without rules are open to everyone.
+pub fn evaluate(
+    rules: &[PermissionRule],
+    user_id: &str,
+    role_ids: &[String],
+    channel_id: &str,
+) -> bool {
+    let matches = |rule: &PermissionRule| match rule.target {
+        RuleTarget::User => rule.target_id == user_id,
+        RuleTarget::Role => role_ids.contains(&rule.target_id),
+        RuleTarget::Channel => rule.target_id == channel_id,
+    };
+    if rules.iter().any(|rule| !rule.allow && matches(rule)) {
+        return false;
+    }
+    let (channel_allows, subject_allows): (Vec<&PermissionRule>, Vec<&PermissionRule>) = rules
+        .iter()
+        .filter(|rule| rule.allow)
+        .partition(|rule| rule.target == RuleTarget::Channel);
+    let subject_ok = subject_allows.is_empty() || subject_allows.iter().any(|rule| matches(rule));
+    let channel_ok = channel_allows.is_empty() || channel_allows.iter().any(|rule| matches(rule));
+    subject_ok && channel_ok
+}
+
+/// Adds a rule, replacing any existing rule for the same command and target
+pub fn set_rule(
+    db_path: &str,
+    guild_id: &str,
+    rule: &PermissionRule,
+    created_by: &str,
+) -> rusqlite::Result<()> {
+    let conn = Connection::open(db_path)?;
+    let created_at = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO command_permissions (guild_id, command, target_kind, target_id, allow, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT (guild_id, command, target_kind, target_id)
+         DO UPDATE SET allow = excluded.allow, created_by = excluded.created_by, created_at = excluded.created_at",
+        rusqlite::params![
+            guild_id,
+            rule.command,
+            rule.target.as_str(),
+            rule.target_id,
+            rule.allow,
+            created_by,
+            created_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// Removes the rule for a command and target. Returns false if there was none.
+pub fn remove_rule(
+    db_path: &str,
+    guild_id: &str,
+    command: &str,
+    target: RuleTarget,
+    target_id: &str,
+) -> rusqlite::Result<bool> {
+    let conn = Connection::open(db_path)?;
+    let removed = conn.execute(
+        "DELETE FROM command_permissions WHERE guild_id = ?1 AND command = ?2 AND target_kind = ?3 AND target_id = ?4",
+        rusqlite::params![guild_id, command, target.as_str(), target_id],
+    )?;
+    Ok(removed > 0)
+}
+
+/// Every rule in the guild, ordered by command
+pub fn list_rules(db_path: &str, guild_id: &str) -> rusqlite::Result<Vec<PermissionRule>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT command, target_kind, target_id, allow FROM command_permissions WHERE guild_id = ?1 ORDER BY command, target_kind, target_id",
+    )?;
+    let rows = stmt.query_map([guild_id], |row| {
+        let kind: String = row.get(1)?;
+        Ok(PermissionRule {
+            command: row.get(0)?,
+            target: RuleTarget::parse(&kind).unwrap_or(RuleTarget::User),
+            target_id: row.get(2)?,
+            allow: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The guild's rules that apply to the command with this qualified name
+pub fn rules_for_command(
+    db_path: &str,
+    guild_id: &str,
+    qualified_name: &str,
+) -> rusqlite::Result<Vec<PermissionRule>> {
+    Ok(list_rules(db_path, guild_id)?
+        .into_iter()
+        .filter(|rule| rule_applies(&rule.command, qualified_name))
+        .collect())
+}
+
+pub fn format_permission_list_response(locale: &str, rules: &[PermissionRule]) -> String {
+    if rules.is_empty() {
+        return tr(locale, "perms-list-empty");
+    }
+    let lines: Vec<String> = rules
+        .iter()
+        .map(|rule| {
+            format!(
+                "`{}` {} {}",
+                rule.command,
+                tr(
+                    locale,
+                    if rule.allow {
+                        "perms-allow"
+                    } else {
+                        "perms-deny"
+                    }
+                ),
+                rule.target.mention(&rule.target_id)
+            )
+        })
+        .collect();
+    format!(
+        "**{}**\n{}",
+        tr(locale, "perms-list-title"),
+        lines.join("\n")
+    )
+}
+
+pub fn format_denied_response(locale: &str, qualified_name: &str) -> String {
+    tr_args(
+        locale,
+        "permission-denied",
+        &fluent_args!["command" => qualified_name],
+    )
+}
@@ -0,0 +1,125 @@
+use crate::FeedItem;
+use crate::export::HistoryFilter;
+use crate::store::{HistoryQuery, HistoryStore, SqliteHistoryStore};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Author name shown in the feed for users who opted out
+pub const ANONYMOUS_NAME: &str = "Anonymous";
+
+/// Shown instead of the output of commands run by users who opted out, since replies
+/// such as `/userinfo` and `/avatar` contain the user's ID, name or avatar URL
+pub const ANONYMOUS_OUTPUT: &str = "(hidden)";
+
+/// Returns true if the user ran `/privacy optout` and has not opted back in
+pub fn is_opted_out(db_path: &str, user_id: &str) -> rusqlite::Result<bool> {
+    let conn = Connection::open(db_path)?;
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM privacy_optouts WHERE user_id = ?1)",
+        [user_id],
+        |row| row.get(0),
+    )
+}
+
+/// IDs of every user who opted out, for anonymizing rows read from a history store
+pub fn opted_out_users(db_path: &str) -> rusqlite::Result<HashSet<String>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare("SELECT user_id FROM privacy_optouts")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Opts a user out of (or back into) history logging and the feed.
+/// Returns false if the user was already in the requested state.
+pub fn set_opted_out(db_path: &str, user_id: &str, opted_out: bool) -> rusqlite::Result<bool> {
+    let conn = Connection::open(db_path)?;
+    let changed = if opted_out {
+        conn.execute(
+            "INSERT OR IGNORE INTO privacy_optouts (user_id, opted_out_at) VALUES (?1, ?2)",
+            rusqlite::params![user_id, chrono::Utc::now().to_rfc3339()],
+        )?
+    } else {
+        conn.execute("DELETE FROM privacy_optouts WHERE user_id = ?1", [user_id])?
+    };
+    Ok(changed > 0)
+}
+
+/// One command_history row as included in a data export
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HistoryRecord {
+    pub timestamp: String,
+    pub username: String,
+    /// None for commands run in DMs
+    pub guild_id: Option<String>,
+    pub command: String,
+    pub output: String,
+    pub status: String,
+}
+
+/// ### Everything stored about a user, as sent by `/privacy export`
+#[derive(Clone, Debug, Serialize)]
+pub struct UserExport {
+    pub user_id: String,
+    pub exported_at: String,
+    pub opted_out: bool,
+    pub history: Vec<HistoryRecord>,
+}
+
+/// Collects a user's opt-out state and every row of the SQLite history, oldest first
+pub fn export_user_data(db_path: &str, user_id: &str) -> Result<UserExport, String> {
+    export_user_data_from(&SqliteHistoryStore::new(db_path), db_path, user_id)
+}
+
+/// Collects a user's opt-out state from the SQLite database at `db_path` and every row
+/// of `history`, oldest first
+pub fn export_user_data_from(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    user_id: &str,
+) -> Result<UserExport, String> {
+    let opted_out =
+        is_opted_out(db_path, user_id).map_err(|e| format!("Failed to read the opt-out: {}", e))?;
+    let query = HistoryQuery {
+        filter: HistoryFilter {
+            user_id: Some(user_id.to_string()),
+            include_denied: true,
+            ..HistoryFilter::default()
+        },
+        ..HistoryQuery::default()
+    };
+    let mut records = Vec::new();
+    history.query(&query, &mut |row| {
+        records.push(HistoryRecord {
+            timestamp: row.timestamp,
+            username: row.username,
+            guild_id: row.guild_id,
+            command: row.command,
+            output: row.output,
+            status: row.status,
+        });
+        Ok(())
+    })?;
+    Ok(UserExport {
+        user_id: user_id.to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        opted_out,
+        history: records,
+    })
+}
+
+/// Erases every row of a user from the SQLite history and returns how many were removed.
+/// The opt-out itself is kept so logging stays off.
+pub fn delete_user_history(db_path: &str, user_id: &str) -> Result<usize, String> {
+    SqliteHistoryStore::new(db_path).delete_user(user_id)
+}
+
+/// Replaces the author and output of a feed item so opted-out users cannot be identified
+pub fn anonymize(item: FeedItem) -> FeedItem {
+    FeedItem {
+        author_id: String::new(),
+        author_name: ANONYMOUS_NAME.to_string(),
+        command_output: ANONYMOUS_OUTPUT.to_string(),
+        ..item
+    }
+}
@@ -0,0 +1,313 @@
+use crate::i18n::{tr, tr_args};
+use crate::permissions::rule_applies;
+use fluent::fluent_args;
+use rusqlite::{Connection, TransactionBehavior};
+
+/// Whose invocations share a token bucket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitScope {
+    User,
+    Channel,
+    Guild,
+}
+
+impl LimitScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitScope::User => "user",
+            LimitScope::Channel => "channel",
+            LimitScope::Guild => "guild",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "user" => Some(LimitScope::User),
+            "channel" => Some(LimitScope::Channel),
+            "guild" => Some(LimitScope::Guild),
+            _ => None,
+        }
+    }
+}
+
+/// A token bucket holding up to `capacity` invocations that refills completely over
+/// `period_secs`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period_secs: f64,
+}
+
+impl RateLimit {
+    /// Tokens in a bucket last written at `updated_at` with `tokens` left, at time `now`
+    pub fn refill(&self, tokens: f64, updated_at: f64, now: f64) -> f64 {
+        let elapsed = (now - updated_at).max(0.0);
+        (tokens + elapsed * self.capacity as f64 / self.period_secs).min(self.capacity as f64)
+    }
+
+    /// Seconds until a bucket holding `tokens` has a whole token again
+    pub fn retry_after(&self, tokens: f64) -> f64 {
+        ((1.0 - tokens) * self.period_secs / self.capacity as f64).max(0.0)
+    }
+}
+
+/// A configured limit for a command (or parent command, or `*`) in one scope
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitRule {
+    pub command: String,
+    pub scope: LimitScope,
+    pub limit: RateLimit,
+}
+
+/// Parses a `RATE_LIMITS` value: `;`-separated groups of `command:scope=N/period`,
+/// where one group may list several comma-separated scopes and the period is a number
+/// of seconds with an optional `s`, `m` or `h` suffix.
+/// e.g. `*:user=5/10s;codename:user=3/10s,guild=30/1m`
+pub fn parse_rate_limits(spec: &str) -> Result<Vec<RateLimitRule>, String> {
+    let mut rules = Vec::new();
+    for group in spec.split(';').map(str::trim).filter(|g| !g.is_empty()) {
+        let (command, limits) = group
+            .split_once(':')
+            .ok_or_else(|| format!("Rate limit {:?} is missing \"command:\"", group))?;
+        let command = command.trim();
+        if command.is_empty() {
+            return Err(format!("Rate limit {:?} has an empty command", group));
+        }
+        for limit in limits.split(',').map(str::trim) {
+            let invalid = || format!("Invalid rate limit {:?} for {:?}", limit, command);
+            let (scope, rate) = limit.split_once('=').ok_or_else(invalid)?;
+            let scope = LimitScope::parse(scope.trim()).ok_or_else(invalid)?;
+            let (capacity, period) = rate.split_once('/').ok_or_else(invalid)?;
+            let capacity: u32 = capacity.trim().parse().map_err(|_| invalid())?;
+            let period_secs = parse_period(period.trim()).ok_or_else(invalid)?;
+            if capacity == 0 || period_secs <= 0.0 {
+                return Err(invalid());
+            }
+            rules.push(RateLimitRule {
+                command: command.to_string(),
+                scope,
+                limit: RateLimit {
+                    capacity,
+                    period_secs,
+                },
+            });
+        }
+    }
+    Ok(rules)
+}
+
+fn parse_period(period: &str) -> Option<f64> {
+    let (number, multiplier) = match period.chars().last()? {
+        's' => (&period[..period.len() - 1], 1.0),
+        'm' => (&period[..period.len() - 1], 60.0),
+        'h' => (&period[..period.len() - 1], 3600.0),
+        _ => (period, 1.0),
+    };
+    number.parse::<f64>().ok().map(|n| n * multiplier)
+}
+
+/// Who ran which command where
+#[derive(Clone, Copy, Debug)]
+pub struct Invocation<'a> {
+    pub qualified_name: &'a str,
+    pub user_id: &'a str,
+    pub channel_id: &'a str,
+    /// None in DMs, where guild limits don't apply
+    pub guild_id: Option<&'a str>,
+}
+
+/// The limit that stopped an invocation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Throttled {
+    pub command: String,
+    pub scope: LimitScope,
+    pub retry_after_secs: f64,
+}
+
+/// Takes one token from every bucket that applies to the invocation, or none of them
+/// if any bucket is empty. Buckets live in `rate_limit_buckets`, so cooldowns survive
+/// restarts. Throttled invocations are counted per day in `throttle_counts`.
+pub fn check_rate_limits(
+    db_path: &str,
+    rules: &[RateLimitRule],
+    invocation: &Invocation,
+    now: f64,
+) -> rusqlite::Result<Option<Throttled>> {
+    let applicable: Vec<(&RateLimitRule, String)> = rules
+        .iter()
+        .filter(|rule| rule_applies(&rule.command, invocation.qualified_name))
+        .filter_map(|rule| {
+            let id = match rule.scope {
+                LimitScope::User => Some(invocation.user_id),
+                LimitScope::Channel => Some(invocation.channel_id),
+                LimitScope::Guild => invocation.guild_id,
+            }?;
+            Some((
+                rule,
+                format!("{}|{}|{}", rule.command, rule.scope.as_str(), id),
+            ))
+        })
+        .collect();
+    if applicable.is_empty() {
+        return Ok(None);
+    }
+
+    let mut conn = Connection::open(db_path)?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let mut levels = Vec::with_capacity(applicable.len());
+    let mut throttled: Option<Throttled> = None;
+    for (rule, bucket) in &applicable {
+        let stored: Option<(f64, f64)> = tx
+            .query_row(
+                "SELECT tokens, updated_at FROM rate_limit_buckets WHERE bucket = ?1",
+                [bucket],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+        let tokens = match stored {
+            Some((tokens, updated_at)) => rule.limit.refill(tokens, updated_at, now),
+            None => rule.limit.capacity as f64,
+        };
+        if tokens < 1.0 {
+            let retry_after_secs = rule.limit.retry_after(tokens);
+            // Report the limit with the longest wait
+            if throttled
+                .as_ref()
+                .is_none_or(|t| retry_after_secs > t.retry_after_secs)
+            {
+                throttled = Some(Throttled {
+                    command: rule.command.clone(),
+                    scope: rule.scope,
+                    retry_after_secs,
+                });
+            }
+        }
+        levels.push(tokens);
+    }
+
+    match &throttled {
+        Some(throttled) => {
+            let day = chrono::DateTime::from_timestamp(now as i64, 0)
+                .unwrap_or_default()
+                .format("%Y-%m-%d")
+                .to_string();
+            tx.execute(
+                "INSERT INTO throttle_counts (day, command, scope, count) VALUES (?1, ?2, ?3, 1)
+                 ON CONFLICT (day, command, scope) DO UPDATE SET count = count + 1",
+                rusqlite::params![day, invocation.qualified_name, throttled.scope.as_str()],
+            )?;
+        }
+        None => {
+            for ((_, bucket), tokens) in applicable.iter().zip(levels) {
+                tx.execute(
+                    "INSERT INTO rate_limit_buckets (bucket, tokens, updated_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (bucket) DO UPDATE SET tokens = excluded.tokens, updated_at = excluded.updated_at",
+                    rusqlite::params![bucket, tokens - 1.0, now],
+                )?;
+            }
+        }
+    }
+    // Buckets untouched for the longest period have refilled and are the same as missing ones
+    let longest_period = rules
+        .iter()
+        .map(|rule| rule.limit.period_secs)
+        .fold(0.0, f64::max);
+    tx.execute(
+        "DELETE FROM rate_limit_buckets WHERE updated_at < ?1",
+        [now - longest_period],
+    )?;
+    tx.commit()?;
+    Ok(throttled)
+}
+
+/// Throttled invocations per command and scope since `since_day` (YYYY-MM-DD),
+/// most throttled first
+pub fn throttle_counts(
+    db_path: &str,
+    since_day: &str,
+) -> rusqlite::Result<Vec<(String, LimitScope, i64)>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT command, scope, SUM(count) AS total FROM throttle_counts WHERE day >= ?1
+         GROUP BY command, scope ORDER BY total DESC, command",
+    )?;
+    let rows = stmt.query_map([since_day], |row| {
+        let scope: String = row.get(1)?;
+        Ok((
+            row.get(0)?,
+            LimitScope::parse(&scope).unwrap_or(LimitScope::User),
+            row.get(2)?,
+        ))
+    })?;
+    rows.collect()
+}
+
+pub fn format_slow_down_response(
+    locale: &str,
+    qualified_name: &str,
+    throttled: &Throttled,
+) -> String {
+    let id = match throttled.scope {
+        LimitScope::User => "ratelimit-user",
+        LimitScope::Channel => "ratelimit-channel",
+        LimitScope::Guild => "ratelimit-guild",
+    };
+    tr_args(
+        locale,
+        id,
+        &fluent_args![
+            "command" => qualified_name,
+            "seconds" => throttled.retry_after_secs.ceil().max(1.0) as u64
+        ],
+    )
+}
+
+pub fn format_rate_limits_response(
+    locale: &str,
+    rules: &[RateLimitRule],
+    counts: &[(String, LimitScope, i64)],
+) -> String {
+    let mut output = format!("**{}**", tr(locale, "ratelimits-title"));
+    if rules.is_empty() {
+        output.push('\n');
+        output.push_str(&tr(locale, "ratelimits-none"));
+    }
+    for rule in rules {
+        output.push('\n');
+        output.push_str(&tr_args(
+            locale,
+            "ratelimits-rule",
+            &fluent_args![
+                "command" => rule.command.as_str(),
+                "scope" => rule.scope.as_str(),
+                "capacity" => rule.limit.capacity,
+                "seconds" => rule.limit.period_secs
+            ],
+        ));
+    }
+    output.push_str(&format!(
+        "\n**{}**",
+        tr(locale, "ratelimits-throttled-title")
+    ));
+    if counts.is_empty() {
+        output.push('\n');
+        output.push_str(&tr(locale, "ratelimits-nothing-throttled"));
+    }
+    for (command, scope, count) in counts {
+        output.push('\n');
+        output.push_str(&tr_args(
+            locale,
+            "ratelimits-throttled",
+            &fluent_args![
+                "command" => command.as_str(),
+                "scope" => scope.as_str(),
+                "count" => *count
+            ],
+        ));
+    }
+    output
+}
@@ -0,0 +1,119 @@
+use crate::{BotError, BotState};
+use colored::Colorize;
+use poise::serenity_prelude as serenity;
+use rusqlite::{Connection, OptionalExtension};
+
+/// Where application commands get registered
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegistrationTarget {
+    /// Globally, for production. Changes can take a while to reach every client.
+    Global,
+    /// In each of these guilds only, for development. Changes show up instantly.
+    Guilds(Vec<u64>),
+}
+
+impl RegistrationTarget {
+    /// Guild registration when dev guilds are configured, global otherwise
+    pub fn from_dev_guilds(dev_guild_ids: &[u64]) -> Self {
+        if dev_guild_ids.is_empty() {
+            RegistrationTarget::Global
+        } else {
+            RegistrationTarget::Guilds(dev_guild_ids.to_vec())
+        }
+    }
+}
+
+/// Key in `bot_meta` under which the fingerprint registered for `scope` is stored
+fn fingerprint_key(scope: &str) -> String {
+    format!("commands_fingerprint:{}", scope)
+}
+
+/// Stable fingerprint of the command set sent to Discord. Going through
+/// `serde_json::Value` sorts object keys, so localization maps hash the same every run.
+pub fn command_set_fingerprint(commands: &[serenity::CreateCommand]) -> String {
+    let json = serde_json::to_value(commands)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    format!("{:016x}", crate::fnv1a_64(json.as_bytes()))
+}
+
+/// Fingerprint last registered for `scope` ("global" or "guild:<id>"), if any
+pub fn stored_fingerprint(db_path: &str, scope: &str) -> rusqlite::Result<Option<String>> {
+    let conn = Connection::open(db_path)?;
+    conn.query_row(
+        "SELECT value FROM bot_meta WHERE key = ?1",
+        [fingerprint_key(scope)],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn store_fingerprint(db_path: &str, scope: &str, fingerprint: &str) -> rusqlite::Result<()> {
+    let conn = Connection::open(db_path)?;
+    conn.execute(
+        "INSERT INTO bot_meta (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        rusqlite::params![fingerprint_key(scope), fingerprint],
+    )?;
+    Ok(())
+}
+
+/// Forgets every stored fingerprint so the next startup registers commands again
+pub fn clear_fingerprints(db_path: &str) -> rusqlite::Result<usize> {
+    let conn = Connection::open(db_path)?;
+    conn.execute(
+        "DELETE FROM bot_meta WHERE key LIKE 'commands_fingerprint:%'",
+        [],
+    )
+}
+
+/// Registers `commands` at `target`, skipping every scope whose stored fingerprint
+/// matches the current command set.
+pub async fn register_commands(
+    http: impl AsRef<serenity::Http>,
+    commands: &[poise::Command<BotState, BotError>],
+    target: &RegistrationTarget,
+    db_path: &str,
+) -> Result<(), BotError> {
+    let create_commands = poise::builtins::create_application_commands(commands);
+    let fingerprint = command_set_fingerprint(&create_commands);
+    let scopes: Vec<(String, Option<serenity::GuildId>)> = match target {
+        RegistrationTarget::Global => vec![("global".to_string(), None)],
+        RegistrationTarget::Guilds(guild_ids) => guild_ids
+            .iter()
+            .map(|id| (format!("guild:{}", id), Some(serenity::GuildId::new(*id))))
+            .collect(),
+    };
+
+    for (scope, guild_id) in scopes {
+        if stored_fingerprint(db_path, &scope)?.as_deref() == Some(fingerprint.as_str()) {
+            println!(
+                "{}",
+                format!("Commands unchanged for {}, skipping registration", scope).white()
+            );
+            continue;
+        }
+        match guild_id {
+            Some(guild_id) => {
+                guild_id
+                    .set_commands(http.as_ref(), create_commands.clone())
+                    .await?;
+            }
+            None => {
+                serenity::Command::set_global_commands(http.as_ref(), create_commands.clone())
+                    .await?;
+            }
+        }
+        store_fingerprint(db_path, &scope, &fingerprint)?;
+        println!(
+            "{}",
+            format!(
+                "Registered {} commands for {}",
+                create_commands.len(),
+                scope
+            )
+            .white()
+            .on_cyan()
+        );
+    }
+    Ok(())
+}
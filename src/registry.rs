@@ -0,0 +1,182 @@
+use crate::i18n::{tr, tr_args};
+use crate::{CodenameData, capitalize_first, generate_codename};
+use fluent::fluent_args;
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+/// How many times unique mode re-rolls before giving up on finding an unclaimed codename
+pub const MAX_UNIQUE_ATTEMPTS: usize = 50;
+
+/// A codename claimed in a guild's `codename_registry`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub guild_id: String,
+    pub codename: String,
+    pub owner_id: String,
+    pub owner_name: String,
+    pub description: String,
+    pub claimed_at: String,
+}
+
+/// Returns true if `codename` is an adjective followed by an animal from `codename_data`,
+/// i.e. something `/codename generate` could have produced in any `CodenameStyle`.
+/// Comparison ignores case and treats `-` and `_` as spaces.
+pub fn is_known_codename(codename_data: &CodenameData, codename: &str) -> bool {
+    canonical_codename(codename_data, codename).is_some()
+}
+
+/// The spelling `/codename generate` uses for `codename` (e.g. "Quick Fox" for
+/// "quick-fox"), or None if it isn't a known codename. The registry stores and looks up
+/// codenames in this form only.
+pub fn canonical_codename(codename_data: &CodenameData, codename: &str) -> Option<String> {
+    let unstyle = |s: &str| s.trim().to_lowercase().replace(['-', '_'], " ");
+    let codename = unstyle(codename);
+    codename_data.adjectives.iter().find_map(|adjective| {
+        let animal = codename
+            .strip_prefix(&unstyle(adjective))
+            .and_then(|rest| rest.strip_prefix(' '))?;
+        codename_data
+            .animals
+            .iter()
+            .find(|known| {
+                unstyle(known) == animal && codename_data.blocklist.allows(adjective, known)
+            })
+            .map(|known| {
+                format!(
+                    "{} {}",
+                    capitalize_first(adjective),
+                    capitalize_first(known)
+                )
+            })
+    })
+}
+
+/// Generate a codename that is not in `taken` (canonical names), re-rolling up to
+/// `max_attempts` times.
+pub fn generate_codename_avoiding(
+    codename_data: &CodenameData,
+    taken: &HashSet<String>,
+    max_attempts: usize,
+) -> Result<String, String> {
+    for _ in 0..max_attempts {
+        let codename = generate_codename(codename_data)?;
+        if !taken.contains(&codename) {
+            return Ok(codename);
+        }
+    }
+    Err(format!(
+        "Could not find an unclaimed codename after {} attempts",
+        max_attempts
+    ))
+}
+
+/// Canonical names of every codename claimed in the guild
+pub fn claimed_codenames(db_path: &str, guild_id: &str) -> rusqlite::Result<HashSet<String>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare("SELECT codename FROM codename_registry WHERE guild_id = ?1")?;
+    let rows = stmt.query_map([guild_id], |row| row.get::<_, String>(0))?;
+    rows.collect()
+}
+
+/// Claim `codename` (in its `canonical_codename` form) for `owner_id` in the guild.
+/// Returns `Ok(false)` if the name is already claimed there.
+pub fn claim_codename(
+    db_path: &str,
+    guild_id: &str,
+    codename: &str,
+    owner_id: &str,
+    owner_name: &str,
+    description: &str,
+) -> rusqlite::Result<bool> {
+    let conn = Connection::open(db_path)?;
+    let claimed_at = chrono::Utc::now().to_rfc3339();
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO codename_registry (guild_id, codename, owner_id, owner_name, description, claimed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![guild_id, codename, owner_id, owner_name, description, claimed_at],
+    )?;
+    Ok(inserted == 1)
+}
+
+/// All codenames claimed in the guild, oldest claim first
+pub fn list_claimed_codenames(
+    db_path: &str,
+    guild_id: &str,
+) -> rusqlite::Result<Vec<RegistryEntry>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT guild_id, codename, owner_id, owner_name, description, claimed_at FROM codename_registry WHERE guild_id = ?1 ORDER BY claimed_at ASC, id ASC",
+    )?;
+    let rows = stmt.query_map([guild_id], |row| {
+        Ok(RegistryEntry {
+            guild_id: row.get(0)?,
+            codename: row.get(1)?,
+            owner_id: row.get(2)?,
+            owner_name: row.get(3)?,
+            description: row.get(4)?,
+            claimed_at: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Release a claimed codename, given in its `canonical_codename` form. When `owner_id`
+/// is `Some`, only that owner's claim is
+/// removed; `None` releases it regardless of owner. Returns true if a claim was removed.
+pub fn release_codename(
+    db_path: &str,
+    guild_id: &str,
+    codename: &str,
+    owner_id: Option<&str>,
+) -> rusqlite::Result<bool> {
+    let conn = Connection::open(db_path)?;
+    let removed = match owner_id {
+        Some(owner_id) => conn.execute(
+            "DELETE FROM codename_registry WHERE guild_id = ?1 AND codename = ?2 AND owner_id = ?3",
+            rusqlite::params![guild_id, codename, owner_id],
+        )?,
+        None => conn.execute(
+            "DELETE FROM codename_registry WHERE guild_id = ?1 AND codename = ?2",
+            rusqlite::params![guild_id, codename],
+        )?,
+    };
+    Ok(removed > 0)
+}
+
+pub fn format_claim_response(
+    locale: &str,
+    codename: &str,
+    owner_name: &str,
+    description: &str,
+) -> String {
+    let args = fluent_args![
+        "codename" => codename,
+        "owner" => owner_name,
+        "description" => description
+    ];
+    if description.is_empty() {
+        tr_args(locale, "codename-claimed", &args)
+    } else {
+        tr_args(locale, "codename-claimed-for", &args)
+    }
+}
+
+pub fn format_codename_list_response(locale: &str, entries: &[RegistryEntry]) -> String {
+    if entries.is_empty() {
+        return tr(locale, "codename-list-empty");
+    }
+    let mut response = tr_args(
+        locale,
+        "codename-list-header",
+        &fluent_args!["count" => entries.len()],
+    );
+    for entry in entries {
+        response.push_str(&format!(
+            "\n- **{}** ({})",
+            entry.codename, entry.owner_name
+        ));
+        if !entry.description.is_empty() {
+            response.push_str(&format!(": {}", entry.description));
+        }
+    }
+    response
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude as serenity;
+
+/// Accent colour used when a response does not pick its own
+pub const DEFAULT_COLOR: u32 = 0x5865F2;
+/// Accent colour of codename responses
+pub const CODENAME_COLOR: u32 = 0x57F287;
+/// Accent colour of avatar and user responses
+pub const USER_COLOR: u32 = 0xEB459E;
+
+/// ### A command reply that is sent to Discord as an embed
+/// `to_text` renders the same content as plain text for the history table and the
+/// dashboard feed, which cannot show embeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BotResponse {
+    pub title: Option<String>,
+    pub description: String,
+    pub color: u32,
+    pub thumbnail: Option<String>,
+    pub image: Option<String>,
+    /// Inline name/value pairs shown below the description
+    pub fields: Vec<(String, String)>,
+    pub footer: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl BotResponse {
+    /// A response with only a description, stamped with the current time
+    pub fn new(description: impl Into<String>) -> Self {
+        BotResponse {
+            title: None,
+            description: description.into(),
+            color: DEFAULT_COLOR,
+            thumbnail: None,
+            image: None,
+            fields: Vec::new(),
+            footer: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn color(mut self, color: u32) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn thumbnail(mut self, url: impl Into<String>) -> Self {
+        self.thumbnail = Some(url.into());
+        self
+    }
+
+    pub fn image(mut self, url: impl Into<String>) -> Self {
+        self.image = Some(url.into());
+        self
+    }
+
+    pub fn field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn footer(mut self, footer: impl Into<String>) -> Self {
+        self.footer = Some(footer.into());
+        self
+    }
+
+    /// Builds the serenity embed for this response
+    pub fn to_embed(&self) -> serenity::CreateEmbed {
+        let mut embed = serenity::CreateEmbed::new().colour(self.color).timestamp(
+            serenity::Timestamp::from_unix_timestamp(self.timestamp.timestamp())
+                .unwrap_or_else(|_| serenity::Timestamp::now()),
+        );
+        if !self.description.is_empty() {
+            embed = embed.description(&self.description);
+        }
+        if let Some(title) = &self.title {
+            embed = embed.title(title);
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            embed = embed.thumbnail(thumbnail);
+        }
+        if let Some(image) = &self.image {
+            embed = embed.image(image);
+        }
+        for (name, value) in &self.fields {
+            embed = embed.field(name, value, true);
+        }
+        if let Some(footer) = &self.footer {
+            embed = embed.footer(serenity::CreateEmbedFooter::new(footer));
+        }
+        embed
+    }
+
+    /// Plain-text rendering of the embed for the feed and the history table.
+    /// A response with only a description renders as exactly that description.
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(title) = &self.title {
+            lines.push(format!("**{}**", title));
+        }
+        if !self.description.is_empty() {
+            lines.push(self.description.clone());
+        }
+        for (name, value) in &self.fields {
+            lines.push(format!("{}: {}", name, value));
+        }
+        // A banner image and an avatar thumbnail are both kept
+        lines.extend(self.thumbnail.iter().chain(&self.image).cloned());
+        if let Some(footer) = &self.footer {
+            lines.push(format!("_{}_", footer));
+        }
+        lines.join("\n")
+    }
+}
+
+impl From<String> for BotResponse {
+    fn from(description: String) -> Self {
+        BotResponse::new(description)
+    }
+}
+
+impl From<&str> for BotResponse {
+    fn from(description: &str) -> Self {
+        BotResponse::new(description)
+    }
+}
@@ -0,0 +1,135 @@
+use crate::events::prune_events;
+use crate::settings::SettingKey;
+use crate::store::{HistoryStore, SqliteHistoryStore};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How often the background task prunes when `HISTORY_PRUNE_INTERVAL_HOURS` is not set
+pub const DEFAULT_PRUNE_INTERVAL_HOURS: u64 = 24;
+
+/// ### How much command history to keep
+/// Unset limits keep everything. Guilds can override `max_age_days` with the
+/// `history_retention_days` setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Rows older than this many days are removed
+    pub max_age_days: Option<u32>,
+    /// Only the newest this many rows are kept, across all guilds
+    pub max_rows: Option<u64>,
+}
+
+/// Rows removed by one `prune_history` run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Rows past the global or their guild's maximum age
+    pub expired: usize,
+    /// Rows beyond `max_rows`
+    pub trimmed: usize,
+}
+
+impl PruneReport {
+    pub fn total(&self) -> usize {
+        self.expired + self.trimmed
+    }
+}
+
+/// Retention days of every guild with a valid `history_retention_days` setting
+pub fn guild_retention_overrides(db_path: &str) -> rusqlite::Result<HashMap<String, u32>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare("SELECT guild_id, value FROM guild_settings WHERE key = ?1")?;
+    let rows = stmt.query_map([SettingKey::HistoryRetentionDays.name()], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    rows.filter_map(|row| match row {
+        Ok((guild_id, days)) => days.parse().ok().map(|days| Ok((guild_id, days))),
+        Err(e) => Some(Err(e)),
+    })
+    .collect()
+}
+
+/// Removes rows of the SQLite history outside the retention policy. Guilds with a
+/// `history_retention_days` setting use it instead of `max_age_days`.
+pub fn prune_history(
+    db_path: &str,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<PruneReport, String> {
+    let overrides = guild_retention_overrides(db_path)
+        .map_err(|e| format!("Failed to load retention overrides: {}", e))?;
+    SqliteHistoryStore::new(db_path).prune(policy, &overrides, now)
+}
+
+/// Refreshes the query planner statistics, and reclaims free pages with `VACUUM` when
+/// `vacuum` is set (it rewrites the whole file, so only worth it after deletes)
+pub fn maintain_database(db_path: &str, vacuum: bool) -> Result<(), String> {
+    SqliteHistoryStore::new(db_path).maintain(vacuum)
+}
+
+/// Prunes the SQLite history and runs database maintenance
+pub fn prune_and_maintain(
+    db_path: &str,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<PruneReport, String> {
+    prune_store(&SqliteHistoryStore::new(db_path), db_path, policy, now)
+}
+
+/// Prunes `history` and runs its maintenance, as done by the background task and
+/// `/history prune`. The guild overrides are read from the SQLite database at `db_path`,
+/// whose feed events expire the same way (they are not in the report).
+pub fn prune_store(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<PruneReport, String> {
+    let overrides = guild_retention_overrides(db_path)
+        .map_err(|e| format!("Failed to load retention overrides: {}", e))?;
+    let report = history.prune(policy, &overrides, now)?;
+    prune_events(db_path, policy.max_age_days, &overrides, now)
+        .map_err(|e| format!("Failed to prune feed events: {}", e))?;
+    history.maintain(report.total() > 0)?;
+    Ok(report)
+}
+
+/// Prunes the history right away and then every `interval_hours`. An interval of 0
+/// disables the task.
+pub fn spawn_history_pruning(
+    history: Arc<dyn HistoryStore>,
+    db_path: String,
+    policy: RetentionPolicy,
+    interval_hours: u64,
+) {
+    if interval_hours == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(interval_hours * 3600));
+        loop {
+            interval.tick().await;
+            let history = history.clone();
+            let db_path = db_path.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                prune_store(&*history, &db_path, &policy, Utc::now())
+            })
+            .await;
+            match result {
+                Ok(Ok(report)) if report.total() > 0 => println!(
+                    "{}",
+                    format!(
+                        "Pruned command history: {} expired, {} over the row limit",
+                        report.expired, report.trimmed
+                    )
+                    .white()
+                ),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => println!("{}", format!("History pruning failed: {}", e).red()),
+                Err(e) => println!("{}", format!("History pruning panicked: {}", e).red()),
+            }
+        }
+    });
+}
@@ -0,0 +1,345 @@
+use crate::i18n::tr;
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// Locales a guild can pick with `/config set locale`, each with a catalog under
+/// `assets/locales`
+pub const SUPPORTED_LOCALES: &[&str] = &["en-US", "de", "fr"];
+/// `locale` value that replies in each user's own Discord language
+pub const AUTO_LOCALE: &str = "auto";
+/// `history_retention_days` value that uses the bot-wide `HISTORY_MAX_AGE_DAYS`
+pub const DEFAULT_VALUE: &str = "default";
+/// Longest `history_retention_days` a guild can pick, 100 years
+pub const MAX_RETENTION_DAYS: u32 = 36500;
+
+/// How generated codenames are written out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodenameStyle {
+    /// `Quick Fox`
+    Title,
+    /// `quick fox`
+    Lower,
+    /// `QUICK FOX`
+    Upper,
+    /// `quick-fox`
+    Kebab,
+    /// `quick_fox`
+    Snake,
+}
+
+impl CodenameStyle {
+    pub const ALL: [CodenameStyle; 5] = [
+        CodenameStyle::Title,
+        CodenameStyle::Lower,
+        CodenameStyle::Upper,
+        CodenameStyle::Kebab,
+        CodenameStyle::Snake,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodenameStyle::Title => "title",
+            CodenameStyle::Lower => "lower",
+            CodenameStyle::Upper => "upper",
+            CodenameStyle::Kebab => "kebab",
+            CodenameStyle::Snake => "snake",
+        }
+    }
+
+    pub fn parse(style: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str().eq_ignore_ascii_case(style))
+    }
+
+    /// Rewrites a generated codename (`Quick Fox`) in this style
+    pub fn apply(&self, codename: &str) -> String {
+        match self {
+            CodenameStyle::Title => codename.to_string(),
+            CodenameStyle::Lower => codename.to_lowercase(),
+            CodenameStyle::Upper => codename.to_uppercase(),
+            CodenameStyle::Kebab => codename.to_lowercase().replace(' ', "-"),
+            CodenameStyle::Snake => codename.to_lowercase().replace(' ', "_"),
+        }
+    }
+}
+
+/// ### Typed per-guild preferences, stored as key/value rows in `guild_settings`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuildSettings {
+    /// Broadcast this guild's commands to the web feed
+    pub feed_visible: bool,
+    /// Send command replies so only the invoking user sees them
+    pub ephemeral_replies: bool,
+    /// Style of codenames produced by `/codename generate` and the context menus
+    pub codename_style: CodenameStyle,
+    /// One of `SUPPORTED_LOCALES`, or None to follow each user's Discord locale
+    pub locale: Option<String>,
+    /// Record this guild's commands in the command history
+    pub history_logging: bool,
+    /// Days this guild's history is kept, overriding `HISTORY_MAX_AGE_DAYS`
+    pub history_retention_days: Option<u32>,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        GuildSettings {
+            feed_visible: true,
+            ephemeral_replies: false,
+            codename_style: CodenameStyle::Title,
+            locale: None,
+            history_logging: true,
+            history_retention_days: None,
+        }
+    }
+}
+
+/// A setting `/config` can read and change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingKey {
+    FeedVisible,
+    EphemeralReplies,
+    CodenameStyle,
+    Locale,
+    HistoryLogging,
+    HistoryRetentionDays,
+}
+
+impl SettingKey {
+    pub const ALL: [SettingKey; 6] = [
+        SettingKey::FeedVisible,
+        SettingKey::EphemeralReplies,
+        SettingKey::CodenameStyle,
+        SettingKey::Locale,
+        SettingKey::HistoryLogging,
+        SettingKey::HistoryRetentionDays,
+    ];
+
+    /// Name used in `/config` and the `key` column
+    pub fn name(&self) -> &'static str {
+        match self {
+            SettingKey::FeedVisible => "feed_visible",
+            SettingKey::EphemeralReplies => "ephemeral_replies",
+            SettingKey::CodenameStyle => "codename_style",
+            SettingKey::Locale => "locale",
+            SettingKey::HistoryLogging => "history_logging",
+            SettingKey::HistoryRetentionDays => "history_retention_days",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|key| key.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Catalog description of this key in `locale`
+    pub fn description(&self, locale: &str) -> String {
+        tr(locale, &format!("setting-{}", self.name()))
+    }
+
+    /// Values accepted by `validate`, for error messages
+    pub fn allowed_values(&self) -> Vec<&'static str> {
+        match self {
+            SettingKey::FeedVisible | SettingKey::EphemeralReplies | SettingKey::HistoryLogging => {
+                vec!["true", "false"]
+            }
+            SettingKey::CodenameStyle => CodenameStyle::ALL.iter().map(|s| s.as_str()).collect(),
+            SettingKey::Locale => std::iter::once(AUTO_LOCALE)
+                .chain(SUPPORTED_LOCALES.iter().copied())
+                .collect(),
+            SettingKey::HistoryRetentionDays => vec![DEFAULT_VALUE, "1-36500"],
+        }
+    }
+
+    /// Checks `value` for this key and returns it in its stored form
+    pub fn validate(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        let normalized = match self {
+            SettingKey::FeedVisible | SettingKey::EphemeralReplies | SettingKey::HistoryLogging => {
+                parse_bool(value).map(|b| b.to_string())
+            }
+            SettingKey::CodenameStyle => {
+                CodenameStyle::parse(value).map(|s| s.as_str().to_string())
+            }
+            SettingKey::Locale => std::iter::once(&AUTO_LOCALE)
+                .chain(SUPPORTED_LOCALES)
+                .find(|locale| locale.eq_ignore_ascii_case(value))
+                .map(|locale| locale.to_string()),
+            SettingKey::HistoryRetentionDays if value.eq_ignore_ascii_case(DEFAULT_VALUE) => {
+                Some(DEFAULT_VALUE.to_string())
+            }
+            SettingKey::HistoryRetentionDays => value
+                .parse::<u32>()
+                .ok()
+                .filter(|days| (1..=MAX_RETENTION_DAYS).contains(days))
+                .map(|days| days.to_string()),
+        };
+        normalized.ok_or_else(|| {
+            format!(
+                "`{}` is not a valid value for `{}`, expected one of: {}",
+                value,
+                self.name(),
+                self.allowed_values().join(", ")
+            )
+        })
+    }
+
+    /// Validates `value` and sets this key on `settings`
+    fn apply(&self, settings: &mut GuildSettings, value: &str) -> Result<(), String> {
+        let value = self.validate(value)?;
+        match self {
+            SettingKey::FeedVisible => settings.feed_visible = value == "true",
+            SettingKey::EphemeralReplies => settings.ephemeral_replies = value == "true",
+            SettingKey::CodenameStyle => {
+                settings.codename_style =
+                    CodenameStyle::parse(&value).unwrap_or(CodenameStyle::Title)
+            }
+            SettingKey::Locale => settings.locale = Some(value).filter(|v| v != AUTO_LOCALE),
+            SettingKey::HistoryLogging => settings.history_logging = value == "true",
+            SettingKey::HistoryRetentionDays => {
+                settings.history_retention_days = value.parse().ok()
+            }
+        }
+        Ok(())
+    }
+
+    /// Current value of this key in `settings`, in stored form
+    pub fn value_in(&self, settings: &GuildSettings) -> String {
+        match self {
+            SettingKey::FeedVisible => settings.feed_visible.to_string(),
+            SettingKey::EphemeralReplies => settings.ephemeral_replies.to_string(),
+            SettingKey::CodenameStyle => settings.codename_style.as_str().to_string(),
+            SettingKey::Locale => settings
+                .locale
+                .clone()
+                .unwrap_or_else(|| AUTO_LOCALE.to_string()),
+            SettingKey::HistoryLogging => settings.history_logging.to_string(),
+            SettingKey::HistoryRetentionDays => settings
+                .history_retention_days
+                .map_or_else(|| DEFAULT_VALUE.to_string(), |days| days.to_string()),
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Some(true),
+        "false" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads a guild's settings. Missing keys use their defaults; unknown keys and values
+/// that no longer validate are ignored.
+pub fn load_guild_settings(db_path: &str, guild_id: &str) -> rusqlite::Result<GuildSettings> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare("SELECT key, value FROM guild_settings WHERE guild_id = ?1")?;
+    let rows = stmt.query_map([guild_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut settings = GuildSettings::default();
+    for row in rows {
+        let (key, value) = row?;
+        if let Some(key) = SettingKey::parse(&key) {
+            key.apply(&mut settings, &value).ok();
+        }
+    }
+    Ok(settings)
+}
+
+/// Guilds that hide themselves from the feed and the stats with `feed_visible`
+pub fn hidden_guilds(db_path: &str) -> rusqlite::Result<HashSet<String>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt =
+        conn.prepare("SELECT guild_id FROM guild_settings WHERE key = ?1 AND value = 'false'")?;
+    let rows = stmt.query_map([SettingKey::FeedVisible.name()], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Stores a setting. `value` should already have passed `SettingKey::validate`.
+pub fn store_setting(
+    db_path: &str,
+    guild_id: &str,
+    key: SettingKey,
+    value: &str,
+    updated_by: &str,
+) -> rusqlite::Result<()> {
+    let conn = Connection::open(db_path)?;
+    let updated_at = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO guild_settings (guild_id, key, value, updated_by, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (guild_id, key) DO UPDATE SET value = excluded.value, updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+        rusqlite::params![guild_id, key.name(), value, updated_by, updated_at],
+    )?;
+    Ok(())
+}
+
+/// Removes a stored setting so the default applies again. Returns false if it was unset.
+pub fn reset_setting(db_path: &str, guild_id: &str, key: SettingKey) -> rusqlite::Result<bool> {
+    let conn = Connection::open(db_path)?;
+    let removed = conn.execute(
+        "DELETE FROM guild_settings WHERE guild_id = ?1 AND key = ?2",
+        rusqlite::params![guild_id, key.name()],
+    )?;
+    Ok(removed > 0)
+}
+
+/// ### In-memory cache of `guild_settings`, filled on first use of each guild
+/// Callers load missing guilds with `load_guild_settings` (from a blocking task) and
+/// `insert` them. Writes go to the database and then invalidate the guild's entry.
+#[derive(Default)]
+pub struct SettingsCache {
+    guilds: RwLock<HashMap<String, Arc<GuildSettings>>>,
+}
+
+impl SettingsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cached settings of a guild, if loaded
+    pub fn get(&self, guild_id: &str) -> Option<Arc<GuildSettings>> {
+        self.guilds
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(guild_id)
+            .cloned()
+    }
+
+    /// Caches freshly loaded settings and returns them
+    pub fn insert(&self, guild_id: &str, settings: GuildSettings) -> Arc<GuildSettings> {
+        let settings = Arc::new(settings);
+        self.guilds
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(guild_id.to_string(), settings.clone());
+        settings
+    }
+
+    /// Drops a guild's cached settings so the next `get` reads them again
+    pub fn invalidate(&self, guild_id: &str) {
+        self.guilds
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(guild_id);
+    }
+}
+
+pub fn format_settings_response(locale: &str, settings: &GuildSettings) -> String {
+    let lines: Vec<String> = SettingKey::ALL
+        .iter()
+        .map(|key| format_setting_line(locale, *key, settings))
+        .collect();
+    format!("**{}**\n{}", tr(locale, "config-title"), lines.join("\n"))
+}
+
+pub fn format_setting_line(locale: &str, key: SettingKey, settings: &GuildSettings) -> String {
+    format!(
+        "`{}` = `{}` ({})",
+        key.name(),
+        key.value_in(settings),
+        key.description(locale)
+    )
+}
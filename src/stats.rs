@@ -0,0 +1,279 @@
+use crate::export::HistoryFilter;
+use crate::i18n::{tr, tr_args};
+use crate::privacy::opted_out_users;
+use crate::response::BotResponse;
+
+use crate::store::{HistoryGroup, HistoryStore, SqliteHistoryStore, StoreResult, UserCount};
+use chrono::{DateTime, NaiveDate, Utc};
+use fluent::fluent_args;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Days covered by `/stats` and `/leaderboard` when no `days` option is given
+pub const DEFAULT_STATS_DAYS: u32 = 30;
+/// Longest window the commands accept
+pub const MAX_STATS_DAYS: u32 = 365;
+/// Entries in each top list of `/stats`
+const TOP_LIMIT: usize = 5;
+/// Hours listed under busiest hours
+const BUSIEST_HOURS_LIMIT: usize = 3;
+/// Users shown by `/leaderboard`
+pub const LEADERBOARD_LIMIT: usize = 10;
+
+/// ### Command usage of a guild over the last `days` days
+/// Only commands that ran are counted, failed and denied attempts are not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageStats {
+    pub days: u32,
+    pub total: u64,
+    /// Most used qualified command names, busiest first
+    pub top_commands: Vec<(String, u64)>,
+    /// Most active users, leaving out those who opted out with `/privacy optout`
+    pub top_users: Vec<UserCount>,
+    /// One entry per day of the window (UTC), oldest first, days without usage included
+    pub daily: Vec<(NaiveDate, u64)>,
+    /// Hours of the day (UTC) with the most usage, busiest first
+    pub busiest_hours: Vec<(u32, u64)>,
+}
+
+/// Commands of `guild_id` that ran in the `days` days before `now`
+fn window_filter(guild_id: &str, days: u32, now: DateTime<Utc>) -> HistoryFilter {
+    HistoryFilter {
+        since: Some(now - chrono::Duration::days(days as i64)),
+        guild_id: Some(guild_id.to_string()),
+        ..HistoryFilter::default()
+    }
+}
+
+/// Counts per value of a single `group`, keyed by `parse`d key. Keys that don't parse
+/// are dropped.
+fn counts_by<K: Eq + Hash>(
+    history: &dyn HistoryStore,
+    filter: &HistoryFilter,
+    group: HistoryGroup,
+    parse: impl Fn(&str) -> Option<K>,
+) -> StoreResult<HashMap<K, u64>> {
+    Ok(history
+        .count_by(filter, &[group])?
+        .into_iter()
+        .filter_map(|group| Some((parse(group.keys.first()?)?, group.count)))
+        .collect())
+}
+
+/// The `limit` entries with the highest counts, ties broken by key
+fn top_counts<K: Ord>(counts: HashMap<K, u64>, limit: usize) -> Vec<(K, u64)> {
+    let mut counts: Vec<(K, u64)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts.truncate(limit);
+    counts
+}
+
+/// The `limit` users of `history` with the most commands matching `filter`, leaving
+/// out those who opted out (according to the SQLite database at `db_path`)
+fn top_users(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    filter: &HistoryFilter,
+    limit: usize,
+) -> StoreResult<Vec<UserCount>> {
+    let opted_out =
+        opted_out_users(db_path).map_err(|e| format!("Failed to load privacy opt-outs: {}", e))?;
+    let mut users: Vec<UserCount> = history
+        .user_counts(filter)?
+        .into_iter()
+        .filter(|user| !opted_out.contains(&user.user_id))
+        .collect();
+    users.sort_by(|a, b| b.commands.cmp(&a.commands).then(a.user_id.cmp(&b.user_id)));
+    users.truncate(limit);
+    Ok(users)
+}
+
+/// Aggregates the usage of `guild_id` in the SQLite history over the `days` days
+/// before `now`
+pub fn usage_stats(
+    db_path: &str,
+    guild_id: &str,
+    days: u32,
+    now: DateTime<Utc>,
+) -> StoreResult<UsageStats> {
+    usage_stats_from(
+        &SqliteHistoryStore::new(db_path),
+        db_path,
+        guild_id,
+        days,
+        now,
+    )
+}
+
+/// Aggregates the usage of `guild_id` in `history` over the `days` days before `now`.
+/// Opt-outs are read from the SQLite database at `db_path`.
+pub fn usage_stats_from(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    guild_id: &str,
+    days: u32,
+    now: DateTime<Utc>,
+) -> StoreResult<UsageStats> {
+    let filter = window_filter(guild_id, days, now);
+    let commands = counts_by(history, &filter, HistoryGroup::Command, |command| {
+        Some(command.to_string())
+    })?;
+    let by_day = counts_by(history, &filter, HistoryGroup::TimePrefix(10), |day| {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()
+    })?;
+    let by_hour = counts_by(history, &filter, HistoryGroup::Hour, |hour| {
+        hour.parse::<u32>().ok()
+    })?;
+
+    let today = now.date_naive();
+    let daily = (0..days as i64)
+        .rev()
+        .map(|ago| {
+            let day = today - chrono::Duration::days(ago);
+            (day, by_day.get(&day).copied().unwrap_or(0))
+        })
+        .collect();
+
+    Ok(UsageStats {
+        days,
+        total: history.count(&filter)?,
+        top_commands: top_counts(commands, TOP_LIMIT),
+        top_users: top_users(history, db_path, &filter, TOP_LIMIT)?,
+        daily,
+        busiest_hours: top_counts(by_hour, BUSIEST_HOURS_LIMIT),
+    })
+}
+
+/// The `LEADERBOARD_LIMIT` most active users of `guild_id` in the SQLite history over
+/// the `days` days before `now`, most active first
+pub fn leaderboard(
+    db_path: &str,
+    guild_id: &str,
+    days: u32,
+    now: DateTime<Utc>,
+) -> StoreResult<Vec<UserCount>> {
+    leaderboard_from(
+        &SqliteHistoryStore::new(db_path),
+        db_path,
+        guild_id,
+        days,
+        now,
+    )
+}
+
+/// Like `leaderboard`, counting the commands in `history`. Users who opted out
+/// (according to the SQLite database at `db_path`) are left out.
+pub fn leaderboard_from(
+    history: &dyn HistoryStore,
+    db_path: &str,
+    guild_id: &str,
+    days: u32,
+    now: DateTime<Utc>,
+) -> StoreResult<Vec<UserCount>> {
+    top_users(
+        history,
+        db_path,
+        &window_filter(guild_id, days, now),
+        LEADERBOARD_LIMIT,
+    )
+}
+
+/// Renders counts as a row of block characters scaled to the largest count
+pub fn sparkline(counts: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|&count| {
+            if max == 0 {
+                BARS[0]
+            } else {
+                BARS[((count * (BARS.len() as u64 - 1)) as f64 / max as f64).round() as usize]
+            }
+        })
+        .collect()
+}
+
+fn ranked_lines(lines: impl Iterator<Item = String>) -> String {
+    lines
+        .enumerate()
+        .map(|(index, line)| format!("{}. {}", index + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_stats_response(locale: &str, stats: &UsageStats) -> BotResponse {
+    let title = tr_args(locale, "stats-title", &fluent_args!["days" => stats.days]);
+    if stats.total == 0 {
+        return BotResponse::new(tr(locale, "stats-empty")).title(title);
+    }
+    let counts: Vec<u64> = stats.daily.iter().map(|(_, count)| *count).collect();
+    let mut description = tr_args(locale, "stats-total", &fluent_args!["count" => stats.total]);
+    if let (Some((first, _)), Some((last, _))) = (stats.daily.first(), stats.daily.last()) {
+        description.push_str(&format!(
+            "\n`{}`\n{} – {}",
+            sparkline(&counts),
+            first.format("%Y-%m-%d"),
+            last.format("%Y-%m-%d")
+        ));
+    }
+    if let Some((day, count)) = stats.daily.iter().max_by_key(|(day, count)| (*count, *day)) {
+        description.push('\n');
+        description.push_str(&tr_args(
+            locale,
+            "stats-peak-day",
+            &fluent_args!["date" => day.format("%Y-%m-%d").to_string(), "count" => *count],
+        ));
+    }
+
+    let mut response = BotResponse::new(description).title(title);
+    if !stats.top_commands.is_empty() {
+        response = response.field(
+            tr(locale, "stats-top-commands"),
+            ranked_lines(
+                stats
+                    .top_commands
+                    .iter()
+                    .map(|(command, count)| format!("`/{}` ({})", command, count)),
+            ),
+        );
+    }
+    if !stats.top_users.is_empty() {
+        response = response.field(
+            tr(locale, "stats-top-users"),
+            ranked_lines(
+                stats
+                    .top_users
+                    .iter()
+                    .map(|user| format!("{} ({})", user.username, user.commands)),
+            ),
+        );
+    }
+    if !stats.busiest_hours.is_empty() {
+        response = response.field(
+            tr(locale, "stats-busiest-hours"),
+            ranked_lines(
+                stats
+                    .busiest_hours
+                    .iter()
+                    .map(|(hour, count)| format!("{:02}:00 ({})", hour, count)),
+            ),
+        );
+    }
+    response
+}
+
+pub fn format_leaderboard_response(locale: &str, days: u32, entries: &[UserCount]) -> BotResponse {
+    let title = tr_args(locale, "leaderboard-title", &fluent_args!["days" => days]);
+    if entries.is_empty() {
+        return BotResponse::new(tr(locale, "stats-empty")).title(title);
+    }
+    let description = ranked_lines(entries.iter().map(|entry| {
+        tr_args(
+            locale,
+            "leaderboard-entry",
+            &fluent_args!["name" => entry.username.as_str(), "count" => entry.commands],
+        )
+    }));
+    BotResponse::new(description).title(title)
+}
@@ -0,0 +1,126 @@
+use crate::i18n::{tr, tr_args};
+use crate::response::{BotResponse, USER_COLOR};
+use chrono::{DateTime, Utc};
+use fluent::fluent_args;
+
+/// Discord's epoch (2015-01-01T00:00:00Z) in milliseconds since the Unix epoch
+pub const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+/// Most characters Discord accepts in an embed field value
+pub const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Creation time encoded in a Discord snowflake ID
+pub fn snowflake_timestamp(id: u64) -> DateTime<Utc> {
+    let millis = (id >> 22) + DISCORD_EPOCH_MS;
+    DateTime::from_timestamp_millis(millis as i64).unwrap_or_default()
+}
+
+/// Human readable age between `from` and `now`, e.g. "3 years, 2 months, 5 days"
+pub fn format_age(locale: &str, from: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let days = (now - from).num_days().max(0);
+    if days == 0 {
+        return tr(locale, "age-less-than-a-day");
+    }
+    let (years, months, days) = (days / 365, days % 365 / 30, days % 365 % 30);
+    [
+        (years, "age-years"),
+        (months, "age-months"),
+        (days, "age-days"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, unit)| tr_args(locale, unit, &fluent_args!["count" => *count]))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// What `/userinfo` knows about a user
+pub struct UserInfo<'a> {
+    pub user_id: u64,
+    pub user_name: &'a str,
+    pub avatar_url: &'a str,
+    /// When the user joined the current server, if run in one
+    pub joined_at: Option<DateTime<Utc>>,
+    /// Role mentions of the user in the current server
+    pub roles: Vec<String>,
+    /// Rows in command_history for this user
+    pub commands_run: u64,
+}
+
+fn format_date_and_age(locale: &str, date: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    tr_args(
+        locale,
+        "userinfo-date-age",
+        &fluent_args![
+            "date" => date.format("%Y-%m-%d").to_string(),
+            "age" => format_age(locale, date, now)
+        ],
+    )
+}
+
+/// Role mentions separated by spaces. Roles that don't fit in `MAX_FIELD_LENGTH`
+/// characters are left out and counted in a "+N more" suffix.
+pub fn format_roles(locale: &str, roles: &[String]) -> String {
+    let all = roles.join(" ");
+    if all.chars().count() <= MAX_FIELD_LENGTH {
+        return all;
+    }
+    let more = |hidden: usize| {
+        tr_args(
+            locale,
+            "userinfo-roles-more",
+            &fluent_args!["count" => hidden],
+        )
+    };
+    let mut shown = String::new();
+    let mut count = 0;
+    for role in roles {
+        let line = if shown.is_empty() {
+            role.clone()
+        } else {
+            format!("{} {}", shown, role)
+        };
+        let suffix = more(roles.len() - count - 1);
+        if line.chars().count() + 1 + suffix.chars().count() > MAX_FIELD_LENGTH {
+            break;
+        }
+        shown = line;
+        count += 1;
+    }
+    if shown.is_empty() {
+        more(roles.len())
+    } else {
+        format!("{} {}", shown, more(roles.len() - count))
+    }
+}
+
+pub fn userinfo_response(locale: &str, info: &UserInfo<'_>, now: DateTime<Utc>) -> BotResponse {
+    let created = snowflake_timestamp(info.user_id);
+    let mut response = BotResponse::new(format!("<@{}>", info.user_id))
+        .title(tr_args(
+            locale,
+            "userinfo-title",
+            &fluent_args!["name" => info.user_name],
+        ))
+        .color(USER_COLOR)
+        .thumbnail(info.avatar_url)
+        .field(
+            tr(locale, "userinfo-created"),
+            format_date_and_age(locale, created, now),
+        );
+    if let Some(joined_at) = info.joined_at {
+        response = response.field(
+            tr(locale, "userinfo-joined"),
+            format_date_and_age(locale, joined_at, now),
+        );
+    }
+    if !info.roles.is_empty() {
+        response = response.field(
+            tr(locale, "userinfo-roles"),
+            format_roles(locale, &info.roles),
+        );
+    }
+    response.field(
+        tr(locale, "userinfo-commands-run"),
+        info.commands_run.to_string(),
+    )
+}
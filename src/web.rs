@@ -1,30 +1,84 @@
-use crate::FeedItem;
-
+use crate::analytics::{
+    Bucket, STATS_CACHE_SECONDS, TimeWindow, TtlCache, command_counts_from, error_rates_from,
+    unique_users_from,
+};
+use crate::events::{FeedEvent, FeedFilter};
+use crate::export::{
+    ExportFormat, HistoryFilter, export_file_name, export_history_from, parse_time_bound,
+};
+use crate::history_writer::HistoryWriter;
+use crate::store::{HistoryStore, StoreResult};
 use crate::websocket::handle_socket_primary;
 use crate::websocket::init_command_broadcast;
+use crate::websocket::resumed_feed;
 use axum::{
-    Router,
-    body::Body,
-    extract::ws::WebSocketUpgrade,
-    http::{Request, StatusCode, header},
+    Json, Router,
+    body::{Body, Bytes},
+    extract::{Query, State, ws::WebSocketUpgrade},
+    http::{HeaderMap, Request, StatusCode, header},
     middleware::{self, Next},
-    response::Response,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::get,
 };
 use colored::Colorize;
-use tokio::sync::broadcast;
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
 use tower_http::services::ServeDir;
 
-/// Global broadcast channel for command usage events
-pub static COMMAND_TX: once_cell::sync::OnceCell<broadcast::Sender<FeedItem>> =
+/// Global broadcast channel for feed events
+pub static COMMAND_TX: once_cell::sync::OnceCell<broadcast::Sender<FeedEvent>> =
     once_cell::sync::OnceCell::new();
 
+/// Bearer token required by the history export and metrics routes; they answer 404
+/// while it is unset. The feed and stats routes are public like the feed page.
+static API_TOKEN: once_cell::sync::OnceCell<String> = once_cell::sync::OnceCell::new();
+
+/// ### Shared state of the web server's handlers
+#[derive(Clone)]
+pub struct AppState {
+    /// Where the command history is read from
+    pub history: Arc<dyn HistoryStore>,
+    /// Queue new history rows go through, for its metrics
+    pub history_writer: HistoryWriter,
+    /// The bot's SQLite database, for privacy opt-outs, feed visibility and the stats
+    pub db_path: String,
+}
+
+impl AppState {
+    pub fn new(
+        history: Arc<dyn HistoryStore>,
+        history_writer: HistoryWriter,
+        db_path: &str,
+    ) -> Self {
+        AppState {
+            history,
+            history_writer,
+            db_path: db_path.to_string(),
+        }
+    }
+}
+
+/// Stats responses by endpoint and query, so dashboards polling the charts don't rerun
+/// the aggregates on every request
+static STATS_CACHE: once_cell::sync::Lazy<TtlCache<serde_json::Value>> =
+    once_cell::sync::Lazy::new(|| {
+        TtlCache::new(std::time::Duration::from_secs(STATS_CACHE_SECONDS))
+    });
+
 /// Sets up and runs the web server on the specified port
 /// # Arguments
 /// * `port` - The port number to bind the web server to
+/// * `api_token` - Token clients must send as `Authorization: Bearer <token>` to use the
+///   `/api` routes. `None` disables them.
+/// * `state` - History store and database the handlers read from
 /// # Example
-/// * `setup_web_server("8080").await;`
-pub async fn setup_web_server(port: &str) {
+/// * `setup_web_server("8080", None, AppState::new(history, writer, DEFAULT_DB_PATH)).await;`
+pub async fn setup_web_server(port: &str, api_token: Option<String>, state: AppState) {
     println!(
         "{}",
         format!("Starting web server on port {}...", port)
@@ -33,18 +87,31 @@ pub async fn setup_web_server(port: &str) {
     );
 
     // Create broadcast channel for command events
-    let (tx, _rx) = broadcast::channel::<FeedItem>(100);
+    let (tx, _rx) = broadcast::channel::<FeedEvent>(100);
     COMMAND_TX.set(tx).ok();
 
     let service = ServeDir::new("./frontend/build");
 
     // Initialize the broadcast channel
     init_command_broadcast();
+    if let Some(token) = api_token {
+        API_TOKEN.set(token).ok();
+    }
 
     let app = Router::new()
         .route("/ws/feed", get(websocket_handler))
+        .route("/api/v1/feed/stream", get(feed_stream_handler))
+        .route("/api/history/export", get(history_export_handler))
+        .route("/api/v1/stats/commands", get(command_stats_handler))
+        .route("/api/v1/stats/users", get(user_stats_handler))
+        .route("/api/v1/stats/errors", get(error_stats_handler))
+        .route(
+            "/api/v1/metrics/history-writer",
+            get(history_writer_metrics_handler),
+        )
         .fallback_service(service)
-        .layer(middleware::from_fn(log_requests));
+        .layer(middleware::from_fn(log_requests))
+        .with_state(state);
 
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port))
         .await
@@ -59,8 +126,284 @@ pub async fn setup_web_server(port: &str) {
 }
 
 /// WebSocket handler for the feed endpoint
-async fn websocket_handler(ws: WebSocketUpgrade) -> Result<Response, StatusCode> {
-    Ok(ws.on_upgrade(handle_socket_primary))
+async fn websocket_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FeedQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let filter = match FeedFilter::parse(query.types.as_deref()) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    ws.on_upgrade(move |socket| handle_socket_primary(socket, state, filter))
+}
+
+/// Query parameters of `/ws/feed` and `/api/v1/feed/stream`
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedQuery {
+    /// Comma-separated event types to receive, every type when unset
+    pub types: Option<String>,
+}
+
+/// Reads the `Last-Event-ID` header an SSE client sends when it reconnects. The IDs
+/// are the events' RFC 3339 timestamps.
+pub fn last_event_id(headers: &HeaderMap) -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+    let Some(value) = headers.get("last-event-id") else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|id| chrono::DateTime::parse_from_rfc3339(id.trim()).ok())
+        .map(|time| Some(time.with_timezone(&chrono::Utc)))
+        .ok_or_else(|| format!("Invalid Last-Event-ID {:?}", value))
+}
+
+/// `GET /api/v1/feed/stream`: the live feed as Server-Sent Events, for clients that
+/// can't use the WebSocket. Reconnecting clients get what they missed since their
+/// `Last-Event-ID` first.
+async fn feed_stream_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FeedQuery>,
+) -> Response {
+    let parsed = FeedFilter::parse(query.types.as_deref())
+        .and_then(|filter| Ok((filter, last_event_id(&headers)?)));
+    let (filter, after) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let events = match resumed_feed(&state, filter, after).await {
+        Ok(events) => events,
+        Err(e) => {
+            println!("{}", format!("Feed stream failed: {}", e).red());
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+    };
+    let events = events.map(|event| Event::default().id(event.timestamp()).json_data(&event));
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Checks the `Authorization: Bearer` header against `API_TOKEN`
+fn authorize(headers: &HeaderMap) -> Result<(), StatusCode> {
+    let Some(token) = API_TOKEN.get() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match provided {
+        Some(provided) if provided == token => Ok(()),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// Query parameters of `/api/history/export`
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    /// `csv` (default) or `jsonl`
+    pub format: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub command: Option<String>,
+    pub include_denied: Option<bool>,
+}
+
+impl ExportQuery {
+    /// Validates the query into an export format and filter
+    pub fn parse(&self) -> Result<(ExportFormat, HistoryFilter), String> {
+        let format = match &self.format {
+            Some(format) => ExportFormat::parse(format)
+                .ok_or_else(|| format!("Unknown export format {:?}, use csv or jsonl", format))?,
+            None => ExportFormat::Csv,
+        };
+        let bound = |value: &Option<String>| value.as_deref().map(parse_time_bound).transpose();
+        let filter = HistoryFilter {
+            since: bound(&self.since)?,
+            until: bound(&self.until)?,
+            guild_id: self.guild_id.clone(),
+            user_id: self.user_id.clone(),
+            command: self.command.clone(),
+            include_denied: self.include_denied.unwrap_or(false),
+            include_errors: false,
+            exclude_guilds: Vec::new(),
+        };
+        Ok((format, filter))
+    }
+}
+
+/// Sends everything written to it as body chunks of a streamed response
+struct ChannelWriter(mpsc::Sender<Result<Bytes, std::io::Error>>);
+
+impl std::io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `GET /api/history/export`: streams the filtered command history as a CSV or JSON
+/// Lines download while it is read from the database
+async fn history_export_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    if let Err(status) = authorize(&headers) {
+        return status.into_response();
+    }
+    let (format, filter) = match query.parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let (tx, rx) = mpsc::channel(16);
+    tokio::task::spawn_blocking(move || {
+        let mut writer = std::io::BufWriter::with_capacity(64 * 1024, ChannelWriter(tx.clone()));
+        if let Err(e) = export_history_from(
+            &*state.history,
+            &state.db_path,
+            &filter,
+            format,
+            &mut writer,
+        ) {
+            // Aborts the download so a truncated file is not mistaken for a complete one
+            println!("{}", format!("History export failed: {}", e).red());
+            let _ = tx.blocking_send(Err(std::io::Error::other(e)));
+        }
+    });
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}\"",
+                export_file_name(format, chrono::Utc::now())
+            ),
+        )
+        .body(Body::from_stream(stream))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Query parameters of the `/api/v1/stats` routes
+#[derive(Debug, Default, Deserialize)]
+pub struct StatsQuery {
+    /// `minute`, `hour` (default) or `day`
+    pub bucket: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Only this command and its subcommands
+    pub command: Option<String>,
+}
+
+impl StatsQuery {
+    /// Validates the query into the window to aggregate and the command filter
+    pub fn parse(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(TimeWindow, Option<String>), String> {
+        let bucket = match &self.bucket {
+            Some(bucket) => Bucket::parse(bucket)
+                .ok_or_else(|| format!("Unknown bucket {:?}, use minute, hour or day", bucket))?,
+            None => Bucket::Hour,
+        };
+        let bound = |value: &Option<String>| value.as_deref().map(parse_time_bound).transpose();
+        let window = TimeWindow::new(bucket, bound(&self.since)?, bound(&self.until)?, now)?;
+        Ok((window, self.command.clone()))
+    }
+
+    /// Identifies the query's result in `STATS_CACHE`
+    fn cache_key(&self, endpoint: &str) -> String {
+        format!(
+            "{}?bucket={:?}&since={:?}&until={:?}&command={:?}",
+            endpoint, self.bucket, self.since, self.until, self.command
+        )
+    }
+}
+
+/// Answers a stats request from the cache, or runs `load` in a blocking task and caches
+/// its result
+async fn cached_stats<T: Serialize + Send + 'static>(
+    state: AppState,
+    endpoint: &str,
+    query: StatsQuery,
+    load: fn(&dyn HistoryStore, &str, &TimeWindow, Option<&str>) -> StoreResult<T>,
+) -> Response {
+    let key = query.cache_key(endpoint);
+    if let Some(stats) = STATS_CACHE.get(&key) {
+        return Json(stats).into_response();
+    }
+    let (window, command) = match query.parse(chrono::Utc::now()) {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        load(&*state.history, &state.db_path, &window, command.as_deref())
+            .and_then(|stats| serde_json::to_value(stats).map_err(|e| e.to_string()))
+    })
+    .await;
+    match result {
+        Ok(Ok(stats)) => {
+            STATS_CACHE.insert(key, stats.clone());
+            Json(stats).into_response()
+        }
+        Ok(Err(e)) => {
+            println!("{}", format!("Stats query failed: {}", e).red());
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(e) => {
+            println!("{}", format!("Stats query panicked: {}", e).red());
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// `GET /api/v1/stats/commands`: uses per command and bucket
+async fn command_stats_handler(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Response {
+    cached_stats(state, "commands", query, command_counts_from).await
+}
+
+/// `GET /api/v1/stats/users`: distinct users per bucket
+async fn user_stats_handler(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Response {
+    cached_stats(state, "users", query, unique_users_from).await
+}
+
+/// `GET /api/v1/stats/errors`: ok, failed and denied invocations and the error rate per bucket
+async fn error_stats_handler(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Response {
+    cached_stats(state, "errors", query, error_rates_from).await
+}
+
+/// `GET /api/v1/metrics/history-writer`: queue depth and throughput of the history writer
+async fn history_writer_metrics_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = authorize(&headers) {
+        return status.into_response();
+    }
+    Json(state.history_writer.metrics()).into_response()
 }
 
 /// Middleware to log incoming requests
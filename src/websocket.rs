@@ -1,15 +1,16 @@
-use crate::DEFAULT_DB_PATH;
-use crate::FeedItem;
-use crate::load_recent_commands;
+use crate::events::{FEED_REPLAY_LIMIT, FeedEvent, FeedFilter, feed_after, recent_feed};
+use crate::web::AppState;
 use axum::extract::ws::{Message, WebSocket};
+use chrono::{DateTime, Utc};
 use futures::sink::SinkExt;
 use futures::stream::SplitSink;
 use futures::stream::SplitStream;
-use futures::stream::StreamExt;
-use tokio::sync::broadcast;
+use futures::stream::{Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
 
-/// Global broadcast channel for command usage events
-pub static COMMAND_TX: once_cell::sync::OnceCell<broadcast::Sender<FeedItem>> =
+/// Global broadcast channel for feed events
+pub static COMMAND_TX: once_cell::sync::OnceCell<broadcast::Sender<FeedEvent>> =
     once_cell::sync::OnceCell::new();
 
 /// Initialize the broadcast channel (call this once at application startup)
@@ -18,33 +19,41 @@ pub fn init_command_broadcast() {
     let _ = COMMAND_TX.set(tx);
 }
 
-pub async fn handle_socket_primary(socket: WebSocket) {
+pub async fn handle_socket_primary(socket: WebSocket, state: AppState, filter: FeedFilter) {
     let (sender, receiver) = socket.split();
+    // Replies meant for this client only, such as the recent events it asked for
+    let (replies, replies_rx) = mpsc::channel(FEED_REPLAY_LIMIT);
 
     // Spawn sender task
-    tokio::spawn(sender_task(sender));
+    tokio::spawn(sender_task(sender, filter, replies_rx));
 
     // Run receiver task
-    receiver_task(receiver).await;
+    receiver_task(receiver, state, replies).await;
 }
 
 // ============================================================================
 // SENDER: Broadcasts events to the client
 // ============================================================================
 
-async fn sender_task(mut sender: SplitSink<WebSocket, Message>) {
-    let tx = match COMMAND_TX.get() {
-        Some(tx) => tx.clone(),
-        None => return,
+async fn sender_task(
+    mut sender: SplitSink<WebSocket, Message>,
+    filter: FeedFilter,
+    replies: mpsc::Receiver<FeedEvent>,
+) {
+    let Some(live) = live_feed(filter.clone()) else {
+        return;
     };
+    let replies = futures::stream::unfold(replies, |mut rx| async move {
+        rx.recv().await.map(|event| (event, rx))
+    })
+    .filter(move |event| futures::future::ready(filter.matches(event)));
+    let mut events = futures::stream::select(Box::pin(live), Box::pin(replies));
 
-    let mut rx = tx.subscribe();
-
-    while let Ok(event) = rx.recv().await {
-        if let Ok(json) = serde_json::to_string(&event) {
-            if sender.send(Message::Text(json.into())).await.is_err() {
-                break;
-            }
+    while let Some(event) = events.next().await {
+        if let Ok(json) = serde_json::to_string(&event)
+            && sender.send(Message::Text(json.into())).await.is_err()
+        {
+            break;
         }
     }
 }
@@ -53,7 +62,11 @@ async fn sender_task(mut sender: SplitSink<WebSocket, Message>) {
 // RECEIVER: Handles incoming messages from the client
 // ============================================================================
 
-async fn receiver_task(mut receiver: SplitStream<WebSocket>) {
+async fn receiver_task(
+    mut receiver: SplitStream<WebSocket>,
+    state: AppState,
+    replies: mpsc::Sender<FeedEvent>,
+) {
     while let Some(result) = receiver.next().await {
         match result {
             Ok(Message::Text(text)) => {
@@ -66,14 +79,14 @@ async fn receiver_task(mut receiver: SplitStream<WebSocket>) {
                     }
                 };
                 // check the message action type is equal to request_items
-                if let Some(action) = message.get("action") {
-                    if action == "request_items" {
-                        // handle request for recent commands
-                        if let Some(count) = message.get("count") {
-                            if let Some(count) = count.as_i64() {
-                                handle_request_for_recent_commands(count).await;
-                            }
-                        }
+                if let Some(action) = message.get("action")
+                    && action == "request_items"
+                {
+                    // handle request for recent commands
+                    if let Some(count) = message.get("count")
+                        && let Some(count) = count.as_i64()
+                    {
+                        handle_request_for_recent_commands(&state, count, &replies).await;
                     }
                 }
             }
@@ -92,23 +105,98 @@ async fn receiver_task(mut receiver: SplitStream<WebSocket>) {
 // HELPERS
 // ============================================================================
 
-/// Broadcasts a command usage event to all connected WebSocket clients
-pub fn broadcast_command_usage(feed_item: FeedItem) {
-    println!("Lets try broadcasting command usage event...");
+/// Live feed events matching `filter`, shared by the WebSocket and SSE endpoints. A
+/// client that falls too far behind skips the events it missed. None until the
+/// broadcast channel is initialized.
+pub fn live_feed(filter: FeedFilter) -> Option<impl Stream<Item = FeedEvent> + Send + 'static> {
+    let rx = COMMAND_TX.get()?.subscribe();
+    let events = futures::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => return Some((event, rx)),
+                Err(RecvError::Lagged(skipped)) => {
+                    println!("Feed client lagged, skipped {} events", skipped)
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Some(events.filter(move |event| futures::future::ready(filter.matches(event))))
+}
+
+/// Live feed events for a client resuming after `after`: first the newest
+/// `FEED_REPLAY_LIMIT` events it missed, from the history, then the live ones. Without
+/// `after` this is just `live_feed`.
+pub async fn resumed_feed(
+    state: &AppState,
+    filter: FeedFilter,
+    after: Option<DateTime<Utc>>,
+) -> Result<impl Stream<Item = FeedEvent> + Send + 'static, String> {
+    // Subscribing first means nothing published while the history loads is lost
+    let live = live_feed(filter.clone()).ok_or("Broadcast channel not initialized")?;
+    let replay = match after {
+        Some(after) => {
+            // Commands broadcast while the client was away may still be queued
+            state.history_writer.flush().await;
+            let state = state.clone();
+            tokio::task::spawn_blocking(move || {
+                feed_after(
+                    &*state.history,
+                    &state.db_path,
+                    Some(after),
+                    FEED_REPLAY_LIMIT,
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())??
+        }
+        None => Vec::new(),
+    };
+    let replay: Vec<FeedEvent> = replay
+        .into_iter()
+        .filter(|event| filter.matches(event))
+        .collect();
+    // Live events the replay already covered are dropped
+    let replayed_until = replay.last().and_then(FeedEvent::time).or(after);
+    let live = live.filter(move |event| {
+        let newer = match (replayed_until, event.time()) {
+            (Some(until), Some(time)) => time > until,
+            _ => true,
+        };
+        futures::future::ready(newer)
+    });
+    Ok(futures::stream::iter(replay).chain(live))
+}
+
+/// Broadcasts a feed event to all connected WebSocket clients
+pub fn broadcast_event(event: FeedEvent) {
     if let Some(tx) = COMMAND_TX.get() {
-        println!("Broadcasting command usage event");
-        println!("feed_item: {:?}", feed_item);
-        let _ = tx.send(feed_item);
+        println!("Broadcasting {} event", event.kind());
+        let _ = tx.send(event);
     } else {
         println!("Broadcast channel not initialized");
     }
 }
 
-/// when the frontend requests for recent commands use load_recent_commands to load the recent commands from the database and send them to the client with a websocket message
-pub async fn handle_request_for_recent_commands(count: i64) {
-    // Load recent commands from the database
-    let recent_commands = match load_recent_commands(DEFAULT_DB_PATH, count) {
-        Ok(commands) => commands,
+/// when the frontend requests for recent commands use recent_feed to load the recent commands and other events and send them to the requesting client through `replies`
+pub async fn handle_request_for_recent_commands(
+    state: &AppState,
+    count: i64,
+    replies: &mpsc::Sender<FeedEvent>,
+) {
+    // Load recent events from the history store and the events table, including
+    // commands the history writer still has queued
+    state.history_writer.flush().await;
+    let state = state.clone();
+    let loaded =
+        tokio::task::spawn_blocking(move || recent_feed(&*state.history, &state.db_path, count))
+            .await;
+    let recent_commands = match loaded {
+        Ok(Ok(commands)) => commands,
+        Ok(Err(e)) => {
+            eprintln!("Failed to load recent commands: {}", e);
+            return;
+        }
         Err(e) => {
             eprintln!("Failed to load recent commands: {}", e);
             return;
@@ -119,11 +207,10 @@ pub async fn handle_request_for_recent_commands(count: i64) {
         recent_commands.len()
     );
 
-    // Send the recent commands to the client via broadcast
-    // Small delay ensures subscribers are ready
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    // Send the recent commands to the client with a websocket message
-    for feed_item in recent_commands {
-        broadcast_command_usage(feed_item);
+    // Only this client asked, so the events skip the broadcast channel
+    for event in recent_commands {
+        if replies.send(event).await.is_err() {
+            break;
+        }
     }
 }
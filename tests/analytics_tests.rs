@@ -0,0 +1,250 @@
+use chrono::{DateTime, Utc};
+use discordbot::analytics::{
+    Bucket, MAX_BUCKETS, TimeWindow, TtlCache, command_counts, command_counts_from, error_rates,
+    error_rates_from, unique_users, unique_users_from,
+};
+use discordbot::config::BotConfig;
+use discordbot::export::HistoryFilter;
+use discordbot::history_writer::{HistoryWriter, WriterConfig};
+use discordbot::privacy::set_opted_out;
+use discordbot::settings::{SettingKey, SettingsCache, store_setting};
+use discordbot::store::{
+    HistoryQuery, HistoryStore, MEMORY_HISTORY_CAPACITY, MemoryHistoryStore, SqliteHistoryStore,
+};
+use discordbot::web::StatsQuery;
+use discordbot::{BotState, CommandStatus, HistoryEntry, db_setup, history_logging_enabled};
+use rusqlite::Connection;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+fn time(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .expect("timestamp")
+        .with_timezone(&Utc)
+}
+
+/// Inserts a history row at `timestamp` (RFC 3339)
+fn insert_row(path: &str, timestamp: &str, user: &str, guild: &str, command: &str, status: &str) {
+    let conn = Connection::open(path).expect("open conn");
+    conn.execute(
+        "INSERT INTO command_history (timestamp, user_id, username, command, output, guild_id, status) VALUES (?1, ?2, 'name', ?3, '', ?4, ?5)",
+        rusqlite::params![time(timestamp).to_rfc3339(), user, command, guild, status],
+    )
+    .expect("insert");
+}
+
+async fn seeded_db() -> NamedTempFile {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_row(path, "2025-03-10T09:15:00Z", "1", "7", "avatar", "ok");
+    insert_row(path, "2025-03-10T09:45:00Z", "2", "7", "avatar", "ok");
+    insert_row(
+        path,
+        "2025-03-10T09:50:00Z",
+        "1",
+        "7",
+        "codename generate",
+        "ok",
+    );
+    insert_row(path, "2025-03-10T10:05:00Z", "1", "7", "avatar", "error");
+    insert_row(path, "2025-03-10T10:10:00Z", "2", "7", "perms", "denied");
+    insert_row(path, "2025-03-10T10:20:00Z", "3", "7", "avatar", "ok");
+    // Outside the window, and in a guild hidden from the feed
+    insert_row(path, "2025-03-10T07:00:00Z", "1", "7", "avatar", "ok");
+    insert_row(path, "2025-03-10T09:30:00Z", "4", "8", "avatar", "ok");
+    store_setting(path, "8", SettingKey::FeedVisible, "false", "9").expect("store");
+    tmp
+}
+
+/// The seeded rows copied into a memory store, as `HISTORY_BACKEND=memory` keeps them
+fn memory_copy(path: &str) -> MemoryHistoryStore {
+    let memory = MemoryHistoryStore::new(MEMORY_HISTORY_CAPACITY);
+    let query = HistoryQuery {
+        filter: HistoryFilter {
+            include_denied: true,
+            ..HistoryFilter::default()
+        },
+        ..HistoryQuery::default()
+    };
+    SqliteHistoryStore::new(path)
+        .query(&query, &mut |row| {
+            let status = match row.status.as_str() {
+                "error" => CommandStatus::Error,
+                "denied" => CommandStatus::Denied,
+                _ => CommandStatus::Ok,
+            };
+            let entry = HistoryEntry {
+                guild_id: row.guild_id,
+                status,
+                ..HistoryEntry::new(&row.user_id, &row.username, &row.command, &row.output)
+            };
+            memory.insert(&entry, time(&row.timestamp))
+        })
+        .expect("copy");
+    memory
+}
+
+fn hours() -> TimeWindow {
+    TimeWindow::new(
+        Bucket::Hour,
+        Some(time("2025-03-10T08:30:00Z")),
+        Some(time("2025-03-10T11:00:00Z")),
+        time("2025-03-10T12:00:00Z"),
+    )
+    .expect("window")
+}
+
+#[test]
+fn windows_round_since_down_and_limit_buckets() {
+    let window = hours();
+    assert_eq!(window.since, time("2025-03-10T08:00:00Z"));
+    let now = time("2025-03-10T12:34:56Z");
+    let default = TimeWindow::new(Bucket::Minute, None, None, now).expect("window");
+    assert_eq!(default.since, time("2025-03-10T11:34:00Z"));
+    assert_eq!(default.until, now);
+    let later = now + chrono::Duration::days(2);
+    assert!(TimeWindow::new(Bucket::Day, Some(later), Some(now), now).is_err());
+    let too_long = now - chrono::Duration::minutes(MAX_BUCKETS + 1);
+    assert!(TimeWindow::new(Bucket::Minute, Some(too_long), None, now).is_err());
+}
+
+#[tokio::test]
+async fn command_counts_fill_every_bucket() {
+    let tmp = seeded_db().await;
+    let path = tmp.path().to_str().expect("path to str");
+
+    let counts = command_counts(path, &hours(), None).expect("counts");
+    assert_eq!(counts.bucket, "hour");
+    assert_eq!(counts.since, "2025-03-10T08:00:00Z");
+    let series: Vec<_> = counts
+        .commands
+        .iter()
+        .map(|series| {
+            let points: Vec<_> = series.points.iter().map(|point| point.count).collect();
+            (series.command.as_str(), series.total, points)
+        })
+        .collect();
+    assert_eq!(
+        series,
+        [
+            ("avatar", 3, vec![0, 2, 1]),
+            ("codename generate", 1, vec![0, 1, 0])
+        ]
+    );
+    assert_eq!(counts.commands[0].points[1].start, "2025-03-10T09:00:00Z");
+
+    let codename = command_counts(path, &hours(), Some("codename")).expect("counts");
+    assert_eq!(codename.commands.len(), 1);
+    assert_eq!(codename.commands[0].command, "codename generate");
+}
+
+#[tokio::test]
+async fn unique_users_count_distinct_users() {
+    let tmp = seeded_db().await;
+    let path = tmp.path().to_str().expect("path to str");
+
+    let users = unique_users(path, &hours(), None).expect("users");
+    let points: Vec<_> = users.points.iter().map(|point| point.count).collect();
+    assert_eq!(points, [0, 2, 1]);
+    assert_eq!(users.total, 3);
+}
+
+#[tokio::test]
+async fn error_rates_leave_denied_attempts_out() {
+    let tmp = seeded_db().await;
+    let path = tmp.path().to_str().expect("path to str");
+
+    let rates = error_rates(path, &hours(), None).expect("rates");
+    assert_eq!((rates.ok, rates.errors, rates.denied), (4, 1, 1));
+    assert!((rates.error_rate - 0.2).abs() < 1e-9);
+    let tenth = &rates.points[2];
+    assert_eq!((tenth.ok, tenth.errors, tenth.denied), (1, 1, 1));
+    assert!((tenth.error_rate - 0.5).abs() < 1e-9);
+    assert_eq!(rates.points[0].error_rate, 0.0);
+}
+
+#[tokio::test]
+async fn stats_read_the_configured_history_store() {
+    let tmp = seeded_db().await;
+    let path = tmp.path().to_str().expect("path to str");
+    let memory = memory_copy(path);
+
+    assert_eq!(
+        command_counts_from(&memory, path, &hours(), None),
+        command_counts(path, &hours(), None)
+    );
+    assert_eq!(
+        unique_users_from(&memory, path, &hours(), None),
+        unique_users(path, &hours(), None)
+    );
+    assert_eq!(
+        error_rates_from(&memory, path, &hours(), None),
+        error_rates(path, &hours(), None)
+    );
+    // Rows only in the SQLite database don't count for another store
+    let empty = MemoryHistoryStore::new(MEMORY_HISTORY_CAPACITY);
+    let counts = command_counts_from(&empty, path, &hours(), None).expect("counts");
+    assert!(counts.commands.is_empty());
+}
+
+#[tokio::test]
+async fn failures_are_only_logged_where_history_logging_is_on() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    store_setting(path, "7", SettingKey::HistoryLogging, "false", "admin").expect("store");
+    set_opted_out(path, "43", true).expect("opt out");
+    let history: Arc<dyn HistoryStore> = Arc::new(MemoryHistoryStore::new(MEMORY_HISTORY_CAPACITY));
+    let data = BotState {
+        db_path: path.to_string(),
+        codename_path: String::new(),
+        config: BotConfig::default(),
+        settings: SettingsCache::new(),
+        history: history.clone(),
+        history_writer: HistoryWriter::spawn(history, WriterConfig::default()),
+    };
+
+    // The error rates must not count failures of a guild that turned logging off
+    assert!(!history_logging_enabled(&data, Some("7"), "42").await);
+    assert!(history_logging_enabled(&data, Some("8"), "42").await);
+    assert!(history_logging_enabled(&data, None, "42").await);
+    assert!(!history_logging_enabled(&data, Some("8"), "43").await);
+}
+
+#[test]
+fn stats_query_is_validated() {
+    let now = time("2025-03-10T12:00:00Z");
+    let (window, command) = StatsQuery::default().parse(now).expect("defaults");
+    assert_eq!(window.bucket, Bucket::Hour);
+    assert_eq!(window.since, time("2025-03-09T12:00:00Z"));
+    assert_eq!(command, None);
+
+    let query = StatsQuery {
+        bucket: Some("day".to_string()),
+        since: Some("2025-03-01".to_string()),
+        command: Some("avatar".to_string()),
+        ..StatsQuery::default()
+    };
+    let (window, command) = query.parse(now).expect("valid query");
+    assert_eq!(window.bucket, Bucket::Day);
+    assert_eq!(window.since, time("2025-03-01T00:00:00Z"));
+    assert_eq!(command.as_deref(), Some("avatar"));
+
+    let bad_bucket = StatsQuery {
+        bucket: Some("week".to_string()),
+        ..StatsQuery::default()
+    };
+    assert!(bad_bucket.parse(now).is_err());
+}
+
+#[test]
+fn cache_entries_expire() {
+    let cache = TtlCache::new(Duration::from_millis(50));
+    cache.insert("a".to_string(), 1);
+    assert_eq!(cache.get("a"), Some(1));
+    assert_eq!(cache.get("b"), None);
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(cache.get("a"), None);
+}
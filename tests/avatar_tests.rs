@@ -0,0 +1,41 @@
+use discordbot::avatar::{
+    ImageFormat, guild_member_avatar_url, image_extension, normalize_image_size, user_avatar_url,
+    user_banner_url,
+};
+
+#[test]
+fn image_size_is_clamped_and_rounded_to_power_of_two() {
+    assert_eq!(normalize_image_size(0), 16);
+    assert_eq!(normalize_image_size(16), 16);
+    assert_eq!(normalize_image_size(100), 64);
+    assert_eq!(normalize_image_size(1024), 1024);
+    assert_eq!(normalize_image_size(4000), 2048);
+    assert_eq!(normalize_image_size(4096), 4096);
+    assert_eq!(normalize_image_size(u16::MAX), 4096);
+}
+
+#[test]
+fn gif_is_only_used_for_animated_hashes() {
+    assert_eq!(image_extension("a_abc", None), "gif");
+    assert_eq!(image_extension("abc", None), "png");
+    assert_eq!(image_extension("abc", Some(ImageFormat::Gif)), "png");
+    assert_eq!(image_extension("a_abc", Some(ImageFormat::Gif)), "gif");
+    assert_eq!(image_extension("a_abc", Some(ImageFormat::Webp)), "webp");
+    assert_eq!(image_extension("abc", Some(ImageFormat::Jpg)), "jpg");
+}
+
+#[test]
+fn cdn_urls_have_expected_shape() {
+    assert_eq!(
+        user_avatar_url(42, "abc", Some(ImageFormat::Webp), 512),
+        "https://cdn.discordapp.com/avatars/42/abc.webp?size=512"
+    );
+    assert_eq!(
+        guild_member_avatar_url(7, 42, "a_def", None, 300),
+        "https://cdn.discordapp.com/guilds/7/users/42/avatars/a_def.gif?size=256"
+    );
+    assert_eq!(
+        user_banner_url(42, "ghi", Some(ImageFormat::Png), 4096),
+        "https://cdn.discordapp.com/banners/42/ghi.png?size=4096"
+    );
+}
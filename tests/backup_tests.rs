@@ -0,0 +1,136 @@
+use discordbot::backup::{
+    BackupConfig, backup_and_rotate, backup_database, list_snapshots, restore_snapshot,
+    rotate_snapshots, snapshot_file_name, validate_snapshot,
+};
+use discordbot::config::BotConfig;
+use discordbot::{SCHEMA_VERSION, count_commands_for_user, db_setup, insert_command_history_sync};
+use rusqlite::Connection;
+use tempfile::TempDir;
+
+fn at(seconds: i64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).expect("timestamp")
+}
+
+#[test]
+fn snapshot_names_sort_by_time() {
+    assert_eq!(snapshot_file_name(at(0)), "history-20231114T221320Z.db");
+    assert!(snapshot_file_name(at(59)) < snapshot_file_name(at(61)));
+}
+
+#[tokio::test]
+async fn backups_are_rotated_to_the_newest_snapshots() {
+    let tmp = TempDir::new().expect("create temp dir");
+    let db = tmp.path().join("history.db");
+    let db = db.to_str().expect("path to str");
+    let dir = tmp.path().join("backups");
+    let dir = dir.to_str().expect("path to str");
+    let _ = db_setup(db).await;
+    insert_command_history_sync(db, "42", "alice", "avatar", "ok").expect("insert");
+
+    let config = BackupConfig {
+        dir: dir.to_string(),
+        interval_hours: 1,
+        keep: 2,
+    };
+    for seconds in [0, 1, 2] {
+        backup_and_rotate(db, &config, at(seconds)).expect("backup");
+    }
+    let snapshots = list_snapshots(dir).expect("list");
+    let names: Vec<_> = snapshots
+        .iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        vec![snapshot_file_name(at(1)), snapshot_file_name(at(2))]
+    );
+    assert_eq!(rotate_snapshots(dir, 2).expect("rotate"), 0);
+
+    let snapshot = snapshots[1].to_str().expect("path to str");
+    assert_eq!(validate_snapshot(snapshot), Ok(SCHEMA_VERSION));
+    assert_eq!(count_commands_for_user(snapshot, "42").expect("count"), 1);
+}
+
+#[tokio::test]
+async fn restore_swaps_in_a_valid_snapshot() {
+    let tmp = TempDir::new().expect("create temp dir");
+    let db = tmp.path().join("history.db");
+    let db = db.to_str().expect("path to str");
+    let dir = tmp.path().join("backups");
+    let dir = dir.to_str().expect("path to str");
+    let _ = db_setup(db).await;
+    insert_command_history_sync(db, "42", "alice", "avatar", "ok").expect("insert");
+    let snapshot = backup_database(db, dir, at(0)).expect("backup");
+    insert_command_history_sync(db, "42", "alice", "avatar", "later").expect("insert");
+
+    let previous = restore_snapshot(snapshot.to_str().unwrap(), db, dir, at(10))
+        .expect("restore")
+        .expect("previous database saved");
+    assert_eq!(count_commands_for_user(db, "42").expect("count"), 1);
+    assert_eq!(
+        count_commands_for_user(previous.to_str().unwrap(), "42").expect("count"),
+        2
+    );
+}
+
+#[tokio::test]
+async fn invalid_snapshots_are_rejected() {
+    let tmp = TempDir::new().expect("create temp dir");
+    let db = tmp.path().join("history.db");
+    let db = db.to_str().expect("path to str");
+    let _ = db_setup(db).await;
+
+    let missing = tmp.path().join("missing.db");
+    assert!(validate_snapshot(missing.to_str().unwrap()).is_err());
+
+    let garbage = tmp.path().join("garbage.db");
+    std::fs::write(&garbage, "not a database").expect("write");
+    assert!(validate_snapshot(garbage.to_str().unwrap()).is_err());
+
+    let foreign = tmp.path().join("foreign.db");
+    Connection::open(&foreign)
+        .expect("open")
+        .execute_batch("CREATE TABLE other (id INTEGER);")
+        .expect("create");
+    assert!(validate_snapshot(foreign.to_str().unwrap()).is_err());
+
+    let newer = tmp.path().join("newer.db");
+    std::fs::copy(db, &newer).expect("copy");
+    Connection::open(&newer)
+        .expect("open")
+        .execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION + 1))
+        .expect("bump version");
+    let error = validate_snapshot(newer.to_str().unwrap()).unwrap_err();
+    assert!(error.contains("newer"), "{}", error);
+
+    // A rejected snapshot leaves the database alone
+    let dir = tmp.path().join("backups");
+    assert!(restore_snapshot(newer.to_str().unwrap(), db, dir.to_str().unwrap(), at(0)).is_err());
+    assert!(!dir.exists());
+}
+
+#[test]
+fn backup_settings_are_read_from_the_environment() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert_eq!(config.backup, BackupConfig::default());
+
+    let config = BotConfig::from_lookup(|key| match key {
+        "BACKUP_DIR" => Some("/data/backups".to_string()),
+        "BACKUP_INTERVAL_HOURS" => Some("6".to_string()),
+        "BACKUP_KEEP" => Some("30".to_string()),
+        _ => None,
+    })
+    .expect("valid config");
+    assert_eq!(
+        config.backup,
+        BackupConfig {
+            dir: "/data/backups".to_string(),
+            interval_hours: 6,
+            keep: 30
+        }
+    );
+    // Keeping no snapshot would delete each one right after taking it
+    let error = BotConfig::from_lookup(|key| (key == "BACKUP_KEEP").then(|| "0".to_string()))
+        .expect_err("zero snapshots");
+    assert!(error.contains("BACKUP_KEEP"), "{}", error);
+}
@@ -0,0 +1,130 @@
+use discordbot::{CodenameBlocklist, CodenameData, generate_codename, load_codename_data};
+
+fn words_of(part: &str) -> Vec<String> {
+    part.split(|c: char| c.is_whitespace() || c == '-')
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Adjectives of the shipped dataset containing a blocked word; no animal contains one
+const BLOCKED_ADJECTIVES: [&str; 6] = [
+    "aboriginal",
+    "lewd",
+    "obese",
+    "obscene",
+    "psychotic",
+    "racial",
+];
+
+/// Pairs the shipped blocklist rules out on their own, sorted
+const BLOCKED_PAIRS: [(&str, &str); 10] = [
+    ("bloody", "jesus"),
+    ("dead", "jesus"),
+    ("demonic", "jesus"),
+    ("devilish", "jesus"),
+    ("female", "dog"),
+    ("hellish", "jesus"),
+    ("homeless", "human"),
+    ("illegal", "human"),
+    ("jobless", "human"),
+    ("womanly", "human"),
+];
+
+#[test]
+fn blocklist_sweep_over_full_dataset() {
+    let data = load_codename_data("./assets/CodenameData.json").expect("load codename data");
+    let mut blocked = Vec::new();
+    for adjective in &data.adjectives {
+        for animal in &data.animals {
+            if !data.blocklist.allows(adjective, animal) {
+                blocked.push((adjective.as_str(), animal.as_str()));
+            }
+        }
+    }
+
+    // Blocked adjectives rule out every animal, the rest are the listed pairs
+    let mut adjectives: Vec<&str> = data
+        .adjectives
+        .iter()
+        .map(String::as_str)
+        .filter(|adjective| {
+            blocked.iter().filter(|(adj, _)| adj == adjective).count() == data.animals.len()
+        })
+        .collect();
+    adjectives.sort();
+    assert_eq!(adjectives, BLOCKED_ADJECTIVES);
+    let mut pairs: Vec<(&str, &str)> = blocked
+        .iter()
+        .copied()
+        .filter(|(adjective, _)| !BLOCKED_ADJECTIVES.contains(adjective))
+        .collect();
+    pairs.sort();
+    assert_eq!(pairs, BLOCKED_PAIRS);
+    assert_eq!(blocked.len(), 6 * data.animals.len() + 10);
+
+    // Re-rolls are bounded, so the blocklist must leave the vast majority of pairs usable
+    let total = data.adjectives.len() * data.animals.len();
+    assert!(
+        blocked.len() * 100 < total,
+        "blocklist removes too many combinations: {} of {}",
+        blocked.len(),
+        total
+    );
+}
+
+#[test]
+fn blocklist_entries_refer_to_dataset_words() {
+    let data = load_codename_data("./assets/CodenameData.json").expect("load codename data");
+    let all_words: Vec<String> = data
+        .adjectives
+        .iter()
+        .chain(data.animals.iter())
+        .flat_map(|entry| words_of(entry))
+        .collect();
+    for word in &data.blocklist.words {
+        assert!(
+            all_words.contains(&word.to_lowercase()),
+            "blocked word {} does not occur in the dataset",
+            word
+        );
+    }
+    for (adjective, animal) in &data.blocklist.combinations {
+        assert!(
+            data.adjectives.contains(adjective),
+            "unknown adjective {}",
+            adjective
+        );
+        assert!(data.animals.contains(animal), "unknown animal {}", animal);
+    }
+}
+
+#[test]
+fn generate_codename_rerolls_blocked_pairs() {
+    let data = CodenameData {
+        adjectives: vec!["quick".to_string(), "lewd".to_string()],
+        animals: vec!["fox".to_string(), "owl".to_string()],
+        blocklist: CodenameBlocklist {
+            words: vec!["LEWD".to_string()],
+            combinations: vec![("quick".to_string(), "owl".to_string())],
+        },
+    };
+    for _ in 0..50 {
+        assert_eq!(
+            generate_codename(&data).expect("should generate"),
+            "Quick Fox"
+        );
+    }
+}
+
+#[test]
+fn generate_codename_errors_when_everything_is_blocked() {
+    let data = CodenameData {
+        adjectives: vec!["quick".to_string()],
+        animals: vec!["black-and-white-fox".to_string()],
+        blocklist: CodenameBlocklist {
+            words: vec!["white".to_string()],
+            combinations: vec![],
+        },
+    };
+    assert!(generate_codename(&data).is_err());
+}
@@ -0,0 +1,104 @@
+use discordbot::codename_check::{
+    IssueKind, check_codename_data, format_dataset_report, normalize_codename_data,
+    read_codename_data_unchecked, write_codename_data,
+};
+use discordbot::{CodenameBlocklist, CodenameData, load_codename_data};
+use tempfile::NamedTempFile;
+
+fn messy_data() -> CodenameData {
+    CodenameData {
+        adjectives: vec![
+            "quick".to_string(),
+            " Brown".to_string(),
+            "".to_string(),
+            "QUICK".to_string(),
+            "lazy  old".to_string(),
+        ],
+        animals: vec!["fox".to_string(), "vicuña".to_string()],
+        blocklist: CodenameBlocklist {
+            words: vec!["Lazy".to_string()],
+            combinations: vec![],
+        },
+    }
+}
+
+#[test]
+fn check_reports_statistics() {
+    let report = check_codename_data(&messy_data());
+    assert_eq!(report.adjective_count, 5);
+    assert_eq!(report.animal_count, 2);
+    assert_eq!(report.combination_count, 10);
+    // "lazy  old" is blocked for both animals
+    assert_eq!(report.allowed_combination_count, 8);
+    assert!((report.entropy_bits - 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn check_reports_suspicious_entries() {
+    let report = check_codename_data(&messy_data());
+    let kinds: Vec<(&str, usize, IssueKind)> = report
+        .issues
+        .iter()
+        .map(|issue| (issue.list, issue.index, issue.kind.clone()))
+        .collect();
+    assert!(kinds.contains(&("adjectives", 1, IssueKind::Whitespace)));
+    assert!(kinds.contains(&("adjectives", 1, IssueKind::Casing)));
+    assert!(kinds.contains(&("adjectives", 2, IssueKind::Empty)));
+    assert!(kinds.contains(&("adjectives", 3, IssueKind::Duplicate { first_index: 0 })));
+    assert!(kinds.contains(&("adjectives", 4, IssueKind::Whitespace)));
+    assert!(kinds.contains(&("animals", 1, IssueKind::NonAscii)));
+    assert!(report.has_errors());
+    assert!(format_dataset_report(&report).contains("duplicate of entry 0"));
+}
+
+#[test]
+fn non_ascii_alone_is_only_a_warning() {
+    let data = CodenameData {
+        adjectives: vec!["quick".to_string()],
+        animals: vec!["vicuña".to_string()],
+        ..Default::default()
+    };
+    let report = check_codename_data(&data);
+    assert_eq!(report.issues.len(), 1);
+    assert!(!report.has_errors());
+}
+
+#[test]
+fn normalize_sorts_dedupes_and_cleans() {
+    let normalized = normalize_codename_data(&messy_data());
+    assert_eq!(
+        normalized.adjectives,
+        vec![
+            "brown".to_string(),
+            "lazy old".to_string(),
+            "quick".to_string()
+        ]
+    );
+    assert_eq!(
+        normalized.animals,
+        vec!["fox".to_string(), "vicuña".to_string()]
+    );
+    assert_eq!(normalized.blocklist.words, vec!["lazy".to_string()]);
+    assert!(!check_codename_data(&normalized).has_errors());
+}
+
+#[test]
+fn normalized_file_round_trips() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let normalized = normalize_codename_data(&messy_data());
+    write_codename_data(path, &normalized).expect("write");
+
+    let reread = read_codename_data_unchecked(path).expect("read back");
+    assert_eq!(reread.adjectives, normalized.adjectives);
+    assert_eq!(reread.animals, normalized.animals);
+    assert_eq!(reread.blocklist.words, normalized.blocklist.words);
+    load_codename_data(path).expect("normalized file is valid");
+}
+
+#[test]
+fn shipped_dataset_has_no_errors() {
+    let data = read_codename_data_unchecked("./assets/CodenameData.json").expect("read dataset");
+    let report = check_codename_data(&data);
+    assert!(!report.has_errors(), "{}", format_dataset_report(&report));
+}
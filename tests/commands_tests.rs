@@ -1,3 +1,4 @@
+use discordbot::i18n::DEFAULT_LOCALE;
 use discordbot::{
     CodenameData, format_codename_response, format_register_response, generate_codename,
 };
@@ -35,13 +36,25 @@ fn generate_codename_returns_nonempty() {
 #[test]
 fn register_response_format() {
     assert_eq!(
-        format_register_response(),
+        format_register_response(DEFAULT_LOCALE),
         "Registered application commands"
     );
 }
 
 #[test]
 fn codename_response_format() {
-    let s = format_codename_response("quick fox");
+    let s = format_codename_response(DEFAULT_LOCALE, "quick fox");
     assert!(s.contains("quick fox"));
 }
+
+#[test]
+fn codename_batch_response_numbers_entries() {
+    let names = vec!["Quick Fox".to_string(), "Brown Owl".to_string()];
+    let s = discordbot::format_codename_batch_response(DEFAULT_LOCALE, &names);
+    assert!(s.contains("1. **Quick Fox**"));
+    assert!(s.contains("2. **Brown Owl**"));
+    assert!(
+        discordbot::format_favorite_codename_response(DEFAULT_LOCALE, "Quick Fox")
+            .contains("Quick Fox")
+    );
+}
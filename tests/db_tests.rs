@@ -1,5 +1,12 @@
-use discordbot::{db_setup, insert_command_history_sync, log_command_usage_with_author};
+use discordbot::history_writer::{HistoryWriter, WriterConfig};
+use discordbot::store::SqliteHistoryStore;
+use discordbot::{
+    CommandStatus, HistoryEntry, SCHEMA_VERSION, count_commands_for_user, db_setup,
+    insert_command_history_sync, insert_history_entry_sync, load_recent_commands,
+    log_command_usage_with_author, schema_version,
+};
 use rusqlite::Connection;
+use std::sync::Arc;
 use tempfile::NamedTempFile;
 
 #[tokio::test]
@@ -72,7 +79,12 @@ async fn log_command_usage_with_author_inserts_row() {
     let path = tmp.path().to_str().expect("path to str");
 
     let _ = db_setup(path).await;
-    log_command_usage_with_author(path, "7", "asyncuser", "acmd", "done").await;
+    let writer = HistoryWriter::spawn(
+        Arc::new(SqliteHistoryStore::new(path)),
+        WriterConfig::default(),
+    );
+    log_command_usage_with_author(&writer, "7", "asyncuser", "acmd", "done").await;
+    writer.flush().await;
 
     let conn = Connection::open(path).expect("open conn");
     let mut stmt = conn
@@ -92,3 +104,74 @@ async fn log_command_usage_with_author_inserts_row() {
     assert_eq!(command, "acmd");
     assert_eq!(output, "done");
 }
+
+#[tokio::test]
+async fn db_setup_migrates_unversioned_database() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    // A history table as created before schema versioning existed
+    let conn = Connection::open(path).expect("open conn");
+    conn.execute_batch(
+        "CREATE TABLE command_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            username TEXT NOT NULL,
+            command TEXT NOT NULL,
+            output TEXT NOT NULL
+        );
+        INSERT INTO command_history (timestamp, user_id, username, command, output)
+        VALUES ('2024-01-01T00:00:00+00:00', '1', 'old', 'codename', 'Quick Fox');",
+    )
+    .expect("create old schema");
+    drop(conn);
+
+    let _ = db_setup(path).await;
+    assert_eq!(schema_version(path).expect("version"), SCHEMA_VERSION);
+    // Running setup again is a no-op
+    let _ = db_setup(path).await;
+    assert_eq!(schema_version(path).expect("version"), SCHEMA_VERSION);
+
+    let conn = Connection::open(path).expect("open conn");
+    let (command, status): (String, String) = conn
+        .query_row(
+            "SELECT command, status FROM command_history WHERE user_id = '1'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .expect("query status");
+    assert_eq!(command, "codename generate");
+    assert_eq!(status, "ok");
+}
+
+#[tokio::test]
+async fn history_entry_records_guild_and_status() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    let entry = HistoryEntry {
+        guild_id: Some("99".to_string()),
+        status: CommandStatus::Denied,
+        ..HistoryEntry::new("5", "denied", "codename claim", "not allowed")
+    };
+    insert_history_entry_sync(path, &entry).expect("insert");
+    insert_command_history_sync(path, "5", "denied", "avatar", "ok").expect("insert");
+
+    let conn = Connection::open(path).expect("open conn");
+    let (guild_id, status): (Option<String>, String) = conn
+        .query_row(
+            "SELECT guild_id, status FROM command_history WHERE command = 'codename claim'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .expect("query");
+    assert_eq!(guild_id.as_deref(), Some("99"));
+    assert_eq!(status, "denied");
+
+    // Denied attempts are kept out of the feed and the per-user count
+    assert_eq!(count_commands_for_user(path, "5").expect("count"), 1);
+    let feed = load_recent_commands(path, 10).expect("feed");
+    assert_eq!(feed.len(), 1);
+    assert_eq!(feed[0].command_name, "avatar");
+}
@@ -0,0 +1,370 @@
+use discordbot::config::BotConfig;
+use discordbot::events::{
+    FeedEvent, FeedFilter, delete_user_events, feed_after, prune_events, recent_events,
+    recent_feed, record_event,
+};
+use discordbot::history_writer::{HistoryWriter, WriterConfig};
+use discordbot::privacy::{ANONYMOUS_NAME, set_opted_out};
+use discordbot::settings::{SettingKey, store_setting};
+use discordbot::store::{HistoryStore, SqliteHistoryStore};
+use discordbot::web::{AppState, last_event_id};
+use discordbot::websocket::{broadcast_event, init_command_broadcast, resumed_feed};
+use discordbot::{CommandStatus, FeedItem, HistoryEntry, db_setup};
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+fn at(seconds: i64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).expect("timestamp")
+}
+
+fn member_joined(seconds: i64, user_id: &str) -> FeedEvent {
+    FeedEvent::MemberJoined {
+        item_uuid: format!("member-{}", seconds),
+        timestamp: at(seconds).to_rfc3339(),
+        guild_id: "7".to_string(),
+        user_id: user_id.to_string(),
+        user_name: format!("user{}", user_id),
+    }
+}
+
+fn shard_connected(seconds: i64) -> FeedEvent {
+    FeedEvent::ShardConnected {
+        item_uuid: format!("shard-{}", seconds),
+        timestamp: at(seconds).to_rfc3339(),
+        shard_id: 0,
+    }
+}
+
+#[test]
+fn command_events_keep_the_feed_item_fields_next_to_the_type() {
+    let item = FeedItem {
+        item_uuid: "uuid".to_string(),
+        timestamp: at(0).to_rfc3339(),
+        author_id: "42".to_string(),
+        author_name: "alice".to_string(),
+        command_name: "avatar".to_string(),
+        command_output: "done".to_string(),
+        test_item: false,
+    };
+    let event = FeedEvent::CommandExecuted(item.clone());
+    let json = serde_json::to_value(&event).expect("serialize");
+    assert_eq!(json["type"], "command_executed");
+    assert_eq!(json["author_name"], "alice");
+    assert_eq!(json["command_output"], "done");
+    // Older clients read command events as plain feed items
+    let as_item: FeedItem = serde_json::from_value(json.clone()).expect("read as FeedItem");
+    assert_eq!(as_item, item);
+    assert_eq!(
+        serde_json::from_value::<FeedEvent>(json).expect("deserialize"),
+        event
+    );
+
+    let json = serde_json::to_value(shard_connected(0)).expect("serialize");
+    assert_eq!(json["type"], "shard_connected");
+    assert_eq!(json["shard_id"], 0);
+    assert_eq!(
+        FeedEvent::CommandFailed(item).kind(),
+        "command_failed",
+        "kind matches the serialized tag"
+    );
+}
+
+#[test]
+fn anonymizing_removes_the_user() {
+    let FeedEvent::MemberJoined {
+        user_id, user_name, ..
+    } = member_joined(0, "42").anonymized()
+    else {
+        panic!("variant changed");
+    };
+    assert_eq!(user_id, "");
+    assert_eq!(user_name, ANONYMOUS_NAME);
+    assert_eq!(shard_connected(0).anonymized(), shard_connected(0));
+}
+
+#[tokio::test]
+async fn events_are_stored_pruned_and_deleted() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+
+    record_event(path, Some("7"), &member_joined(20, "42")).expect("record");
+    record_event(path, None, &shard_connected(0)).expect("record");
+    record_event(path, Some("7"), &member_joined(10, "43")).expect("record");
+
+    assert_eq!(
+        recent_events(path, 10).expect("load"),
+        [
+            shard_connected(0),
+            member_joined(10, "43"),
+            member_joined(20, "42")
+        ]
+    );
+    assert_eq!(
+        recent_events(path, 1).expect("load"),
+        [member_joined(20, "42")]
+    );
+
+    assert_eq!(delete_user_events(path, "42").expect("delete"), 1);
+    assert_eq!(
+        prune_events(path, Some(1), &HashMap::new(), at(5 + 24 * 3600)).expect("prune"),
+        1
+    );
+    assert_eq!(
+        recent_events(path, 10).expect("load"),
+        [member_joined(10, "43")]
+    );
+}
+
+#[tokio::test]
+async fn events_follow_the_retention_overrides_of_their_guild() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+
+    record_event(path, Some("7"), &member_joined(0, "42")).expect("record");
+    record_event(path, Some("8"), &member_joined(1, "43")).expect("record");
+    record_event(path, None, &shard_connected(2)).expect("record");
+    let two_days_later = at(2 * 24 * 3600);
+
+    let overrides = HashMap::from([("7".to_string(), 30)]);
+    assert_eq!(
+        prune_events(path, Some(1), &overrides, two_days_later).expect("prune"),
+        2
+    );
+    assert_eq!(
+        recent_events(path, 10).expect("load"),
+        [member_joined(0, "42")]
+    );
+    // Overrides apply even without a global maximum age
+    let overrides = HashMap::from([("7".to_string(), 1)]);
+    assert_eq!(
+        prune_events(path, None, &overrides, two_days_later).expect("prune"),
+        1
+    );
+    assert!(recent_events(path, 10).expect("load").is_empty());
+}
+
+#[tokio::test]
+async fn recent_feed_merges_commands_and_events_by_time() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    let history = SqliteHistoryStore::new(path);
+
+    history
+        .insert(&HistoryEntry::new("1", "user1", "avatar", "first"), at(0))
+        .expect("insert");
+    history
+        .insert(&HistoryEntry::new("1", "user1", "avatar", "third"), at(20))
+        .expect("insert");
+    record_event(path, Some("7"), &member_joined(10, "42")).expect("record");
+    record_event(path, None, &shard_connected(30)).expect("record");
+    set_opted_out(path, "42", true).expect("opt out");
+
+    let feed = recent_feed(&history, path, 3).expect("load");
+    let kinds: Vec<&str> = feed.iter().map(FeedEvent::kind).collect();
+    assert_eq!(
+        kinds,
+        ["member_joined", "command_executed", "shard_connected"]
+    );
+    assert_eq!(feed[0], member_joined(10, "42").anonymized());
+    let FeedEvent::CommandExecuted(item) = &feed[1] else {
+        panic!("expected a command");
+    };
+    assert_eq!(item.command_output, "third");
+}
+
+#[test]
+fn member_events_are_read_from_the_environment() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert!(!config.feed_member_events);
+
+    for (value, expected) in [("true", true), ("YES", true), ("0", false), (" ", false)] {
+        let config =
+            BotConfig::from_lookup(|key| (key == "FEED_MEMBER_EVENTS").then(|| value.to_string()))
+                .expect("valid config");
+        assert_eq!(config.feed_member_events, expected, "{:?}", value);
+    }
+    let error =
+        BotConfig::from_lookup(|key| (key == "FEED_MEMBER_EVENTS").then(|| "maybe".to_string()))
+            .expect_err("invalid flag");
+    assert!(error.contains("FEED_MEMBER_EVENTS"), "{}", error);
+}
+
+#[test]
+fn filters_select_event_types() {
+    assert_eq!(FeedFilter::parse(None), Ok(FeedFilter::default()));
+    assert_eq!(FeedFilter::parse(Some(" ")), Ok(FeedFilter::default()));
+    assert!(FeedFilter::default().matches(&shard_connected(0)));
+
+    let filter = FeedFilter::parse(Some("member_joined, guild_left")).expect("valid types");
+    assert!(filter.matches(&member_joined(0, "42")));
+    assert!(!filter.matches(&shard_connected(0)));
+
+    let error = FeedFilter::parse(Some("member_joined,typing")).expect_err("unknown type");
+    assert!(error.contains("typing"), "{}", error);
+}
+
+#[test]
+fn last_event_id_is_an_rfc3339_timestamp() {
+    let mut headers = axum::http::HeaderMap::new();
+    assert_eq!(last_event_id(&headers), Ok(None));
+    headers.insert(
+        "last-event-id",
+        at(10).to_rfc3339().parse().expect("header value"),
+    );
+    assert_eq!(last_event_id(&headers), Ok(Some(at(10))));
+    headers.insert("last-event-id", "42".parse().expect("header value"));
+    assert!(last_event_id(&headers).is_err());
+}
+
+#[tokio::test]
+async fn feed_after_skips_what_the_client_already_has() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    let history = SqliteHistoryStore::new(path);
+
+    for (seconds, output) in [(0, "seen"), (10, "last seen"), (20, "missed")] {
+        history
+            .insert(
+                &HistoryEntry::new("1", "user1", "avatar", output),
+                at(seconds),
+            )
+            .expect("insert");
+    }
+    record_event(path, None, &shard_connected(5)).expect("record");
+    record_event(path, None, &shard_connected(30)).expect("record");
+
+    let missed = feed_after(&history, path, Some(at(10)), 10).expect("load");
+    let kinds: Vec<&str> = missed.iter().map(FeedEvent::kind).collect();
+    assert_eq!(kinds, ["command_executed", "shard_connected"]);
+    assert_eq!(missed[1], shard_connected(30));
+    // Replayed commands keep their uuids, so clients can drop the ones they have
+    assert_eq!(
+        feed_after(&history, path, Some(at(10)), 10).expect("load"),
+        missed
+    );
+    assert_eq!(feed_after(&history, path, None, 10).expect("load").len(), 5);
+}
+
+#[tokio::test]
+async fn recent_feed_leaves_out_guilds_hidden_from_the_feed() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    let history = SqliteHistoryStore::new(path);
+
+    for (seconds, guild_id, output) in [(0, "8", "shown"), (10, "7", "hidden"), (20, "7", "hidden")]
+    {
+        let entry = HistoryEntry {
+            guild_id: Some(guild_id.to_string()),
+            ..HistoryEntry::new("1", "user1", "avatar", output)
+        };
+        history.insert(&entry, at(seconds)).expect("insert");
+    }
+    record_event(path, Some("7"), &member_joined(30, "42")).expect("record");
+    store_setting(path, "7", SettingKey::FeedVisible, "false", "admin").expect("store");
+
+    let feed = recent_feed(&history, path, 1).expect("load");
+    let [FeedEvent::CommandExecuted(item)] = feed.as_slice() else {
+        panic!("expected one command, got {:?}", feed);
+    };
+    assert_eq!(item.command_output, "shown");
+}
+
+#[tokio::test]
+async fn failed_commands_are_replayed_as_failures_and_denied_ones_left_out() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    let history = SqliteHistoryStore::new(path);
+
+    for (seconds, output, status) in [
+        (0, "done", CommandStatus::Ok),
+        (10, "Missing permissions", CommandStatus::Error),
+        (20, "denied", CommandStatus::Denied),
+    ] {
+        let entry = HistoryEntry {
+            status,
+            ..HistoryEntry::new("1", "user1", "avatar", output)
+        };
+        history.insert(&entry, at(seconds)).expect("insert");
+    }
+
+    let feed = feed_after(&history, path, None, 10).expect("load");
+    let kinds: Vec<&str> = feed.iter().map(FeedEvent::kind).collect();
+    assert_eq!(kinds, ["command_executed", "command_failed"]);
+    let FeedEvent::CommandFailed(item) = &feed[1] else {
+        panic!("expected a failure");
+    };
+    assert_eq!(item.command_output, "Missing permissions");
+}
+
+#[tokio::test]
+async fn resumed_feed_replays_the_history_then_goes_live() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    let history = Arc::new(SqliteHistoryStore::new(path));
+    let state = AppState::new(
+        history.clone(),
+        HistoryWriter::spawn(history, WriterConfig::default()),
+        path,
+    );
+    record_event(path, None, &shard_connected(10)).expect("record");
+    record_event(path, None, &member_joined(20, "42")).expect("record");
+    init_command_broadcast();
+
+    let filter = FeedFilter::parse(Some("shard_connected")).expect("valid types");
+    let mut events = Box::pin(
+        resumed_feed(&state, filter, Some(at(0)))
+            .await
+            .expect("subscribe"),
+    );
+    assert_eq!(events.next().await, Some(shard_connected(10)));
+
+    // Already replayed, filtered out, then new
+    broadcast_event(shard_connected(10));
+    broadcast_event(member_joined(40, "43"));
+    broadcast_event(shard_connected(50));
+    let next = tokio::time::timeout(Duration::from_secs(5), events.next()).await;
+    assert_eq!(next.expect("live event"), Some(shard_connected(50)));
+}
+
+#[tokio::test]
+async fn resumed_feed_replays_commands_still_queued_on_the_writer() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    db_setup(path).await;
+    let history = Arc::new(SqliteHistoryStore::new(path));
+    let writer = HistoryWriter::spawn(
+        history.clone(),
+        WriterConfig {
+            flush_interval_ms: 3_600_000,
+            ..WriterConfig::default()
+        },
+    );
+    let state = AppState::new(history, writer.clone(), path);
+    writer
+        .log_at(HistoryEntry::new("1", "user1", "avatar", "missed"), at(20))
+        .await;
+    init_command_broadcast();
+
+    let filter = FeedFilter::parse(Some("command_executed")).expect("valid types");
+    let mut events = Box::pin(
+        resumed_feed(&state, filter, Some(at(10)))
+            .await
+            .expect("subscribe"),
+    );
+    let Some(FeedEvent::CommandExecuted(item)) = events.next().await else {
+        panic!("expected the queued command");
+    };
+    assert_eq!(item.command_output, "missed");
+    // The row keeps the timestamp it was logged with, the one the live event carried
+    assert_eq!(item.timestamp, at(20).to_rfc3339());
+}
@@ -0,0 +1,269 @@
+use discordbot::config::BotConfig;
+use discordbot::db_setup;
+use discordbot::export::{
+    CappedBuffer, ExportFormat, HistoryFilter, export_file_name, export_history, parse_time_bound,
+};
+use discordbot::privacy::set_opted_out;
+use discordbot::web::ExportQuery;
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
+
+fn at(seconds: i64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).expect("timestamp")
+}
+
+/// Inserts a history row `seconds` after the test epoch
+fn insert_row(
+    path: &str,
+    seconds: i64,
+    user: &str,
+    guild: Option<&str>,
+    command: &str,
+    output: &str,
+    status: &str,
+) {
+    let conn = Connection::open(path).expect("open conn");
+    conn.execute(
+        "INSERT INTO command_history (timestamp, user_id, username, command, output, guild_id, status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            at(seconds).to_rfc3339(),
+            user,
+            format!("user{}", user),
+            command,
+            output,
+            guild,
+            status
+        ],
+    )
+    .expect("insert");
+}
+
+fn export(path: &str, filter: &HistoryFilter, format: ExportFormat) -> (usize, String) {
+    let mut buf = Vec::new();
+    let count = export_history(path, filter, format, &mut buf).expect("export");
+    (count, String::from_utf8(buf).expect("utf8"))
+}
+
+fn outputs(path: &str, filter: &HistoryFilter) -> Vec<String> {
+    let (_, jsonl) = export(path, filter, ExportFormat::JsonLines);
+    jsonl
+        .lines()
+        .map(|line| {
+            let row: serde_json::Value = serde_json::from_str(line).expect("json line");
+            row["output"].as_str().expect("output").to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn csv_export_quotes_fields() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_row(path, 0, "42", Some("1"), "avatar", "plain", "ok");
+    insert_row(
+        path,
+        1,
+        "42",
+        None,
+        "codename generate",
+        "a, \"b\"\nc",
+        "ok",
+    );
+
+    let (count, csv) = export(path, &HistoryFilter::default(), ExportFormat::Csv);
+    assert_eq!(count, 2);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,timestamp,user_id,username,guild_id,command,output,status")
+    );
+    assert_eq!(
+        lines.next(),
+        Some(format!("1,{},42,user42,1,avatar,plain,ok", at(0).to_rfc3339()).as_str())
+    );
+    assert!(csv.ends_with(&format!(
+        "2,{},42,user42,,codename generate,\"a, \"\"b\"\"\nc\",ok\n",
+        at(1).to_rfc3339()
+    )));
+}
+
+#[tokio::test]
+async fn jsonl_export_writes_one_object_per_row() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_row(path, 0, "42", None, "avatar", "line\nbreak", "ok");
+
+    let (count, jsonl) = export(path, &HistoryFilter::default(), ExportFormat::JsonLines);
+    assert_eq!(count, 1);
+    assert_eq!(jsonl.lines().count(), 1);
+    let row: serde_json::Value = serde_json::from_str(jsonl.trim_end()).expect("json");
+    assert_eq!(row["user_id"], "42");
+    assert_eq!(row["guild_id"], serde_json::Value::Null);
+    assert_eq!(row["output"], "line\nbreak");
+    assert_eq!(row["status"], "ok");
+}
+
+#[tokio::test]
+async fn export_filters_rows() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_row(path, 0, "42", Some("1"), "avatar", "first", "ok");
+    insert_row(
+        path,
+        10,
+        "43",
+        Some("1"),
+        "codename generate",
+        "second",
+        "ok",
+    );
+    insert_row(path, 20, "42", Some("2"), "codename", "third", "ok");
+    insert_row(path, 30, "42", Some("1"), "codenames", "fourth", "ok");
+    insert_row(path, 40, "42", Some("1"), "avatar", "denied", "denied");
+
+    let everything = HistoryFilter::default();
+    assert_eq!(
+        outputs(path, &everything),
+        ["first", "second", "third", "fourth"]
+    );
+    let window = HistoryFilter {
+        since: Some(at(10)),
+        until: Some(at(30)),
+        ..HistoryFilter::default()
+    };
+    assert_eq!(outputs(path, &window), ["second", "third"]);
+    let user_in_guild = HistoryFilter {
+        user_id: Some("42".to_string()),
+        guild_id: Some("1".to_string()),
+        ..HistoryFilter::default()
+    };
+    assert_eq!(outputs(path, &user_in_guild), ["first", "fourth"]);
+    // A parent command matches its subcommands but not commands sharing its prefix
+    let command = HistoryFilter {
+        command: Some("codename".to_string()),
+        ..HistoryFilter::default()
+    };
+    assert_eq!(outputs(path, &command), ["second", "third"]);
+    let with_denied = HistoryFilter {
+        include_denied: true,
+        ..HistoryFilter::default()
+    };
+    assert_eq!(outputs(path, &with_denied).len(), 5);
+}
+
+#[tokio::test]
+async fn export_hides_opted_out_authors() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_row(path, 0, "42", None, "avatar", "<@42>", "ok");
+    set_opted_out(path, "42", true).expect("opt out");
+
+    let (count, csv) = export(path, &HistoryFilter::default(), ExportFormat::Csv);
+    assert_eq!(count, 1);
+    assert!(csv.ends_with(&format!("1,{},,,,avatar,(hidden),ok\n", at(0).to_rfc3339())));
+}
+
+#[tokio::test]
+async fn capped_exports_stop_at_the_limit() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    for seconds in 0..10 {
+        insert_row(path, seconds, "42", None, "avatar", "done", "ok");
+    }
+    let (_, full) = export(path, &HistoryFilter::default(), ExportFormat::Csv);
+
+    let mut fits = CappedBuffer::new(full.len());
+    assert_eq!(
+        export_history(
+            path,
+            &HistoryFilter::default(),
+            ExportFormat::Csv,
+            &mut fits
+        ),
+        Ok(10)
+    );
+    assert!(!fits.overflowed());
+    assert_eq!(fits.into_inner(), full.as_bytes());
+
+    let mut capped = CappedBuffer::new(full.len() / 2);
+    assert!(
+        export_history(
+            path,
+            &HistoryFilter::default(),
+            ExportFormat::Csv,
+            &mut capped
+        )
+        .is_err()
+    );
+    assert!(capped.overflowed());
+    assert!(capped.into_inner().len() <= full.len() / 2);
+}
+
+#[test]
+fn time_bounds_accept_dates_and_timestamps() {
+    assert_eq!(
+        parse_time_bound("2023-11-14").expect("date"),
+        at(-(22 * 3600 + 13 * 60 + 20))
+    );
+    assert_eq!(
+        parse_time_bound("2023-11-14T23:13:20+01:00").expect("timestamp"),
+        at(0)
+    );
+    assert!(parse_time_bound("yesterday").is_err());
+}
+
+#[test]
+fn export_formats_parse_and_name_files() {
+    assert_eq!(ExportFormat::parse("CSV"), Some(ExportFormat::Csv));
+    assert_eq!(ExportFormat::parse("ndjson"), Some(ExportFormat::JsonLines));
+    assert_eq!(ExportFormat::parse("xml"), None);
+    assert_eq!(
+        export_file_name(ExportFormat::JsonLines, at(0)),
+        "history-20231114T221320Z.jsonl"
+    );
+}
+
+#[test]
+fn export_query_is_validated() {
+    let (format, filter) = ExportQuery::default().parse().expect("defaults");
+    assert_eq!(format, ExportFormat::Csv);
+    assert_eq!(filter, HistoryFilter::default());
+
+    let query = ExportQuery {
+        format: Some("jsonl".to_string()),
+        since: Some("2023-11-14".to_string()),
+        include_denied: Some(true),
+        ..ExportQuery::default()
+    };
+    let (format, filter) = query.parse().expect("valid query");
+    assert_eq!(format, ExportFormat::JsonLines);
+    assert!(filter.since.is_some() && filter.include_denied);
+
+    let bad_format = ExportQuery {
+        format: Some("xml".to_string()),
+        ..ExportQuery::default()
+    };
+    assert!(bad_format.parse().is_err());
+    let bad_bound = ExportQuery {
+        until: Some("soon".to_string()),
+        ..ExportQuery::default()
+    };
+    assert!(bad_bound.parse().is_err());
+}
+
+#[test]
+fn api_token_is_read_from_the_environment() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert_eq!(config.api_token, None);
+    let config = BotConfig::from_lookup(|key| (key == "API_TOKEN").then(|| " secret ".to_string()))
+        .expect("valid config");
+    assert_eq!(config.api_token.as_deref(), Some("secret"));
+    let config = BotConfig::from_lookup(|key| (key == "API_TOKEN").then(|| "  ".to_string()))
+        .expect("valid config");
+    assert_eq!(config.api_token, None);
+}
@@ -0,0 +1,570 @@
+//! Behaviour every `HistoryStore` backend has to share. Each test runs against an empty
+//! memory store and a fresh SQLite database, and against PostgreSQL too when
+//! `TEST_DATABASE_URL` is set (see
+//! `scripts/test-postgres.sh`). The tests are plain `#[test]`s because the Postgres
+//! client blocks and must not run inside an async runtime.
+use discordbot::config::BotConfig;
+use discordbot::export::HistoryFilter;
+use discordbot::retention::{PruneReport, RetentionPolicy};
+use discordbot::store::{
+    GroupCount, HistoryBackend, HistoryGroup, HistoryQuery, HistoryStore, MEMORY_HISTORY_CAPACITY,
+    MemoryHistoryStore, PostgresHistoryStore, SqliteHistoryStore, UserCount,
+};
+use discordbot::{CommandStatus, HistoryEntry, db_setup};
+use std::collections::HashMap;
+use tempfile::NamedTempFile;
+
+fn at(seconds: i64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).expect("timestamp")
+}
+
+/// Runs `check` against every configured backend, each starting with an empty history
+fn for_each_store(name: &str, check: fn(&dyn HistoryStore)) {
+    check(&MemoryHistoryStore::new(MEMORY_HISTORY_CAPACITY));
+
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    tokio::runtime::Runtime::new()
+        .expect("runtime")
+        .block_on(db_setup(path));
+    check(&SqliteHistoryStore::new(path));
+
+    match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) => {
+            // One schema per test keeps the tests independent while they run in parallel
+            let schema = format!("history_store_{}", name);
+            let mut admin = postgres::Client::connect(&url, postgres::NoTls).expect("connect");
+            admin
+                .batch_execute(&format!(
+                    "DROP SCHEMA IF EXISTS {0} CASCADE; CREATE SCHEMA {0};",
+                    schema
+                ))
+                .expect("create schema");
+            let separator = if url.contains('?') { '&' } else { '?' };
+            let store = PostgresHistoryStore::connect(&format!(
+                "{}{}options=-csearch_path%3D{}",
+                url, separator, schema
+            ))
+            .expect("connect store");
+            check(&store);
+            drop(store);
+            admin
+                .batch_execute(&format!("DROP SCHEMA {} CASCADE;", schema))
+                .expect("drop schema");
+        }
+        Err(_) => eprintln!(
+            "TEST_DATABASE_URL is not set, skipping Postgres for {}",
+            name
+        ),
+    }
+}
+
+fn insert(
+    store: &dyn HistoryStore,
+    seconds: i64,
+    user: &str,
+    guild: Option<&str>,
+    command: &str,
+    output: &str,
+    status: CommandStatus,
+) {
+    let entry = HistoryEntry {
+        guild_id: guild.map(str::to_string),
+        status,
+        ..HistoryEntry::new(user, &format!("user{}", user), command, output)
+    };
+    store.insert(&entry, at(seconds)).expect("insert");
+}
+
+fn outputs(store: &dyn HistoryStore, query: &HistoryQuery) -> Vec<String> {
+    let mut outputs = Vec::new();
+    let count = store
+        .query(query, &mut |row| {
+            outputs.push(row.output);
+            Ok(())
+        })
+        .expect("query");
+    assert_eq!(count, outputs.len());
+    outputs
+}
+
+fn filtered(filter: HistoryFilter) -> HistoryQuery {
+    HistoryQuery {
+        filter,
+        ..HistoryQuery::default()
+    }
+}
+
+#[test]
+fn rows_round_trip_in_order() {
+    for_each_store("round_trip", |store| {
+        insert(store, 20, "2", None, "avatar", "third", CommandStatus::Ok);
+        insert(
+            store,
+            0,
+            "1",
+            Some("9"),
+            "codename",
+            "first",
+            CommandStatus::Ok,
+        );
+        insert(
+            store,
+            10,
+            "1",
+            Some("9"),
+            "userinfo",
+            "second",
+            CommandStatus::Ok,
+        );
+
+        let mut rows = Vec::new();
+        store
+            .query(&HistoryQuery::default(), &mut |row| {
+                rows.push(row);
+                Ok(())
+            })
+            .expect("query");
+        let first = &rows[0];
+        assert_eq!(first.timestamp, at(0).to_rfc3339());
+        assert_eq!(first.user_id, "1");
+        assert_eq!(first.username, "user1");
+        assert_eq!(first.guild_id.as_deref(), Some("9"));
+        assert_eq!(first.command, "codename");
+        assert_eq!(first.status, "ok");
+        assert_eq!(rows[2].guild_id, None);
+
+        assert_eq!(
+            outputs(store, &HistoryQuery::default()),
+            ["first", "second", "third"]
+        );
+        let newest = HistoryQuery {
+            newest_first: true,
+            limit: Some(2),
+            ..HistoryQuery::default()
+        };
+        assert_eq!(outputs(store, &newest), ["third", "second"]);
+    });
+}
+
+#[test]
+fn batches_are_inserted_in_order() {
+    for_each_store("batches", |store| {
+        let batch: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .map(|(index, output)| {
+                (
+                    HistoryEntry::new("1", "user1", "avatar", output),
+                    at(index as i64),
+                )
+            })
+            .collect();
+        store.insert_batch(&batch).expect("insert batch");
+        store.insert_batch(&[]).expect("insert empty batch");
+        assert_eq!(outputs(store, &HistoryQuery::default()), ["a", "b", "c"]);
+    });
+}
+
+#[test]
+fn filters_and_counts_agree() {
+    for_each_store("filters", |store| {
+        insert(store, 0, "1", Some("9"), "codename", "a", CommandStatus::Ok);
+        insert(
+            store,
+            10,
+            "1",
+            Some("9"),
+            "codename generate",
+            "b",
+            CommandStatus::Ok,
+        );
+        insert(
+            store,
+            20,
+            "2",
+            Some("8"),
+            "codenames",
+            "c",
+            CommandStatus::Ok,
+        );
+        insert(store, 30, "2", None, "avatar", "d", CommandStatus::Denied);
+        insert(store, 40, "3", None, "avatar", "e", CommandStatus::Error);
+
+        let cases = [
+            (HistoryFilter::default(), vec!["a", "b", "c"]),
+            (
+                HistoryFilter {
+                    include_denied: true,
+                    ..HistoryFilter::default()
+                },
+                vec!["a", "b", "c", "d", "e"],
+            ),
+            (
+                HistoryFilter {
+                    include_errors: true,
+                    ..HistoryFilter::default()
+                },
+                vec!["a", "b", "c", "e"],
+            ),
+            (
+                HistoryFilter {
+                    since: Some(at(10)),
+                    until: Some(at(20)),
+                    ..HistoryFilter::default()
+                },
+                vec!["b"],
+            ),
+            (
+                HistoryFilter {
+                    guild_id: Some("9".to_string()),
+                    ..HistoryFilter::default()
+                },
+                vec!["a", "b"],
+            ),
+            // Commands run in DMs have no guild to leave out
+            (
+                HistoryFilter {
+                    exclude_guilds: vec!["9".to_string()],
+                    include_denied: true,
+                    ..HistoryFilter::default()
+                },
+                vec!["c", "d", "e"],
+            ),
+            (
+                HistoryFilter {
+                    user_id: Some("2".to_string()),
+                    include_denied: true,
+                    ..HistoryFilter::default()
+                },
+                vec!["c", "d"],
+            ),
+            (
+                HistoryFilter {
+                    command: Some("codename".to_string()),
+                    ..HistoryFilter::default()
+                },
+                vec!["a", "b"],
+            ),
+            // Command names match literally and case-sensitively in every backend
+            (
+                HistoryFilter {
+                    command: Some("code_ame".to_string()),
+                    ..HistoryFilter::default()
+                },
+                vec![],
+            ),
+            (
+                HistoryFilter {
+                    command: Some("CODENAME".to_string()),
+                    ..HistoryFilter::default()
+                },
+                vec![],
+            ),
+        ];
+        for (filter, expected) in cases {
+            assert_eq!(store.count(&filter).expect("count"), expected.len() as u64);
+            assert_eq!(outputs(store, &filtered(filter)), expected);
+        }
+    });
+}
+
+/// `count_by` or `count_users_by` results as sorted (keys, count) pairs
+fn sorted(counts: Vec<GroupCount>) -> Vec<(Vec<String>, u64)> {
+    let mut counts: Vec<(Vec<String>, u64)> = counts
+        .into_iter()
+        .map(|group| (group.keys, group.count))
+        .collect();
+    counts.sort();
+    counts
+}
+
+fn keys(keys: &[&str], count: u64) -> (Vec<String>, u64) {
+    (keys.iter().map(|key| key.to_string()).collect(), count)
+}
+
+#[test]
+fn aggregates_count_per_group() {
+    for_each_store("aggregates", |store| {
+        // at(0) is 2023-11-14T22:13:20Z
+        insert(store, 0, "1", Some("9"), "codename", "a", CommandStatus::Ok);
+        insert(store, 10, "1", Some("9"), "avatar", "b", CommandStatus::Ok);
+        insert(
+            store,
+            3_600,
+            "2",
+            Some("9"),
+            "avatar",
+            "c",
+            CommandStatus::Ok,
+        );
+        let renamed = HistoryEntry {
+            guild_id: Some("9".to_string()),
+            status: CommandStatus::Error,
+            ..HistoryEntry::new("1", "alice", "avatar", "d")
+        };
+        store.insert(&renamed, at(7_200)).expect("insert");
+        insert(
+            store,
+            7_300,
+            "3",
+            None,
+            "avatar",
+            "e",
+            CommandStatus::Denied,
+        );
+
+        let ok = HistoryFilter::default();
+        let everything = HistoryFilter {
+            include_denied: true,
+            ..HistoryFilter::default()
+        };
+        assert_eq!(
+            sorted(
+                store
+                    .count_by(&ok, &[HistoryGroup::Command])
+                    .expect("count")
+            ),
+            [keys(&["avatar"], 2), keys(&["codename"], 1)]
+        );
+        assert_eq!(
+            sorted(store.count_by(&ok, &[HistoryGroup::Hour]).expect("count")),
+            [keys(&["22"], 2), keys(&["23"], 1)]
+        );
+        assert_eq!(
+            sorted(
+                store
+                    .count_by(
+                        &everything,
+                        &[HistoryGroup::TimePrefix(10), HistoryGroup::Status]
+                    )
+                    .expect("count")
+            ),
+            [
+                keys(&["2023-11-14", "ok"], 3),
+                keys(&["2023-11-15", "denied"], 1),
+                keys(&["2023-11-15", "error"], 1),
+            ]
+        );
+        assert_eq!(
+            sorted(
+                store
+                    .count_users_by(&ok, &[HistoryGroup::TimePrefix(10)])
+                    .expect("count")
+            ),
+            [keys(&["2023-11-14"], 2)]
+        );
+        assert_eq!(
+            sorted(store.count_users_by(&everything, &[]).expect("count")),
+            [keys(&[], 3)]
+        );
+        let nothing = HistoryFilter {
+            user_id: Some("4".to_string()),
+            ..HistoryFilter::default()
+        };
+        assert_eq!(
+            sorted(store.count_users_by(&nothing, &[]).expect("count")),
+            [keys(&[], 0)]
+        );
+
+        let mut users = store
+            .user_counts(&HistoryFilter {
+                include_errors: true,
+                ..HistoryFilter::default()
+            })
+            .expect("count");
+        users.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        assert_eq!(
+            users,
+            [
+                UserCount {
+                    user_id: "1".to_string(),
+                    username: "alice".to_string(),
+                    commands: 3,
+                },
+                UserCount {
+                    user_id: "2".to_string(),
+                    username: "user2".to_string(),
+                    commands: 1,
+                },
+            ]
+        );
+    });
+}
+
+#[test]
+fn visitor_errors_stop_the_query() {
+    for_each_store("visitor_errors", |store| {
+        for seconds in 0..3 {
+            insert(
+                store,
+                seconds,
+                "1",
+                None,
+                "avatar",
+                "row",
+                CommandStatus::Ok,
+            );
+        }
+        let mut visited = 0;
+        let result = store.query(&HistoryQuery::default(), &mut |_| {
+            visited += 1;
+            Err("stop".to_string())
+        });
+        assert_eq!(result, Err("stop".to_string()));
+        assert_eq!(visited, 1);
+    });
+}
+
+#[test]
+fn prune_honours_policy_and_guild_overrides() {
+    for_each_store("prune", |store| {
+        let day = 24 * 3600;
+        let now = at(100 * day);
+        // 50 days old: past the global 30 days, but within guild 7's 60 days
+        insert(
+            store,
+            50 * day,
+            "1",
+            Some("7"),
+            "avatar",
+            "kept",
+            CommandStatus::Ok,
+        );
+        insert(
+            store,
+            50 * day,
+            "1",
+            Some("8"),
+            "avatar",
+            "old",
+            CommandStatus::Ok,
+        );
+        insert(
+            store,
+            50 * day,
+            "1",
+            None,
+            "avatar",
+            "old dm",
+            CommandStatus::Ok,
+        );
+        // 80 days old: past guild 7's override too
+        insert(
+            store,
+            20 * day,
+            "1",
+            Some("7"),
+            "avatar",
+            "ancient",
+            CommandStatus::Ok,
+        );
+        insert(
+            store,
+            95 * day,
+            "1",
+            Some("8"),
+            "avatar",
+            "recent",
+            CommandStatus::Ok,
+        );
+        insert(
+            store,
+            99 * day,
+            "1",
+            Some("8"),
+            "avatar",
+            "newest",
+            CommandStatus::Ok,
+        );
+
+        let overrides = HashMap::from([("7".to_string(), 60)]);
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            max_rows: None,
+        };
+        let report = store.prune(&policy, &overrides, now).expect("prune");
+        assert_eq!(
+            report,
+            PruneReport {
+                expired: 3,
+                trimmed: 0
+            }
+        );
+        assert_eq!(
+            outputs(store, &HistoryQuery::default()),
+            ["kept", "recent", "newest"]
+        );
+
+        let policy = RetentionPolicy {
+            max_age_days: None,
+            max_rows: Some(2),
+        };
+        let report = store.prune(&policy, &overrides, now).expect("prune");
+        assert_eq!(report.trimmed, 1);
+        assert_eq!(
+            outputs(store, &HistoryQuery::default()),
+            ["recent", "newest"]
+        );
+        store.maintain(true).expect("maintain");
+    });
+}
+
+#[test]
+fn delete_user_only_removes_their_rows() {
+    for_each_store("delete_user", |store| {
+        insert(store, 0, "1", Some("9"), "avatar", "a", CommandStatus::Ok);
+        insert(store, 1, "1", None, "avatar", "b", CommandStatus::Denied);
+        insert(store, 2, "2", Some("9"), "avatar", "c", CommandStatus::Ok);
+
+        assert_eq!(store.delete_user("1").expect("delete"), 2);
+        assert_eq!(store.delete_user("1").expect("delete"), 0);
+        let everything = HistoryFilter {
+            include_denied: true,
+            ..HistoryFilter::default()
+        };
+        assert_eq!(outputs(store, &filtered(everything)), ["c"]);
+    });
+}
+
+#[test]
+fn memory_store_drops_the_oldest_rows_when_full() {
+    let store = MemoryHistoryStore::new(2);
+    // Insertion order decides what is dropped, not the timestamps
+    insert(&store, 10, "1", None, "avatar", "a", CommandStatus::Ok);
+    insert(&store, 0, "1", None, "avatar", "b", CommandStatus::Ok);
+    insert(&store, 20, "1", None, "avatar", "c", CommandStatus::Ok);
+    assert_eq!(outputs(&store, &HistoryQuery::default()), ["b", "c"]);
+    assert_eq!(store.count(&HistoryFilter::default()).expect("count"), 2);
+}
+
+#[test]
+fn backend_is_read_from_the_environment() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert_eq!(config.history_backend, HistoryBackend::Sqlite);
+
+    let config = BotConfig::from_lookup(|key| match key {
+        "HISTORY_BACKEND" => Some("PostgreSQL".to_string()),
+        "DATABASE_URL" => Some("postgres://bot@localhost/discordbot".to_string()),
+        _ => None,
+    })
+    .expect("valid config");
+    assert_eq!(
+        config.history_backend,
+        HistoryBackend::Postgres {
+            url: "postgres://bot@localhost/discordbot".to_string()
+        }
+    );
+
+    assert!(HistoryBackend::parse(Some("postgres"), None).is_err());
+    assert!(HistoryBackend::parse(Some("postgres"), Some(" ")).is_err());
+    assert!(HistoryBackend::parse(Some("mysql"), None).is_err());
+    assert_eq!(
+        HistoryBackend::parse(Some("memory"), None),
+        Ok(HistoryBackend::Memory)
+    );
+    assert_eq!(
+        HistoryBackend::parse(Some(" sqlite "), Some("postgres://unused")),
+        Ok(HistoryBackend::Sqlite)
+    );
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use discordbot::HistoryEntry;
+use discordbot::config::BotConfig;
+use discordbot::export::HistoryFilter;
+use discordbot::history_writer::{HistoryWriter, WriterConfig};
+use discordbot::retention::{PruneReport, RetentionPolicy};
+use discordbot::store::{
+    GroupCount, HistoryGroup, HistoryQuery, HistoryRow, HistoryStore, MemoryHistoryStore,
+    StoreResult, UserCount,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+fn entry(output: &str) -> HistoryEntry {
+    HistoryEntry::new("1", "user1", "avatar", output)
+}
+
+fn rows(store: &dyn HistoryStore) -> u64 {
+    store.count(&HistoryFilter::default()).expect("count")
+}
+
+/// Memory store whose batch inserts wait while the test holds `gate`
+struct GatedStore {
+    inner: MemoryHistoryStore,
+    gate: Arc<Mutex<()>>,
+}
+
+impl HistoryStore for GatedStore {
+    fn backend(&self) -> &'static str {
+        "gated"
+    }
+
+    fn insert(&self, entry: &HistoryEntry, timestamp: DateTime<Utc>) -> StoreResult<()> {
+        self.inner.insert(entry, timestamp)
+    }
+
+    fn insert_batch(&self, entries: &[(HistoryEntry, DateTime<Utc>)]) -> StoreResult<()> {
+        let _open = self.gate.blocking_lock();
+        self.inner.insert_batch(entries)
+    }
+
+    fn query(
+        &self,
+        query: &HistoryQuery,
+        visit: &mut dyn FnMut(HistoryRow) -> StoreResult<()>,
+    ) -> StoreResult<usize> {
+        self.inner.query(query, visit)
+    }
+
+    fn count(&self, filter: &HistoryFilter) -> StoreResult<u64> {
+        self.inner.count(filter)
+    }
+
+    fn count_by(
+        &self,
+        filter: &HistoryFilter,
+        groups: &[HistoryGroup],
+    ) -> StoreResult<Vec<GroupCount>> {
+        self.inner.count_by(filter, groups)
+    }
+
+    fn count_users_by(
+        &self,
+        filter: &HistoryFilter,
+        groups: &[HistoryGroup],
+    ) -> StoreResult<Vec<GroupCount>> {
+        self.inner.count_users_by(filter, groups)
+    }
+
+    fn user_counts(&self, filter: &HistoryFilter) -> StoreResult<Vec<UserCount>> {
+        self.inner.user_counts(filter)
+    }
+
+    fn prune(
+        &self,
+        policy: &RetentionPolicy,
+        overrides: &HashMap<String, u32>,
+        now: DateTime<Utc>,
+    ) -> StoreResult<PruneReport> {
+        self.inner.prune(policy, overrides, now)
+    }
+
+    fn delete_user(&self, user_id: &str) -> StoreResult<usize> {
+        self.inner.delete_user(user_id)
+    }
+
+    fn maintain(&self, vacuum: bool) -> StoreResult<()> {
+        self.inner.maintain(vacuum)
+    }
+}
+
+#[tokio::test]
+async fn entries_are_written_in_batches_of_the_batch_size() {
+    let store = Arc::new(MemoryHistoryStore::new(100));
+    let writer = HistoryWriter::spawn(
+        store.clone(),
+        WriterConfig {
+            batch_size: 3,
+            flush_interval_ms: 60_000,
+            queue_capacity: 100,
+        },
+    );
+    for index in 0..7 {
+        writer.log(entry(&index.to_string())).await;
+    }
+    writer.flush().await;
+
+    assert_eq!(rows(&*store), 7);
+    let metrics = writer.metrics();
+    assert_eq!(metrics.written, 7);
+    assert_eq!(metrics.batches, 3);
+    assert_eq!(metrics.failed, 0);
+    assert_eq!(metrics.queue_depth, 0);
+    assert_eq!(metrics.queue_capacity, 100);
+}
+
+#[tokio::test]
+async fn partial_batches_are_written_after_the_flush_interval() {
+    let store = Arc::new(MemoryHistoryStore::new(100));
+    let writer = HistoryWriter::spawn(
+        store.clone(),
+        WriterConfig {
+            batch_size: 100,
+            flush_interval_ms: 20,
+            queue_capacity: 100,
+        },
+    );
+    writer.log(entry("a")).await;
+    writer.log(entry("b")).await;
+    assert_eq!(rows(&*store), 0, "nothing is written synchronously");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(rows(&*store), 2);
+    assert_eq!(writer.metrics().batches, 1);
+}
+
+#[tokio::test]
+async fn log_waits_while_the_queue_is_full() {
+    let gate = Arc::new(Mutex::new(()));
+    let store = Arc::new(GatedStore {
+        inner: MemoryHistoryStore::new(100),
+        gate: gate.clone(),
+    });
+    let writer = HistoryWriter::spawn(
+        store.clone(),
+        WriterConfig {
+            batch_size: 1,
+            flush_interval_ms: 0,
+            queue_capacity: 1,
+        },
+    );
+    let closed = gate.lock().await;
+    // The writer takes the first entry and blocks on the gate, the second fills the queue
+    writer.log(entry("a")).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    writer.log(entry("b")).await;
+    assert_eq!(writer.metrics().queue_depth, 1);
+
+    let blocked = writer.clone();
+    let third = tokio::spawn(async move { blocked.log(entry("c")).await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!third.is_finished(), "log waits for room in the queue");
+    assert_eq!(writer.metrics().full_waits, 1);
+
+    drop(closed);
+    third.await.expect("log task");
+    writer.flush().await;
+    assert_eq!(rows(&*store), 3);
+    assert_eq!(writer.metrics().written, 3);
+}
+
+#[tokio::test]
+async fn flushing_before_a_delete_removes_queued_rows_too() {
+    let store = Arc::new(MemoryHistoryStore::new(100));
+    let writer = HistoryWriter::spawn(
+        store.clone(),
+        WriterConfig {
+            batch_size: 100,
+            flush_interval_ms: 60_000,
+            queue_capacity: 100,
+        },
+    );
+    writer
+        .log(HistoryEntry::new("42", "alice", "avatar", "a"))
+        .await;
+    writer
+        .log(HistoryEntry::new("43", "bob", "avatar", "b"))
+        .await;
+    // What /privacy delete does: flush, then delete
+    writer.flush().await;
+    assert_eq!(store.delete_user("42").expect("delete"), 1);
+
+    writer.flush().await;
+    let remaining = HistoryFilter {
+        user_id: Some("42".to_string()),
+        ..HistoryFilter::default()
+    };
+    assert_eq!(store.count(&remaining).expect("count"), 0);
+    assert_eq!(rows(&*store), 1);
+}
+
+#[test]
+fn writer_settings_are_read_from_the_environment() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert_eq!(config.history_writer, WriterConfig::default());
+
+    let config = BotConfig::from_lookup(|key| match key {
+        "HISTORY_BATCH_SIZE" => Some("50".to_string()),
+        "HISTORY_FLUSH_MS" => Some("0".to_string()),
+        "HISTORY_QUEUE_CAPACITY" => Some("200".to_string()),
+        _ => None,
+    })
+    .expect("valid config");
+    assert_eq!(
+        config.history_writer,
+        WriterConfig {
+            batch_size: 50,
+            flush_interval_ms: 0,
+            queue_capacity: 200
+        }
+    );
+
+    for key in ["HISTORY_BATCH_SIZE", "HISTORY_QUEUE_CAPACITY"] {
+        let error = BotConfig::from_lookup(|name| (name == key).then(|| "0".to_string()))
+            .expect_err("zero is rejected");
+        assert!(error.contains(key), "{}", error);
+    }
+}
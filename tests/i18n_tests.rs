@@ -0,0 +1,81 @@
+use discordbot::i18n::{
+    CATALOG, Catalog, DEFAULT_LOCALE, DEFAULT_LOCALES_PATH, command_message_id,
+};
+use discordbot::settings::{SUPPORTED_LOCALES, SettingKey};
+use discordbot::userinfo::format_age;
+use discordbot::{format_codename_response, format_register_response};
+use fluent::fluent_args;
+
+#[test]
+fn every_locale_defines_every_message() {
+    let catalog = Catalog::load(DEFAULT_LOCALES_PATH).expect("load catalogs");
+    let reference = catalog.message_ids(DEFAULT_LOCALE);
+    assert!(!reference.is_empty());
+    for locale in catalog.locales() {
+        let ids = catalog.message_ids(locale);
+        let missing: Vec<_> = reference.difference(&ids).collect();
+        let extra: Vec<_> = ids.difference(&reference).collect();
+        assert!(missing.is_empty(), "{} is missing {:?}", locale, missing);
+        assert!(
+            extra.is_empty(),
+            "{} has unknown messages {:?}",
+            locale,
+            extra
+        );
+    }
+}
+
+#[test]
+fn supported_locales_have_catalogs_and_setting_descriptions() {
+    let locales = CATALOG.locales();
+    for locale in SUPPORTED_LOCALES {
+        assert!(locales.contains(locale), "no catalog for {}", locale);
+        for key in SettingKey::ALL {
+            assert_ne!(key.description(locale), format!("setting-{}", key.name()));
+        }
+    }
+}
+
+#[test]
+fn locales_resolve_to_the_closest_catalog() {
+    assert_eq!(CATALOG.resolve("de"), "de");
+    assert_eq!(CATALOG.resolve("en-GB"), "en-US");
+    assert_eq!(CATALOG.resolve("fr-CA"), "fr");
+    assert_eq!(CATALOG.resolve("ja"), DEFAULT_LOCALE);
+    assert_eq!(
+        CATALOG.tr("ja", "register-done", None),
+        format_register_response(DEFAULT_LOCALE)
+    );
+    assert_eq!(CATALOG.tr("de", "no-such-message", None), "no-such-message");
+    assert!(CATALOG.get("ja", "register-done", None).is_none());
+}
+
+#[test]
+fn replies_are_formatted_per_locale() {
+    assert!(format_codename_response("de", "Quick Fox").contains("**Quick Fox!**"));
+    assert_ne!(
+        format_codename_response("de", "Quick Fox"),
+        format_codename_response(DEFAULT_LOCALE, "Quick Fox")
+    );
+    let from = chrono::DateTime::from_timestamp(0, 0).expect("timestamp");
+    assert_ne!(
+        format_age("fr", from, from + chrono::Duration::days(2)),
+        format_age(DEFAULT_LOCALE, from, from + chrono::Duration::days(2))
+    );
+    let one = CATALOG.tr("de", "age-days", Some(&fluent_args!["count" => 1]));
+    let two = CATALOG.tr("de", "age-days", Some(&fluent_args!["count" => 2]));
+    assert_eq!(one, "einem Tag");
+    assert_eq!(two, "2 Tagen");
+}
+
+#[test]
+fn command_message_ids_use_dashed_qualified_names() {
+    assert_eq!(
+        command_message_id("codename generate"),
+        "command-codename-generate"
+    );
+    assert_eq!(command_message_id("Show avatar"), "command-show-avatar");
+    let ids = CATALOG.message_ids(DEFAULT_LOCALE);
+    assert!(ids.contains("command-codename-generate-description"));
+    assert!(ids.contains("command-show-avatar-name"));
+}
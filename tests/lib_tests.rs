@@ -17,18 +17,21 @@ fn generate_codename_errors_on_empty_parts() {
     let empty = CodenameData {
         animals: vec![],
         adjectives: vec![],
+        ..Default::default()
     };
     assert!(generate_codename(&empty).is_err());
 
     let only_animals = CodenameData {
         animals: vec!["fox".to_string()],
         adjectives: vec![],
+        ..Default::default()
     };
     assert!(generate_codename(&only_animals).is_err());
 
     let only_adjectives = CodenameData {
         animals: vec![],
         adjectives: vec!["quick".to_string()],
+        ..Default::default()
     };
     assert!(generate_codename(&only_adjectives).is_err());
 }
@@ -38,6 +41,7 @@ fn generate_codename_returns_ok_for_valid_data() {
     let data = CodenameData {
         animals: vec!["Fox".to_string()],
         adjectives: vec!["Quick".to_string()],
+        ..Default::default()
     };
     let res = generate_codename(&data).expect("should generate");
     assert!(res.contains("Quick"));
@@ -50,6 +54,7 @@ fn generate_codename_multiple_runs() {
     let data = CodenameData {
         animals: vec!["fox".to_string(), "dog".to_string()],
         adjectives: vec!["quick".to_string(), "brown".to_string()],
+        ..Default::default()
     };
 
     for _ in 0..10 {
@@ -57,3 +62,60 @@ fn generate_codename_multiple_runs() {
         assert!(!res.is_empty());
     }
 }
+
+#[test]
+fn generate_codename_batch_returns_distinct_names() {
+    let data = CodenameData {
+        animals: vec!["fox".to_string(), "dog".to_string(), "owl".to_string()],
+        adjectives: vec!["quick".to_string(), "brown".to_string()],
+        ..Default::default()
+    };
+    let taken: std::collections::HashSet<String> = ["Quick Fox".to_string()].into_iter().collect();
+    let batch = discordbot::generate_codename_batch(&data, 5, &taken).expect("should generate");
+    assert_eq!(batch.len(), 5);
+    let unique: std::collections::HashSet<String> =
+        batch.iter().map(|name| name.to_lowercase()).collect();
+    assert_eq!(
+        unique.len(),
+        5,
+        "batch should not repeat names: {:?}",
+        batch
+    );
+    assert!(!unique.contains("quick fox"));
+
+    // Only five unclaimed combinations exist, so a sixth must fail
+    assert!(discordbot::generate_codename_batch(&data, 6, &taken).is_err());
+}
+
+#[test]
+fn generate_codename_seeded_is_deterministic() {
+    let data = std::fs::read_to_string("./assets/CodenameData.json").expect("read dataset");
+    let data: CodenameData = serde_json::from_str(&data).expect("parse dataset");
+    let first = discordbot::generate_codename_seeded(&data, "ship it friday").expect("generate");
+    let second = discordbot::generate_codename_seeded(&data, "ship it friday").expect("generate");
+    assert_eq!(first, second);
+
+    let others: std::collections::HashSet<String> = (0..20)
+        .map(|i| discordbot::generate_codename_seeded(&data, &format!("message {}", i)).unwrap())
+        .collect();
+    assert!(others.len() > 1, "different seeds should vary");
+}
+
+#[test]
+fn generate_codename_seeded_is_pinned() {
+    let data = CodenameData {
+        animals: ["fox", "dog", "owl", "cat", "elk"]
+            .map(String::from)
+            .to_vec(),
+        adjectives: ["quick", "brown", "lazy", "calm", "bold"]
+            .map(String::from)
+            .to_vec(),
+        ..Default::default()
+    };
+    // Codenames already handed out must not change when dependencies are upgraded
+    let names: Vec<String> = ["ship it friday", "message 1", "message 2"]
+        .iter()
+        .map(|seed| discordbot::generate_codename_seeded(&data, seed).expect("generate"))
+        .collect();
+    assert_eq!(names, ["Brown Dog", "Bold Fox", "Lazy Owl"]);
+}
@@ -0,0 +1,105 @@
+use discordbot::db_setup;
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::permissions::{
+    PermissionRule, RuleTarget, evaluate, format_permission_list_response, list_rules, remove_rule,
+    rule_applies, rules_for_command, set_rule,
+};
+use tempfile::NamedTempFile;
+
+fn rule(command: &str, target: RuleTarget, target_id: &str, allow: bool) -> PermissionRule {
+    PermissionRule {
+        command: command.to_string(),
+        target,
+        target_id: target_id.to_string(),
+        allow,
+    }
+}
+
+#[test]
+fn rules_apply_to_exact_names_parents_and_wildcard() {
+    assert!(rule_applies("codename claim", "codename claim"));
+    assert!(rule_applies("codename", "codename claim"));
+    assert!(rule_applies("*", "avatar"));
+    assert!(!rule_applies("codename claim", "codename"));
+    assert!(!rule_applies("code", "codename claim"));
+}
+
+#[test]
+fn commands_without_rules_are_open() {
+    assert!(evaluate(&[], "1", &[], "10"));
+}
+
+#[test]
+fn deny_rules_win_over_allow_rules() {
+    let rules = vec![
+        rule("avatar", RuleTarget::Role, "100", true),
+        rule("avatar", RuleTarget::User, "1", false),
+    ];
+    let roles = vec!["100".to_string()];
+    assert!(!evaluate(&rules, "1", &roles, "10"));
+    assert!(evaluate(&rules, "2", &roles, "10"));
+}
+
+#[test]
+fn allow_rules_restrict_subjects_and_channels_separately() {
+    let rules = vec![
+        rule("avatar", RuleTarget::Role, "100", true),
+        rule("avatar", RuleTarget::Channel, "10", true),
+    ];
+    let member = vec!["100".to_string()];
+    assert!(evaluate(&rules, "1", &member, "10"));
+    // Right role, wrong channel
+    assert!(!evaluate(&rules, "1", &member, "11"));
+    // Right channel, missing role
+    assert!(!evaluate(&rules, "1", &[], "10"));
+}
+
+#[tokio::test]
+async fn rules_round_trip_through_the_database() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    set_rule(
+        path,
+        "1",
+        &rule("codename", RuleTarget::Role, "100", true),
+        "42",
+    )
+    .expect("set");
+    set_rule(
+        path,
+        "1",
+        &rule("*", RuleTarget::Channel, "10", false),
+        "42",
+    )
+    .expect("set");
+    set_rule(
+        path,
+        "2",
+        &rule("avatar", RuleTarget::User, "7", false),
+        "42",
+    )
+    .expect("set");
+    // Setting the same target again flips the existing rule instead of adding one
+    set_rule(
+        path,
+        "1",
+        &rule("codename", RuleTarget::Role, "100", false),
+        "42",
+    )
+    .expect("set");
+
+    let rules = list_rules(path, "1").expect("list");
+    assert_eq!(rules.len(), 2);
+    assert!(rules.iter().all(|r| !r.allow));
+    assert!(format_permission_list_response(DEFAULT_LOCALE, &rules).contains("<@&100>"));
+
+    let applicable = rules_for_command(path, "1", "codename claim").expect("rules");
+    assert_eq!(applicable.len(), 2);
+    assert!(rules_for_command(path, "1", "avatar").expect("rules").len() == 1);
+
+    assert!(remove_rule(path, "1", "codename", RuleTarget::Role, "100").expect("remove"));
+    assert!(!remove_rule(path, "1", "codename", RuleTarget::Role, "100").expect("remove"));
+    assert_eq!(list_rules(path, "2").expect("list").len(), 1);
+}
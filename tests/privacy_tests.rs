@@ -0,0 +1,125 @@
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::privacy::{
+    ANONYMOUS_NAME, ANONYMOUS_OUTPUT, anonymize, delete_user_history, export_user_data,
+    is_opted_out, set_opted_out,
+};
+use discordbot::userinfo::{UserInfo, userinfo_response};
+use discordbot::{
+    CommandStatus, FeedItem, HistoryEntry, db_setup, insert_command_history_sync,
+    insert_history_entry_sync, load_recent_commands,
+};
+use tempfile::NamedTempFile;
+
+#[tokio::test]
+async fn opt_out_round_trips() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    assert!(!is_opted_out(path, "42").expect("check"));
+    assert!(set_opted_out(path, "42", true).expect("opt out"));
+    assert!(!set_opted_out(path, "42", true).expect("opt out again"));
+    assert!(is_opted_out(path, "42").expect("check"));
+    assert!(!is_opted_out(path, "43").expect("check"));
+    assert!(set_opted_out(path, "42", false).expect("opt in"));
+    assert!(!set_opted_out(path, "42", false).expect("opt in again"));
+    assert!(!is_opted_out(path, "42").expect("check"));
+}
+
+#[tokio::test]
+async fn export_and_delete_only_touch_the_users_rows() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    insert_command_history_sync(path, "42", "alice", "codename generate", "Quick Fox")
+        .expect("insert");
+    let mut denied = HistoryEntry::new("42", "alice", "reload", "not allowed");
+    denied.guild_id = Some("7".to_string());
+    denied.status = CommandStatus::Denied;
+    insert_history_entry_sync(path, &denied).expect("insert");
+    insert_command_history_sync(path, "43", "bob", "avatar", "bob's avatar").expect("insert");
+
+    let export = export_user_data(path, "42").expect("export");
+    assert_eq!(export.user_id, "42");
+    assert!(!export.opted_out);
+    assert_eq!(export.history.len(), 2);
+    assert_eq!(export.history[0].command, "codename generate");
+    assert_eq!(export.history[1].guild_id.as_deref(), Some("7"));
+    assert_eq!(export.history[1].status, "denied");
+    let json = serde_json::to_string(&export).expect("serialize");
+    assert!(json.contains("Quick Fox") && !json.contains("bob"));
+
+    assert_eq!(delete_user_history(path, "42").expect("delete"), 2);
+    assert_eq!(delete_user_history(path, "42").expect("delete"), 0);
+    assert!(
+        export_user_data(path, "42")
+            .expect("export")
+            .history
+            .is_empty()
+    );
+    assert_eq!(
+        export_user_data(path, "43").expect("export").history.len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn opted_out_authors_are_anonymized_in_the_feed() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    insert_command_history_sync(path, "42", "alice", "codename generate", "Quick Fox")
+        .expect("insert");
+    insert_command_history_sync(path, "43", "bob", "avatar", "bob's avatar").expect("insert");
+    set_opted_out(path, "42", true).expect("opt out");
+
+    let items = load_recent_commands(path, 10).expect("load");
+    let alice = items
+        .iter()
+        .find(|item| item.command_name == "codename generate")
+        .expect("alice's row");
+    assert_eq!(alice.author_id, "");
+    assert_eq!(alice.author_name, ANONYMOUS_NAME);
+    assert_eq!(alice.command_output, ANONYMOUS_OUTPUT);
+    let bob = items
+        .iter()
+        .find(|item| item.command_output == "bob's avatar")
+        .expect("bob's row");
+    assert_eq!(bob.author_name, "bob");
+
+    let anonymous = anonymize(bob.clone());
+    assert_eq!(anonymous.author_name, ANONYMOUS_NAME);
+    assert_eq!(anonymous.item_uuid, bob.item_uuid);
+}
+
+#[test]
+fn anonymizing_hides_output_that_identifies_the_author() {
+    let info = UserInfo {
+        user_id: 175928847299117063,
+        user_name: "alice",
+        avatar_url: "https://cdn.discordapp.com/avatars/175928847299117063/a.png",
+        joined_at: None,
+        roles: vec![],
+        commands_run: 3,
+    };
+    let output = userinfo_response(DEFAULT_LOCALE, &info, chrono::Utc::now()).to_text();
+    assert!(output.contains("175928847299117063"));
+    let item = FeedItem {
+        item_uuid: "uuid".to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        author_id: "175928847299117063".to_string(),
+        author_name: "alice".to_string(),
+        command_name: "userinfo".to_string(),
+        command_output: output,
+        test_item: false,
+    };
+
+    let anonymous = anonymize(item);
+    let json = serde_json::to_string(&anonymous).expect("serialize");
+    assert!(!json.contains("175928847299117063"), "{}", json);
+    assert!(!json.contains("alice"), "{}", json);
+    assert_eq!(anonymous.command_output, ANONYMOUS_OUTPUT);
+    assert_eq!(anonymous.command_name, "userinfo");
+}
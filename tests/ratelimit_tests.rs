@@ -0,0 +1,183 @@
+use discordbot::config::BotConfig;
+use discordbot::db_setup;
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::ratelimit::{
+    Invocation, LimitScope, RateLimit, check_rate_limits, format_rate_limits_response,
+    format_slow_down_response, parse_rate_limits, throttle_counts,
+};
+use tempfile::NamedTempFile;
+
+fn invocation<'a>(qualified_name: &'a str, user_id: &'a str) -> Invocation<'a> {
+    Invocation {
+        qualified_name,
+        user_id,
+        channel_id: "10",
+        guild_id: Some("1"),
+    }
+}
+
+#[test]
+fn rate_limits_are_parsed() {
+    let rules = parse_rate_limits("*:user=5/10s; codename generate:user=3/1m,guild=30/1h")
+        .expect("valid spec");
+    assert_eq!(rules.len(), 3);
+    assert_eq!(rules[0].command, "*");
+    assert_eq!(rules[1].command, "codename generate");
+    assert_eq!(rules[1].limit.period_secs, 60.0);
+    assert_eq!(rules[2].scope, LimitScope::Guild);
+    assert_eq!(rules[2].limit.capacity, 30);
+    assert_eq!(rules[2].limit.period_secs, 3600.0);
+
+    assert!(parse_rate_limits("").expect("empty spec").is_empty());
+    assert!(parse_rate_limits("codename").is_err());
+    assert!(parse_rate_limits("codename:team=1/10s").is_err());
+    assert!(parse_rate_limits("codename:user=0/10s").is_err());
+    assert!(parse_rate_limits("codename:user=1/soon").is_err());
+}
+
+#[test]
+fn config_limits_nothing_unless_configured() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert!(config.rate_limits.is_empty());
+
+    let disabled = BotConfig::from_lookup(|key| (key == "RATE_LIMITS").then(String::new))
+        .expect("valid config");
+    assert!(disabled.rate_limits.is_empty());
+
+    let limited =
+        BotConfig::from_lookup(|key| (key == "RATE_LIMITS").then(|| "*:user=5/10s".to_string()))
+            .expect("valid config");
+    assert_eq!(limited.rate_limits.len(), 1);
+    assert_eq!(limited.rate_limits[0].scope, LimitScope::User);
+}
+
+#[test]
+fn rate_limit_overview_is_localized() {
+    let rules = parse_rate_limits("codename generate:user=3/1m").expect("valid spec");
+    let counts = [("codename generate".to_string(), LimitScope::User, 2)];
+    assert_eq!(
+        format_rate_limits_response(DEFAULT_LOCALE, &rules, &counts),
+        "**Rate limits**\n`codename generate` user: 3 per 60s\n\
+         **Throttled in the last 7 days**\n`codename generate` (user): 2"
+    );
+    let german = format_rate_limits_response("de", &[], &[]);
+    assert!(german.contains("Es sind keine Ratenlimits eingerichtet"));
+    assert!(german.contains("Nichts wurde gebremst"));
+}
+
+#[test]
+fn buckets_refill_over_the_period() {
+    let limit = RateLimit {
+        capacity: 4,
+        period_secs: 8.0,
+    };
+    assert_eq!(limit.refill(0.0, 100.0, 102.0), 1.0);
+    assert_eq!(limit.refill(3.0, 100.0, 200.0), 4.0);
+    assert_eq!(limit.retry_after(0.5), 1.0);
+}
+
+#[tokio::test]
+async fn invocations_are_throttled_until_tokens_refill() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    let rules = parse_rate_limits("codename:user=2/10s").expect("valid spec");
+
+    let alice = invocation("codename generate", "42");
+    assert!(
+        check_rate_limits(path, &rules, &alice, 1000.0)
+            .expect("check")
+            .is_none()
+    );
+    assert!(
+        check_rate_limits(path, &rules, &alice, 1000.0)
+            .expect("check")
+            .is_none()
+    );
+    let throttled = check_rate_limits(path, &rules, &alice, 1001.0)
+        .expect("check")
+        .expect("third call is throttled");
+    assert_eq!(throttled.scope, LimitScope::User);
+    assert_eq!(throttled.retry_after_secs, 4.0);
+    assert!(
+        format_slow_down_response(DEFAULT_LOCALE, "codename generate", &throttled).contains("4s")
+    );
+
+    // The parent limit is shared by subcommands but not by other users or commands
+    let claim = invocation("codename claim", "42");
+    assert!(
+        check_rate_limits(path, &rules, &claim, 1001.0)
+            .expect("check")
+            .is_some()
+    );
+    let bob = invocation("codename generate", "43");
+    assert!(
+        check_rate_limits(path, &rules, &bob, 1001.0)
+            .expect("check")
+            .is_none()
+    );
+    let avatar = invocation("avatar", "42");
+    assert!(
+        check_rate_limits(path, &rules, &avatar, 1001.0)
+            .expect("check")
+            .is_none()
+    );
+
+    // Half the period later one token is back
+    assert!(
+        check_rate_limits(path, &rules, &alice, 1005.0)
+            .expect("check")
+            .is_none()
+    );
+
+    let counts = throttle_counts(path, "1970-01-01").expect("counts");
+    assert_eq!(counts.len(), 2);
+    assert!(
+        counts
+            .iter()
+            .all(|(_, scope, count)| *scope == LimitScope::User && *count == 1)
+    );
+}
+
+#[tokio::test]
+async fn an_empty_bucket_does_not_drain_the_others() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    let rules = parse_rate_limits("*:user=1/10s,guild=3/10s").expect("valid spec");
+
+    let alice = invocation("avatar", "42");
+    assert!(
+        check_rate_limits(path, &rules, &alice, 0.0)
+            .expect("check")
+            .is_none()
+    );
+    // Throttled by the user bucket; the guild bucket keeps its tokens
+    assert!(
+        check_rate_limits(path, &rules, &alice, 0.0)
+            .expect("check")
+            .is_some()
+    );
+    assert!(
+        check_rate_limits(path, &rules, &alice, 0.0)
+            .expect("check")
+            .is_some()
+    );
+    let bob = invocation("avatar", "43");
+    let carol = invocation("avatar", "44");
+    assert!(
+        check_rate_limits(path, &rules, &bob, 0.0)
+            .expect("check")
+            .is_none()
+    );
+    assert!(
+        check_rate_limits(path, &rules, &carol, 0.0)
+            .expect("check")
+            .is_none()
+    );
+    let dave = invocation("avatar", "45");
+    let throttled = check_rate_limits(path, &rules, &dave, 0.0)
+        .expect("check")
+        .expect("guild bucket is empty");
+    assert_eq!(throttled.scope, LimitScope::Guild);
+}
@@ -0,0 +1,82 @@
+use discordbot::config::BotConfig;
+use discordbot::db_setup;
+use discordbot::registration::{
+    RegistrationTarget, clear_fingerprints, command_set_fingerprint, store_fingerprint,
+    stored_fingerprint,
+};
+use poise::serenity_prelude as serenity;
+use tempfile::NamedTempFile;
+
+#[test]
+fn dev_guild_ids_are_parsed_from_the_environment() {
+    let config = BotConfig::from_lookup(|key| match key {
+        "DEV_GUILD_IDS" => Some(" 123, 456 ,,".to_string()),
+        _ => None,
+    })
+    .expect("valid config");
+    assert_eq!(config.dev_guild_ids, vec![123, 456]);
+
+    let empty = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert!(empty.dev_guild_ids.is_empty());
+
+    assert!(BotConfig::from_lookup(|_| Some("12,abc".to_string())).is_err());
+}
+
+#[test]
+fn registration_target_follows_dev_guilds() {
+    assert_eq!(
+        RegistrationTarget::from_dev_guilds(&[]),
+        RegistrationTarget::Global
+    );
+    assert_eq!(
+        RegistrationTarget::from_dev_guilds(&[1, 2]),
+        RegistrationTarget::Guilds(vec![1, 2])
+    );
+}
+
+#[test]
+fn fingerprint_changes_only_with_the_command_set() {
+    let commands = || {
+        vec![
+            serenity::CreateCommand::new("codename")
+                .description("Generates a random codename")
+                .description_localized("en-US", "Generates a random codename")
+                .description_localized("de", "Erzeugt einen zufälligen Codenamen"),
+        ]
+    };
+    assert_eq!(
+        command_set_fingerprint(&commands()),
+        command_set_fingerprint(&commands())
+    );
+
+    let changed = vec![serenity::CreateCommand::new("codename").description("Something else")];
+    assert_ne!(
+        command_set_fingerprint(&commands()),
+        command_set_fingerprint(&changed)
+    );
+}
+
+#[tokio::test]
+async fn fingerprints_are_stored_per_scope_and_cleared() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    assert_eq!(stored_fingerprint(path, "global").expect("read"), None);
+    store_fingerprint(path, "global", "aaaa").expect("store");
+    store_fingerprint(path, "guild:1", "bbbb").expect("store");
+    store_fingerprint(path, "global", "cccc").expect("overwrite");
+    assert_eq!(
+        stored_fingerprint(path, "global").expect("read").as_deref(),
+        Some("cccc")
+    );
+    assert_eq!(
+        stored_fingerprint(path, "guild:1")
+            .expect("read")
+            .as_deref(),
+        Some("bbbb")
+    );
+
+    assert_eq!(clear_fingerprints(path).expect("clear"), 2);
+    assert_eq!(stored_fingerprint(path, "guild:1").expect("read"), None);
+}
@@ -0,0 +1,122 @@
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::registry::{
+    canonical_codename, claim_codename, claimed_codenames, format_codename_list_response,
+    generate_codename_avoiding, is_known_codename, list_claimed_codenames, release_codename,
+};
+use discordbot::{CodenameData, db_setup};
+use std::collections::HashSet;
+use tempfile::NamedTempFile;
+
+fn sample_data() -> CodenameData {
+    CodenameData {
+        adjectives: vec!["quick".to_string(), "brown".to_string()],
+        animals: vec!["fox".to_string(), "guinea pig".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn is_known_codename_matches_generated_shapes() {
+    let data = sample_data();
+    assert!(is_known_codename(&data, "Quick Fox"));
+    assert!(is_known_codename(&data, "  brown Guinea pig "));
+    assert!(!is_known_codename(&data, "Quick Owl"));
+    assert!(!is_known_codename(&data, "Slow Fox"));
+    assert!(!is_known_codename(&data, "QuickFox"));
+    // Names written in another /config codename_style are still recognised
+    assert!(is_known_codename(&data, "quick-fox"));
+    assert!(is_known_codename(&data, "BROWN_GUINEA_PIG"));
+}
+
+#[test]
+fn generate_codename_avoiding_skips_taken_names() {
+    let data = CodenameData {
+        adjectives: vec!["quick".to_string()],
+        animals: vec!["fox".to_string(), "owl".to_string()],
+        ..Default::default()
+    };
+    let taken: HashSet<String> = ["Quick Fox".to_string()].into_iter().collect();
+    for _ in 0..20 {
+        let codename = generate_codename_avoiding(&data, &taken, 200).expect("should generate");
+        assert_eq!(codename, "Quick Owl");
+    }
+
+    let all_taken: HashSet<String> = ["Quick Fox".to_string(), "Quick Owl".to_string()]
+        .into_iter()
+        .collect();
+    assert!(generate_codename_avoiding(&data, &all_taken, 10).is_err());
+}
+
+#[tokio::test]
+async fn claim_list_and_release_round_trip() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    assert!(claim_codename(path, "1", "Quick Fox", "42", "alice", "project x").expect("claim"));
+    // Same name in the same guild is rejected, regardless of case
+    assert!(!claim_codename(path, "1", "quick fox", "43", "bob", "").expect("claim"));
+    // ...but another guild may use it
+    assert!(claim_codename(path, "2", "Quick Fox", "43", "bob", "").expect("claim"));
+
+    let entries = list_claimed_codenames(path, "1").expect("list");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].owner_name, "alice");
+    assert_eq!(entries[0].description, "project x");
+    assert!(format_codename_list_response(DEFAULT_LOCALE, &entries).contains("Quick Fox"));
+
+    let taken = claimed_codenames(path, "1").expect("claimed set");
+    assert!(taken.contains("Quick Fox"));
+
+    // Only the owner can release without forcing
+    assert!(!release_codename(path, "1", "Quick Fox", Some("43")).expect("release"));
+    assert!(release_codename(path, "1", "QUICK FOX", Some("42")).expect("release"));
+    assert!(list_claimed_codenames(path, "1").expect("list").is_empty());
+
+    // Forced release ignores the owner
+    assert!(release_codename(path, "2", "Quick Fox", None).expect("release"));
+}
+
+#[test]
+fn canonical_codename_uses_the_generated_spelling() {
+    let data = sample_data();
+    for styled in ["quick-fox", "Quick_Fox", " QUICK FOX ", "Quick Fox"] {
+        assert_eq!(
+            canonical_codename(&data, styled).as_deref(),
+            Some("Quick Fox"),
+            "{:?}",
+            styled
+        );
+    }
+    assert_eq!(
+        canonical_codename(&data, "brown-guinea-pig").as_deref(),
+        Some("Brown Guinea pig")
+    );
+    assert_eq!(canonical_codename(&data, "Quick Owl"), None);
+}
+
+#[tokio::test]
+async fn styled_spellings_of_a_claimed_codename_collide() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    let data = sample_data();
+    let canonical = |name: &str| canonical_codename(&data, name).expect("known codename");
+
+    assert!(claim_codename(path, "1", &canonical("quick-fox"), "42", "alice", "").expect("claim"));
+    assert!(!claim_codename(path, "1", &canonical("Quick Fox"), "43", "bob", "").expect("claim"));
+    assert!(!claim_codename(path, "1", &canonical("Quick_Fox"), "43", "bob", "").expect("claim"));
+
+    let entries = list_claimed_codenames(path, "1").expect("list");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].codename, "Quick Fox");
+    // Unique mode sees the claim under the name it would generate
+    let taken = claimed_codenames(path, "1").expect("claimed set");
+    assert_eq!(taken, HashSet::from(["Quick Fox".to_string()]));
+    assert!(release_codename(path, "1", &canonical("QUICK-FOX"), Some("42")).expect("release"));
+}
+
+#[test]
+fn empty_list_response_mentions_no_claims() {
+    assert!(format_codename_list_response(DEFAULT_LOCALE, &[]).contains("No codenames"));
+}
@@ -0,0 +1,88 @@
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::response::{BotResponse, CODENAME_COLOR, DEFAULT_COLOR};
+use discordbot::{avatar_response, codename_response};
+
+#[test]
+fn description_only_response_text_is_the_description() {
+    let response = BotResponse::from("Registered application commands");
+    assert_eq!(response.color, DEFAULT_COLOR);
+    assert_eq!(response.to_text(), "Registered application commands");
+}
+
+#[test]
+fn text_fallback_includes_every_part() {
+    let response = BotResponse::new("body")
+        .title("Title")
+        .field("Joined", "yesterday")
+        .image("https://cdn.example/a.png")
+        .footer("footer text");
+    assert_eq!(
+        response.to_text(),
+        "**Title**\nbody\nJoined: yesterday\nhttps://cdn.example/a.png\n_footer text_"
+    );
+}
+
+#[test]
+fn embed_serializes_with_title_color_image_and_timestamp() {
+    let response = BotResponse::new("body")
+        .title("Title")
+        .color(0x123456)
+        .thumbnail("https://cdn.example/t.png")
+        .image("https://cdn.example/a.png")
+        .footer("footer text");
+    let json = serde_json::to_value(response.to_embed()).expect("serialize embed");
+    assert_eq!(json["title"], "Title");
+    assert_eq!(json["description"], "body");
+    assert_eq!(json["color"], 0x123456);
+    assert_eq!(json["image"]["url"], "https://cdn.example/a.png");
+    assert_eq!(json["thumbnail"]["url"], "https://cdn.example/t.png");
+    assert_eq!(json["footer"]["text"], "footer text");
+    assert!(json["timestamp"].is_string());
+}
+
+#[test]
+fn codename_and_avatar_responses() {
+    let codename = codename_response(DEFAULT_LOCALE, "Quick Fox");
+    assert_eq!(codename.color, CODENAME_COLOR);
+    assert!(codename.to_text().contains("Quick Fox"));
+
+    let avatar = avatar_response(
+        DEFAULT_LOCALE,
+        "alice",
+        Some("<@42>"),
+        "https://cdn.example/a.png",
+        None,
+    );
+    assert_eq!(avatar.image.as_deref(), Some("https://cdn.example/a.png"));
+    assert!(avatar.to_text().contains("<@42>'s avatar"));
+
+    let plain = avatar_response(
+        DEFAULT_LOCALE,
+        "alice",
+        None,
+        "https://cdn.example/a.png",
+        None,
+    );
+    assert!(plain.description.is_empty());
+    assert!(plain.to_text().contains("https://cdn.example/a.png"));
+
+    let with_banner = avatar_response(
+        DEFAULT_LOCALE,
+        "alice",
+        None,
+        "https://cdn.example/a.png",
+        Some("https://cdn.example/b.png"),
+    );
+    assert_eq!(
+        with_banner.thumbnail.as_deref(),
+        Some("https://cdn.example/a.png")
+    );
+    assert_eq!(
+        with_banner.image.as_deref(),
+        Some("https://cdn.example/b.png")
+    );
+    // The text fallback keeps the avatar next to the banner
+    let text = with_banner.to_text();
+    assert!(text.contains("https://cdn.example/a.png"), "{}", text);
+    assert!(text.contains("https://cdn.example/b.png"), "{}", text);
+}
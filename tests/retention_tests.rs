@@ -0,0 +1,133 @@
+use discordbot::config::BotConfig;
+use discordbot::db_setup;
+use discordbot::retention::{
+    DEFAULT_PRUNE_INTERVAL_HOURS, PruneReport, RetentionPolicy, prune_and_maintain, prune_history,
+};
+use discordbot::settings::{SettingKey, store_setting};
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
+
+/// Inserts a history row `days_ago` days before `now`
+fn insert_row(path: &str, now: chrono::DateTime<chrono::Utc>, days_ago: i64, guild: Option<&str>) {
+    let conn = Connection::open(path).expect("open conn");
+    conn.execute(
+        "INSERT INTO command_history (timestamp, user_id, username, command, output, guild_id) VALUES (?1, '42', 'alice', 'avatar', ?2, ?3)",
+        rusqlite::params![
+            (now - chrono::Duration::days(days_ago)).to_rfc3339(),
+            format!("{} days ago", days_ago),
+            guild
+        ],
+    )
+    .expect("insert");
+}
+
+fn remaining(path: &str) -> Vec<String> {
+    let conn = Connection::open(path).expect("open conn");
+    let mut stmt = conn
+        .prepare("SELECT output FROM command_history ORDER BY timestamp DESC")
+        .expect("prepare");
+    stmt.query_map([], |row| row.get(0))
+        .expect("query")
+        .collect::<rusqlite::Result<_>>()
+        .expect("rows")
+}
+
+#[tokio::test]
+async fn max_age_respects_guild_overrides() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    let now = chrono::Utc::now();
+
+    insert_row(path, now, 1, None);
+    insert_row(path, now, 40, None);
+    insert_row(path, now, 40, Some("1"));
+    insert_row(path, now, 5, Some("2"));
+    insert_row(path, now, 40, Some("3"));
+    // Guild 1 keeps 60 days, guild 2 only 3, guild 3 explicitly uses the default
+    store_setting(path, "1", SettingKey::HistoryRetentionDays, "60", "9").expect("store");
+    store_setting(path, "2", SettingKey::HistoryRetentionDays, "3", "9").expect("store");
+    store_setting(path, "3", SettingKey::HistoryRetentionDays, "default", "9").expect("store");
+
+    let policy = RetentionPolicy {
+        max_age_days: Some(30),
+        max_rows: None,
+    };
+    let report = prune_history(path, &policy, now).expect("prune");
+    assert_eq!(
+        report,
+        PruneReport {
+            expired: 3,
+            trimmed: 0
+        }
+    );
+    assert_eq!(remaining(path), vec!["1 days ago", "40 days ago"]);
+    assert_eq!(prune_history(path, &policy, now).expect("prune").total(), 0);
+}
+
+#[tokio::test]
+async fn max_rows_keeps_the_newest_rows() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    let now = chrono::Utc::now();
+    for days_ago in [3, 1, 4, 2] {
+        insert_row(path, now, days_ago, Some("1"));
+    }
+
+    let policy = RetentionPolicy {
+        max_age_days: None,
+        max_rows: Some(2),
+    };
+    let report = prune_and_maintain(path, &policy, now).expect("prune");
+    assert_eq!(report.trimmed, 2);
+    assert_eq!(remaining(path), vec!["1 days ago", "2 days ago"]);
+
+    // Without limits nothing is removed
+    let report = prune_and_maintain(path, &RetentionPolicy::default(), now).expect("prune");
+    assert_eq!(report.total(), 0);
+}
+
+#[tokio::test]
+async fn history_indexes_exist() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let dbdata = db_setup(path).await;
+    let mut stmt = dbdata
+        .db
+        .prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'command_history'",
+        )
+        .expect("prepare");
+    let names: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .expect("query")
+        .collect::<rusqlite::Result<_>>()
+        .expect("rows");
+    for column in ["timestamp", "user_id", "command"] {
+        assert!(names.contains(&format!("idx_command_history_{}", column)));
+    }
+}
+
+#[test]
+fn retention_is_read_from_the_environment() {
+    let config = BotConfig::from_lookup(|_| None).expect("valid config");
+    assert_eq!(config.retention, RetentionPolicy::default());
+    assert_eq!(config.prune_interval_hours, DEFAULT_PRUNE_INTERVAL_HOURS);
+
+    let config = BotConfig::from_lookup(|key| match key {
+        "HISTORY_MAX_AGE_DAYS" => Some("90".to_string()),
+        "HISTORY_MAX_ROWS" => Some(" ".to_string()),
+        "HISTORY_PRUNE_INTERVAL_HOURS" => Some("0".to_string()),
+        _ => None,
+    })
+    .expect("valid config");
+    assert_eq!(config.retention.max_age_days, Some(90));
+    assert_eq!(config.retention.max_rows, None);
+    assert_eq!(config.prune_interval_hours, 0);
+
+    assert!(
+        BotConfig::from_lookup(|key| (key == "HISTORY_MAX_ROWS").then(|| "-1".to_string()))
+            .is_err()
+    );
+}
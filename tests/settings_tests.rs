@@ -0,0 +1,127 @@
+use discordbot::db_setup;
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::settings::{
+    CodenameStyle, GuildSettings, SettingKey, SettingsCache, format_settings_response,
+    load_guild_settings, reset_setting, store_setting,
+};
+use tempfile::NamedTempFile;
+
+#[test]
+fn setting_values_are_validated_and_normalized() {
+    assert_eq!(
+        SettingKey::parse("Feed_Visible"),
+        Some(SettingKey::FeedVisible)
+    );
+    assert_eq!(SettingKey::parse("colour"), None);
+
+    assert_eq!(
+        SettingKey::EphemeralReplies.validate(" ON "),
+        Ok("true".to_string())
+    );
+    assert_eq!(
+        SettingKey::HistoryLogging.validate("no"),
+        Ok("false".to_string())
+    );
+    assert!(SettingKey::FeedVisible.validate("maybe").is_err());
+    assert_eq!(
+        SettingKey::CodenameStyle.validate("KEBAB"),
+        Ok("kebab".to_string())
+    );
+    assert!(SettingKey::CodenameStyle.validate("camel").is_err());
+    assert_eq!(
+        SettingKey::Locale.validate("en-us"),
+        Ok("en-US".to_string())
+    );
+    assert_eq!(SettingKey::Locale.validate("FR"), Ok("fr".to_string()));
+    assert_eq!(SettingKey::Locale.validate("Auto"), Ok("auto".to_string()));
+    assert_eq!(
+        SettingKey::HistoryRetentionDays.validate("DEFAULT"),
+        Ok("default".to_string())
+    );
+    assert_eq!(
+        SettingKey::HistoryRetentionDays.validate(" 90 "),
+        Ok("90".to_string())
+    );
+    assert!(SettingKey::HistoryRetentionDays.validate("0").is_err());
+    assert!(
+        SettingKey::HistoryRetentionDays
+            .validate("forever")
+            .is_err()
+    );
+    let error = SettingKey::Locale.validate("xx").unwrap_err();
+    assert!(
+        error.contains("en-US"),
+        "error lists the allowed values: {}",
+        error
+    );
+}
+
+#[test]
+fn codename_styles_rewrite_generated_names() {
+    assert_eq!(
+        CodenameStyle::Title.apply("Quick Polar bear"),
+        "Quick Polar bear"
+    );
+    assert_eq!(CodenameStyle::Lower.apply("Quick Fox"), "quick fox");
+    assert_eq!(CodenameStyle::Upper.apply("Quick Fox"), "QUICK FOX");
+    assert_eq!(
+        CodenameStyle::Kebab.apply("Quick Polar bear"),
+        "quick-polar-bear"
+    );
+    assert_eq!(CodenameStyle::Snake.apply("Quick Fox"), "quick_fox");
+}
+
+#[tokio::test]
+async fn settings_round_trip_through_the_database() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+
+    assert_eq!(
+        load_guild_settings(path, "1").expect("load"),
+        GuildSettings::default()
+    );
+
+    store_setting(path, "1", SettingKey::EphemeralReplies, "true", "42").expect("store");
+    store_setting(path, "1", SettingKey::CodenameStyle, "snake", "42").expect("store");
+    store_setting(path, "1", SettingKey::CodenameStyle, "upper", "42").expect("store");
+    store_setting(path, "2", SettingKey::FeedVisible, "false", "42").expect("store");
+
+    let settings = load_guild_settings(path, "1").expect("load");
+    assert!(settings.ephemeral_replies);
+    assert_eq!(settings.codename_style, CodenameStyle::Upper);
+    assert!(settings.feed_visible);
+    assert!(
+        format_settings_response(DEFAULT_LOCALE, &settings).contains("`codename_style` = `upper`")
+    );
+
+    assert!(reset_setting(path, "1", SettingKey::CodenameStyle).expect("reset"));
+    assert!(!reset_setting(path, "1", SettingKey::CodenameStyle).expect("reset"));
+    let settings = load_guild_settings(path, "1").expect("load");
+    assert_eq!(settings.codename_style, CodenameStyle::Title);
+    assert!(!load_guild_settings(path, "2").expect("load").feed_visible);
+
+    assert_eq!(settings.locale, None);
+    assert_eq!(SettingKey::Locale.value_in(&settings), "auto");
+    store_setting(path, "1", SettingKey::Locale, "de", "42").expect("store");
+    let settings = load_guild_settings(path, "1").expect("load");
+    assert_eq!(settings.locale.as_deref(), Some("de"));
+    assert!(format_settings_response("de", &settings).contains("`locale` = `de`"));
+    store_setting(path, "1", SettingKey::Locale, "auto", "42").expect("store");
+    assert_eq!(load_guild_settings(path, "1").expect("load").locale, None);
+}
+
+#[test]
+fn cache_holds_settings_until_invalidated() {
+    let cache = SettingsCache::new();
+    assert!(cache.get("1").is_none());
+    let settings = GuildSettings {
+        locale: Some("de".to_string()),
+        history_logging: false,
+        ..GuildSettings::default()
+    };
+    cache.insert("1", settings.clone());
+    assert_eq!(cache.get("1").as_deref(), Some(&settings));
+    cache.invalidate("1");
+    assert!(cache.get("1").is_none());
+}
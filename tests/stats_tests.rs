@@ -0,0 +1,240 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::privacy::set_opted_out;
+use discordbot::stats::{
+    UsageStats, format_leaderboard_response, format_stats_response, leaderboard, leaderboard_from,
+    sparkline, usage_stats, usage_stats_from,
+};
+use discordbot::store::{HistoryStore, MEMORY_HISTORY_CAPACITY, MemoryHistoryStore, UserCount};
+use discordbot::{HistoryEntry, db_setup};
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
+
+fn now() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2025-03-10T12:00:00Z")
+        .expect("timestamp")
+        .with_timezone(&Utc)
+}
+
+/// Inserts a history row at `timestamp` (RFC 3339)
+fn insert_row(path: &str, timestamp: &str, user: &str, name: &str, guild: &str, command: &str) {
+    let conn = Connection::open(path).expect("open conn");
+    conn.execute(
+        "INSERT INTO command_history (timestamp, user_id, username, command, output, guild_id) VALUES (?1, ?2, ?3, ?4, '', ?5)",
+        rusqlite::params![
+            DateTime::parse_from_rfc3339(timestamp)
+                .expect("timestamp")
+                .with_timezone(&Utc)
+                .to_rfc3339(),
+            user,
+            name,
+            command,
+            guild
+        ],
+    )
+    .expect("insert");
+}
+
+#[tokio::test]
+async fn usage_stats_aggregate_the_guild_window() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_row(path, "2025-03-10T09:15:00Z", "1", "alice", "7", "avatar");
+    insert_row(
+        path,
+        "2025-03-10T09:45:00Z",
+        "1",
+        "alice2",
+        "7",
+        "codename generate",
+    );
+    insert_row(path, "2025-03-09T09:05:00Z", "2", "bob", "7", "avatar");
+    insert_row(path, "2025-03-08T20:00:00Z", "1", "alice", "7", "avatar");
+    // Outside the window, another guild and a denied attempt are not counted
+    insert_row(path, "2025-03-01T09:00:00Z", "2", "bob", "7", "avatar");
+    insert_row(path, "2025-03-10T10:00:00Z", "2", "bob", "8", "avatar");
+    let conn = Connection::open(path).expect("open conn");
+    conn.execute(
+        "INSERT INTO command_history (timestamp, user_id, username, command, output, guild_id, status) VALUES (?1, '2', 'bob', 'perms', '', '7', 'denied')",
+        [now().to_rfc3339()],
+    )
+    .expect("insert denied");
+
+    let stats = usage_stats(path, "7", 3, now()).expect("stats");
+    assert_eq!(stats.total, 4);
+    assert_eq!(
+        stats.top_commands,
+        [
+            ("avatar".to_string(), 3),
+            ("codename generate".to_string(), 1)
+        ]
+    );
+    assert_eq!(
+        stats.top_users,
+        [
+            UserCount {
+                user_id: "1".to_string(),
+                username: "alice2".to_string(),
+                commands: 3
+            },
+            UserCount {
+                user_id: "2".to_string(),
+                username: "bob".to_string(),
+                commands: 1
+            }
+        ]
+    );
+    let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).expect("date");
+    assert_eq!(stats.daily, [(day(8), 1), (day(9), 1), (day(10), 2)]);
+    assert_eq!(stats.busiest_hours, [(9, 3), (20, 1)]);
+}
+
+#[tokio::test]
+async fn leaderboard_leaves_out_opted_out_users() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    for _ in 0..3 {
+        insert_row(path, "2025-03-10T09:00:00Z", "1", "alice", "7", "avatar");
+    }
+    insert_row(path, "2025-03-10T09:00:00Z", "2", "bob", "7", "avatar");
+    insert_row(path, "2025-03-10T09:00:00Z", "3", "carol", "7", "avatar");
+    set_opted_out(path, "1", true).expect("opt out");
+
+    let entries = leaderboard(path, "7", 30, now()).expect("leaderboard");
+    let names: Vec<_> = entries
+        .iter()
+        .map(|entry| entry.username.as_str())
+        .collect();
+    assert_eq!(names, ["bob", "carol"]);
+    // Opted-out users still count towards the totals
+    assert_eq!(usage_stats(path, "7", 30, now()).expect("stats").total, 5);
+}
+
+#[tokio::test]
+async fn stats_read_the_configured_history_store() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    // Opt-outs stay in SQLite whatever the history backend
+    let _ = db_setup(path).await;
+    let memory = MemoryHistoryStore::new(MEMORY_HISTORY_CAPACITY);
+    let ran_at = |timestamp: &str| {
+        DateTime::parse_from_rfc3339(timestamp)
+            .expect("timestamp")
+            .with_timezone(&Utc)
+    };
+    for (timestamp, user, name, command) in [
+        ("2025-03-09T08:00:00Z", "1", "alice", "avatar"),
+        ("2025-03-10T09:00:00Z", "1", "alice2", "avatar"),
+        ("2025-03-10T09:30:00Z", "2", "bob", "codename generate"),
+    ] {
+        let entry = HistoryEntry {
+            guild_id: Some("7".to_string()),
+            ..HistoryEntry::new(user, name, command, "")
+        };
+        memory.insert(&entry, ran_at(timestamp)).expect("insert");
+    }
+
+    let stats = usage_stats_from(&memory, path, "7", 3, now()).expect("stats");
+    assert_eq!(stats.total, 3);
+    assert_eq!(
+        stats.top_commands,
+        [
+            ("avatar".to_string(), 2),
+            ("codename generate".to_string(), 1)
+        ]
+    );
+    assert_eq!(stats.busiest_hours, [(9, 2), (8, 1)]);
+    let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).expect("date");
+    assert_eq!(stats.daily, [(day(8), 0), (day(9), 1), (day(10), 2)]);
+
+    let entries = leaderboard_from(&memory, path, "7", 30, now()).expect("leaderboard");
+    assert_eq!(
+        entries,
+        [
+            UserCount {
+                user_id: "1".to_string(),
+                username: "alice2".to_string(),
+                commands: 2,
+            },
+            UserCount {
+                user_id: "2".to_string(),
+                username: "bob".to_string(),
+                commands: 1,
+            }
+        ]
+    );
+}
+
+#[test]
+fn sparkline_scales_to_the_largest_count() {
+    assert_eq!(sparkline(&[0, 1, 2, 4]), "▁▃▅█");
+    assert_eq!(sparkline(&[0, 0]), "▁▁");
+    assert_eq!(sparkline(&[]), "");
+}
+
+#[test]
+fn stats_responses_render_fields() {
+    let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).expect("date");
+    let stats = UsageStats {
+        days: 2,
+        total: 3,
+        top_commands: vec![("avatar".to_string(), 3)],
+        top_users: vec![UserCount {
+            user_id: "1".to_string(),
+            username: "alice".to_string(),
+            commands: 3,
+        }],
+        daily: vec![(day(9), 1), (day(10), 2)],
+        busiest_hours: vec![(9, 3)],
+    };
+    let response = format_stats_response(DEFAULT_LOCALE, &stats);
+    assert_eq!(
+        response.title.as_deref(),
+        Some("Usage over the last 2 days")
+    );
+    assert!(response.description.starts_with("3 commands run\n`▅█`"));
+    assert!(
+        response
+            .description
+            .ends_with("Busiest day: 2025-03-10 (2)")
+    );
+    assert_eq!(
+        response.fields,
+        [
+            ("Top commands".to_string(), "1. `/avatar` (3)".to_string()),
+            ("Top users".to_string(), "1. alice (3)".to_string()),
+            (
+                "Busiest hours (UTC)".to_string(),
+                "1. 09:00 (3)".to_string()
+            ),
+        ]
+    );
+
+    let empty = UsageStats {
+        total: 0,
+        top_commands: Vec::new(),
+        top_users: Vec::new(),
+        busiest_hours: Vec::new(),
+        ..stats
+    };
+    let response = format_stats_response(DEFAULT_LOCALE, &empty);
+    assert_eq!(
+        response.description,
+        "No commands were run here in that time"
+    );
+    assert!(response.fields.is_empty());
+
+    let users = [UserCount {
+        user_id: "1".to_string(),
+        username: "alice".to_string(),
+        commands: 1,
+    }];
+    let response = format_leaderboard_response(DEFAULT_LOCALE, 1, &users);
+    assert_eq!(
+        response.title.as_deref(),
+        Some("Most active over the last day")
+    );
+    assert_eq!(response.description, "1. **alice**: 1 command");
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, TimeZone, Utc};
+use discordbot::i18n::DEFAULT_LOCALE;
+use discordbot::userinfo::{
+    MAX_FIELD_LENGTH, UserInfo, format_age, format_roles, snowflake_timestamp, userinfo_response,
+};
+use discordbot::{count_commands_for_user, db_setup, insert_command_history_sync};
+use tempfile::NamedTempFile;
+
+#[test]
+fn snowflake_timestamp_decodes_creation_time() {
+    // Example snowflake from Discord's API reference documentation
+    let created = snowflake_timestamp(175928847299117063);
+    assert_eq!(created.to_rfc3339(), "2016-04-30T11:18:25.796+00:00");
+    assert_eq!(
+        snowflake_timestamp(0),
+        Utc.timestamp_millis_opt(1_420_070_400_000).unwrap()
+    );
+}
+
+#[test]
+fn format_age_spells_out_units() {
+    let from: DateTime<Utc> = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(format_age(DEFAULT_LOCALE, from, from), "less than a day");
+    assert_eq!(
+        format_age(DEFAULT_LOCALE, from, from + chrono::Duration::days(1)),
+        "1 day"
+    );
+    assert_eq!(
+        format_age(
+            DEFAULT_LOCALE,
+            from,
+            from + chrono::Duration::days(365 + 31)
+        ),
+        "1 year, 1 month, 1 day"
+    );
+    assert_eq!(
+        format_age(
+            DEFAULT_LOCALE,
+            from,
+            from + chrono::Duration::days(2 * 365 + 60)
+        ),
+        "2 years, 2 months"
+    );
+}
+
+#[test]
+fn userinfo_response_lists_known_fields() {
+    let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let info = UserInfo {
+        user_id: 175928847299117063,
+        user_name: "alice",
+        avatar_url: "https://cdn.example/a.png",
+        joined_at: Some(Utc.with_ymd_and_hms(2023, 12, 1, 0, 0, 0).unwrap()),
+        roles: vec!["<@&1>".to_string(), "<@&2>".to_string()],
+        commands_run: 12,
+    };
+    let text = userinfo_response(DEFAULT_LOCALE, &info, now).to_text();
+    assert!(text.contains("Account created: 2016-04-30"));
+    assert!(text.contains("Joined server: 2023-12-01 (1 month, 1 day ago)"));
+    assert!(text.contains("Roles: <@&1> <@&2>"));
+    assert!(text.contains("Commands run: 12"));
+
+    let dm_info = UserInfo {
+        joined_at: None,
+        roles: vec![],
+        ..info
+    };
+    let text = userinfo_response(DEFAULT_LOCALE, &dm_info, now).to_text();
+    assert!(!text.contains("Joined server"));
+    assert!(!text.contains("Roles"));
+}
+
+#[test]
+fn long_role_lists_are_cut_to_the_field_limit() {
+    let roles: Vec<String> = (0..300)
+        .map(|i| format!("<@&{}>", 1_000_000_000_000_000_000u64 + i))
+        .collect();
+    let text = format_roles(DEFAULT_LOCALE, &roles);
+    assert!(text.chars().count() <= MAX_FIELD_LENGTH, "{}", text);
+    let shown = text.matches("<@&").count();
+    assert!(shown > 0);
+    assert!(
+        text.ends_with(&format!(" +{} more", roles.len() - shown)),
+        "{}",
+        text
+    );
+    assert!(text.starts_with(&roles[..shown].join(" ")));
+
+    let few = roles[..3].to_vec();
+    assert_eq!(format_roles(DEFAULT_LOCALE, &few), few.join(" "));
+}
+
+#[tokio::test]
+async fn count_commands_for_user_counts_only_that_user() {
+    let tmp = NamedTempFile::new().expect("create temp file");
+    let path = tmp.path().to_str().expect("path to str");
+    let _ = db_setup(path).await;
+    insert_command_history_sync(path, "1", "alice", "codename", "ok").expect("insert");
+    insert_command_history_sync(path, "1", "alice", "avatar", "ok").expect("insert");
+    insert_command_history_sync(path, "2", "bob", "codename", "ok").expect("insert");
+
+    assert_eq!(count_commands_for_user(path, "1").expect("count"), 2);
+    assert_eq!(count_commands_for_user(path, "2").expect("count"), 1);
+    assert_eq!(count_commands_for_user(path, "3").expect("count"), 0);
+}